@@ -0,0 +1,139 @@
+//! Clipboard as a capture source, for apps where selection capture fails
+//! (terminals, Electron). Read on a hot-key or polled in watch mode.
+
+use crate::{source, CaptureEvent, Error, Payload, Result, Source};
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+use tokio::sync::{mpsc::Sender, watch};
+use tracing::{debug, info};
+use utils::config::Config;
+
+/// How often watch mode looks at the clipboard.
+pub const POLL: Duration = Duration::from_millis(500);
+
+/// Cheap identity used by watch mode to spot new copies.
+fn fingerprint(payload: &Payload) -> u64 {
+    let mut h = std::collections::hash_map::DefaultHasher::new();
+    match payload {
+        Payload::Text(text) | Payload::Html(text) | Payload::Url(text) => text.hash(&mut h),
+        Payload::File(path) => path.hash(&mut h),
+        Payload::Image { image, .. } => (image.dimensions(), image.as_raw()).hash(&mut h),
+    }
+    h.finish()
+}
+
+/// Prefer the HTML flavour over plain text unless it has no readable text
+/// (a copied `<img>`); `None` when neither has anything but whitespace.
+pub fn pick_text(html: Option<String>, text: Option<String>) -> Option<Payload> {
+    html.filter(|h| !utils::html::to_text(h).trim().is_empty())
+        .map(Payload::Html)
+        .or_else(|| text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).map(Payload::Text))
+}
+
+/// Read the clipboard now. [`Error::EmptyClipboard`] when it holds neither
+/// text nor an image.
+pub fn read() -> Result<CaptureEvent> {
+    let payload = imp::read_content()?;
+    Ok(CaptureEvent::new(payload, source::active()))
+}
+
+/// Poll the clipboard while `capture.clipboard_watch` is on and send every
+/// new copy. Whatever is already there when watching starts is skipped.
+pub async fn watch(tx: Sender<CaptureEvent>, mut config: watch::Receiver<Config>) -> Result<()> {
+    let mut tick = tokio::time::interval(POLL);
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last: Option<u64> = None;
+    loop {
+        if !config.borrow_and_update().capture.clipboard_watch {
+            last = None;
+            if config.changed().await.is_err() {
+                return Ok(());
+            }
+            continue;
+        }
+        tick.tick().await;
+
+        let payload = match tokio::task::spawn_blocking(imp::read_content).await {
+            Ok(Ok(payload)) => Some(payload),
+            Ok(Err(Error::EmptyClipboard)) => None,
+            Ok(Err(Error::Disabled)) => return Err(Error::Disabled),
+            Ok(Err(e)) => {
+                debug!(error = %e, "clipboard poll failed");
+                continue;
+            }
+            Err(e) => return Err(Error::Clipboard(e.to_string())),
+        };
+        let print = payload.as_ref().map_or(0, fingerprint);
+        let seen = last.replace(print);
+        let Some(payload) = payload.filter(|_| seen.is_some_and(|p| p != print)) else {
+            continue;
+        };
+
+        info!("📋 New clipboard content");
+        let source = tokio::task::spawn_blocking(source::active).await.unwrap_or_else(|_| Source::now());
+        if tx.send(CaptureEvent::new(payload, source)).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(feature = "full")]
+mod imp {
+    use super::*;
+    use arboard::Clipboard;
+
+    fn failed(e: arboard::Error) -> Error {
+        Error::Clipboard(e.to_string())
+    }
+
+    /// `None` for "nothing of this kind on the clipboard", errors otherwise.
+    fn optional<T>(r: std::result::Result<T, arboard::Error>) -> Result<Option<T>> {
+        match r {
+            Ok(v) => Ok(Some(v)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(failed(e)),
+        }
+    }
+
+    pub fn read_content() -> Result<Payload> {
+        let mut clipboard = Clipboard::new().map_err(failed)?;
+        let html = optional(clipboard.get().html())?;
+        let text = optional(clipboard.get_text())?;
+        if let Some(payload) = pick_text(html, text) {
+            return Ok(payload);
+        }
+        let Some(img) = optional(clipboard.get_image())? else {
+            return Err(Error::EmptyClipboard);
+        };
+        image::RgbaImage::from_raw(img.width as u32, img.height as u32, img.bytes.into_owned())
+            .map(|image| Payload::Image { image, region: None })
+            .ok_or_else(|| Error::Clipboard("image data does not match its size".into()))
+    }
+}
+
+#[cfg(not(feature = "full"))]
+mod imp {
+    use super::*;
+
+    pub fn read_content() -> Result<Payload> {
+        Err(Error::Disabled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_html_with_text_over_plain_text() {
+        let pick = |html: Option<&str>, text: &str| pick_text(html.map(String::from), Some(text.to_string()));
+        assert!(matches!(pick(Some("<p>a&amp;b</p>"), "a&b"), Some(Payload::Html(h)) if h == "<p>a&amp;b</p>"));
+        // Image-only HTML (a copied <img>) falls back to the text flavour.
+        assert!(matches!(pick(Some("<img src=x>"), "  alt  "), Some(Payload::Text(t)) if t == "alt"));
+        assert!(pick(None, " \n ").is_none());
+
+        let a = fingerprint(&Payload::Text("x".into()));
+        assert_eq!(a, fingerprint(&Payload::Text("x".into())));
+        assert_ne!(a, fingerprint(&Payload::Text("y".into())));
+    }
+}
@@ -0,0 +1,130 @@
+//! Monitor selection and per-display metadata recorded with each capture.
+
+use crate::region::Rect;
+use utils::config::MonitorChoice;
+
+/// The display a capture was taken from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayMeta {
+    pub id: u32,
+    /// Position and size in global logical coordinates.
+    pub bounds: Rect,
+    /// Physical pixels per logical pixel (2.0 on most HiDPI panels).
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
+impl DisplayMeta {
+    /// Where `rect` (global logical coordinates) lies in a `(width, height)`
+    /// image of this whole display; see [`Rect::pixels_in`]. Each display of a
+    /// mixed-DPI layout maps at its own density whatever the backend reported.
+    pub fn pixel_rect(&self, rect: &Rect, size: (u32, u32)) -> Option<Rect> {
+        rect.pixels_in(&self.bounds, size)
+    }
+
+    /// What a grab of this display keeps: `rect` clipped to it (all of it for
+    /// `None`, or when they do not overlap), in logical coordinates and as
+    /// pixels of a `size` image of the whole display.
+    pub fn clip(&self, rect: Option<Rect>, size: (u32, u32)) -> Option<(Rect, Rect)> {
+        let logical = rect.and_then(|r| r.intersect(&self.bounds)).unwrap_or(self.bounds);
+        Some((logical, self.pixel_rect(&logical, size)?))
+    }
+}
+
+/// Resolve `choice` against the connected displays. Falls back to the
+/// primary, then the first display, when the preferred one is not found.
+pub fn choose(displays: &[DisplayMeta], choice: MonitorChoice, cursor: Option<(i32, i32)>) -> Option<&DisplayMeta> {
+    let under_cursor = || cursor.and_then(|p| displays.iter().find(|d| d.bounds.contains(p)));
+    let picked = match choice {
+        MonitorChoice::Id(id) => displays.iter().find(|d| d.id == id).or_else(under_cursor),
+        MonitorChoice::Cursor => under_cursor(),
+        MonitorChoice::Primary => None,
+    };
+    picked
+        .or_else(|| displays.iter().find(|d| d.is_primary))
+        .or_else(|| displays.first())
+}
+
+/// Bounding box of every display: what a whole-desktop screenshot shows.
+pub fn desktop(displays: &[DisplayMeta]) -> Option<Rect> {
+    displays.iter().map(|d| d.bounds).reduce(|a, b| a.union(&b))
+}
+
+/// Display holding the largest part of `rect`.
+pub fn containing<'a>(displays: &'a [DisplayMeta], rect: &Rect) -> Option<&'a DisplayMeta> {
+    displays
+        .iter()
+        .filter_map(|d| d.bounds.intersect(rect).map(|i| (d, i.w as u64 * i.h as u64)))
+        .max_by_key(|(_, area)| *area)
+        .map(|(d, _)| d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1440p primary at 1x with a 2x laptop panel to its left.
+    fn displays() -> Vec<DisplayMeta> {
+        vec![
+            DisplayMeta { id: 1, bounds: Rect { x: 0, y: 0, w: 2560, h: 1440 }, scale_factor: 1.0, is_primary: true },
+            DisplayMeta { id: 7, bounds: Rect { x: -1512, y: 200, w: 1512, h: 982 }, scale_factor: 2.0, is_primary: false },
+        ]
+    }
+
+    #[test]
+    fn picks_display_under_cursor_or_configured() {
+        let d = displays();
+        assert_eq!(choose(&d, MonitorChoice::Cursor, Some((-100, 500))).unwrap().id, 7);
+        assert_eq!(choose(&d, MonitorChoice::Cursor, None).unwrap().id, 1);
+        assert_eq!(choose(&d, MonitorChoice::Primary, Some((-100, 500))).unwrap().id, 1);
+        assert_eq!(choose(&d, MonitorChoice::Id(7), None).unwrap().id, 7);
+        assert_eq!(choose(&d, MonitorChoice::Id(99), Some((-100, 500))).unwrap().id, 7);
+    }
+
+    #[test]
+    fn region_maps_to_hidpi_pixels() {
+        let d = displays();
+        let sel = Rect { x: -1000, y: 300, w: 400, h: 100 };
+        let display = containing(&d, &sel).unwrap();
+        assert_eq!(display.id, 7);
+        assert_eq!(display.pixel_rect(&sel, (3024, 1964)), Some(Rect { x: 1024, y: 200, w: 800, h: 200 }));
+        // A backend that hands back logical-size pixels still gets the right crop.
+        assert_eq!(display.pixel_rect(&sel, (1512, 982)), Some(Rect { x: 512, y: 100, w: 400, h: 100 }));
+
+        let straddling = Rect { x: -100, y: 1000, w: 300, h: 300 };
+        assert_eq!(display.pixel_rect(&straddling, (3024, 1964)), Some(Rect { x: 2824, y: 1600, w: 200, h: 364 }));
+        assert_eq!(d[0].pixel_rect(&straddling, (2560, 1440)), Some(Rect { x: 0, y: 1000, w: 200, h: 300 }));
+        assert_eq!(d[0].pixel_rect(&sel, (2560, 1440)), None);
+    }
+
+    #[test]
+    fn grabs_keep_negative_origins() {
+        // A 1.5x monitor above and to the left of the primary.
+        let bounds = Rect { x: -1920, y: -400, w: 1920, h: 1080 };
+        let left = DisplayMeta { id: 3, bounds, scale_factor: 1.5, is_primary: false };
+        let sel = Rect { x: -1800, y: -300, w: 600, h: 200 };
+        let (logical, px) = left.clip(Some(sel), (2880, 1620)).unwrap();
+        assert_eq!(logical, sel, "the region reported is the real, signed one");
+        assert_eq!(px, Rect { x: 180, y: 150, w: 900, h: 300 });
+
+        // Spilling onto the primary keeps only this display's part.
+        let spill = Rect { x: -200, y: -50, w: 400, h: 100 };
+        let (logical, px) = left.clip(Some(spill), (2880, 1620)).unwrap();
+        assert_eq!(logical, Rect { x: -200, y: -50, w: 200, h: 100 });
+        assert_eq!(px, Rect { x: 2580, y: 525, w: 300, h: 150 });
+
+        let (logical, px) = left.clip(None, (2880, 1620)).unwrap();
+        assert_eq!((logical, px), (left.bounds, Rect { x: 0, y: 0, w: 2880, h: 1620 }));
+    }
+
+    #[test]
+    fn region_maps_into_desktop_shot() {
+        let d = displays();
+        let all = desktop(&d).unwrap();
+        assert_eq!(all, Rect { x: -1512, y: 0, w: 4072, h: 1440 });
+        // Compositors shoot the whole desktop at the highest scale in use.
+        let sel = Rect { x: -1000, y: 300, w: 400, h: 100 };
+        assert_eq!(sel.pixels_in(&all, (8144, 2880)), Some(Rect { x: 1024, y: 600, w: 800, h: 200 }));
+        assert_eq!(desktop(&[]), None);
+    }
+}
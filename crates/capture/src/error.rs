@@ -0,0 +1,48 @@
+//! Errors returned by the `capture` crate.
+
+use utils::error::Coded;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("capture feature not enabled")]
+    Disabled,
+    #[error("screenshot cancelled by user")]
+    Cancelled,
+    #[error("no display available for capture")]
+    NoDisplay,
+    /// Usually missing screen-recording / accessibility permission.
+    #[error("screen capture failed: {0}")]
+    Failed(String),
+    /// Unparsable or conflicting `[hotkeys]` binding.
+    #[error("{0}")]
+    Hotkey(String),
+    #[error("hot-key listener failed: {0}")]
+    Listener(String),
+    #[error("could not read the clipboard: {0}")]
+    Clipboard(String),
+    #[error("clipboard holds no text or image")]
+    EmptyClipboard,
+    #[error("capture I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("captured image could not be decoded: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Disabled => "capture.disabled",
+            Error::Cancelled => "capture.cancelled",
+            Error::NoDisplay => "capture.no_display",
+            Error::Failed(_) => "capture.failed",
+            Error::Hotkey(_) => "capture.bad_hotkey",
+            Error::Listener(_) => "capture.listener_failed",
+            Error::Clipboard(_) => "capture.clipboard_failed",
+            Error::EmptyClipboard => "capture.clipboard_empty",
+            Error::Io(_) => "capture.io",
+            Error::Image(_) => "capture.bad_image",
+        }
+    }
+}
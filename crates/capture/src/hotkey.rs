@@ -0,0 +1,425 @@
+//! Global hot-key bindings: accelerator strings, per-platform modifier mapping
+//! and conflict detection. Free of input dependencies; the rdev listener and
+//! the Tauri shell both resolve their shortcuts through [`Bindings`].
+
+use crate::{Error, Result};
+use std::fmt;
+use utils::config::HotkeyConfig;
+
+/// Decides what `Cmd` means and which shortcuts the OS already owns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    MacOs,
+    Other,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// Command on macOS, Super/Windows elsewhere.
+    pub meta: bool,
+}
+
+impl Modifiers {
+    fn any_besides_shift(&self) -> bool {
+        self.ctrl || self.alt || self.meta
+    }
+}
+
+/// Physical key of an accelerator, named after the US layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// `A`–`Z` and `0`–`9`, stored upper-case.
+    Char(char),
+    F(u8),
+    Comma,
+    Period,
+    Slash,
+    Semicolon,
+    Quote,
+    Minus,
+    Equal,
+    BracketLeft,
+    BracketRight,
+    Backslash,
+    Backquote,
+    Space,
+    Enter,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+    PrintScreen,
+}
+
+impl Key {
+    fn parse(token: &str) -> Option<Key> {
+        let upper = token.to_ascii_uppercase();
+        if let [c] = upper.as_bytes() {
+            if c.is_ascii_alphanumeric() {
+                return Some(Key::Char(*c as char));
+            }
+        }
+        if let Some(n) = upper.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+            return (1..=24).contains(&n).then_some(Key::F(n));
+        }
+        Some(match upper.as_str() {
+            "," | "COMMA" => Key::Comma,
+            "." | "PERIOD" | "DOT" => Key::Period,
+            "/" | "SLASH" => Key::Slash,
+            ";" | "SEMICOLON" => Key::Semicolon,
+            "'" | "QUOTE" => Key::Quote,
+            "-" | "MINUS" => Key::Minus,
+            "=" | "EQUAL" => Key::Equal,
+            "[" | "BRACKETLEFT" => Key::BracketLeft,
+            "]" | "BRACKETRIGHT" => Key::BracketRight,
+            "\\" | "BACKSLASH" => Key::Backslash,
+            "`" | "BACKQUOTE" => Key::Backquote,
+            "SPACE" => Key::Space,
+            "ENTER" | "RETURN" => Key::Enter,
+            "TAB" => Key::Tab,
+            "ESC" | "ESCAPE" => Key::Escape,
+            "BACKSPACE" => Key::Backspace,
+            "DELETE" | "DEL" => Key::Delete,
+            "INSERT" => Key::Insert,
+            "HOME" => Key::Home,
+            "END" => Key::End,
+            "PAGEUP" => Key::PageUp,
+            "PAGEDOWN" => Key::PageDown,
+            "UP" | "ARROWUP" => Key::Up,
+            "DOWN" | "ARROWDOWN" => Key::Down,
+            "LEFT" | "ARROWLEFT" => Key::Left,
+            "RIGHT" | "ARROWRIGHT" => Key::Right,
+            "PRINTSCREEN" => Key::PrintScreen,
+            _ => return None,
+        })
+    }
+
+    /// Keys that may be bound without a modifier without swallowing typing.
+    fn standalone(&self) -> bool {
+        matches!(self, Key::F(_) | Key::PrintScreen)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Names accepted by Tauri's accelerator parser.
+        let name = match self {
+            Key::Char(c) => return write!(f, "{c}"),
+            Key::F(n) => return write!(f, "F{n}"),
+            Key::Comma => "Comma",
+            Key::Period => "Period",
+            Key::Slash => "Slash",
+            Key::Semicolon => "Semicolon",
+            Key::Quote => "Quote",
+            Key::Minus => "-",
+            Key::Equal => "=",
+            Key::BracketLeft => "BracketLeft",
+            Key::BracketRight => "BracketRight",
+            Key::Backslash => "Backslash",
+            Key::Backquote => "Backquote",
+            Key::Space => "Space",
+            Key::Enter => "Enter",
+            Key::Tab => "Tab",
+            Key::Escape => "Escape",
+            Key::Backspace => "Backspace",
+            Key::Delete => "Delete",
+            Key::Insert => "Insert",
+            Key::Home => "Home",
+            Key::End => "End",
+            Key::PageUp => "PageUp",
+            Key::PageDown => "PageDown",
+            Key::Up => "Up",
+            Key::Down => "Down",
+            Key::Left => "Left",
+            Key::Right => "Right",
+            Key::PrintScreen => "PrintScreen",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A modifier set plus one key, e.g. `CmdOrCtrl+Shift+Comma`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Accelerator {
+    pub mods: Modifiers,
+    pub key: Key,
+}
+
+impl Accelerator {
+    /// Parse `Mod+Mod+Key` (case-insensitive). `Cmd`/`Command` and `CmdOrCtrl`
+    /// mean Command on macOS and Ctrl everywhere else, so one config works on
+    /// every machine; use `Super` for the Windows/Super key.
+    pub fn parse(s: &str, platform: Platform) -> Result<Accelerator> {
+        let bad = |reason: &str| Error::Hotkey(format!("{s:?} {reason}"));
+        let tokens: Vec<&str> = s.split('+').map(str::trim).collect();
+        let (key, mod_tokens) = tokens.split_last().ok_or_else(|| bad("is empty"))?;
+        if key.is_empty() || mod_tokens.iter().any(|t| t.is_empty()) {
+            return Err(bad("is not of the form Mod+Key"));
+        }
+
+        let mut mods = Modifiers::default();
+        for token in mod_tokens {
+            let slot = match token.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut mods.ctrl,
+                "alt" | "option" => &mut mods.alt,
+                "shift" => &mut mods.shift,
+                "super" | "meta" | "win" => &mut mods.meta,
+                "cmd" | "command" | "cmdorctrl" | "cmdorcontrol" | "commandorctrl" | "commandorcontrol" => {
+                    match platform {
+                        Platform::MacOs => &mut mods.meta,
+                        Platform::Other => &mut mods.ctrl,
+                    }
+                }
+                _ => return Err(bad(&format!("has unknown modifier {token:?}"))),
+            };
+            *slot = true;
+        }
+
+        let key = Key::parse(key).ok_or_else(|| bad(&format!("has unknown key {key:?}")))?;
+        if !key.standalone() && !mods.any_besides_shift() {
+            return Err(bad("needs Ctrl, Alt or Cmd/Super, or it fires while typing"));
+        }
+        Ok(Accelerator { mods, key })
+    }
+}
+
+impl fmt::Display for Accelerator {
+    /// Canonical form, also valid input for Tauri's global shortcut manager.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let meta = match Platform::current() {
+            Platform::MacOs => "Cmd",
+            Platform::Other => "Super",
+        };
+        let m = &self.mods;
+        for (on, name) in [(m.meta, meta), (m.ctrl, "Ctrl"), (m.alt, "Alt"), (m.shift, "Shift")] {
+            if on {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Something a global shortcut can trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    CaptureScreen,
+    CaptureSelection,
+    CaptureWindow,
+    CaptureClipboard,
+    StartReview,
+}
+
+impl Action {
+    pub const ALL: [Action; 5] = [
+        Action::CaptureScreen,
+        Action::CaptureSelection,
+        Action::CaptureWindow,
+        Action::CaptureClipboard,
+        Action::StartReview,
+    ];
+
+    /// Field name under `[hotkeys]`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::CaptureScreen => "capture_screen",
+            Action::CaptureSelection => "capture_selection",
+            Action::CaptureWindow => "capture_window",
+            Action::CaptureClipboard => "capture_clipboard",
+            Action::StartReview => "start_review",
+        }
+    }
+
+    /// Configured accelerator string; empty means unbound.
+    pub fn binding<'a>(&self, cfg: &'a HotkeyConfig) -> &'a str {
+        match self {
+            Action::CaptureScreen => &cfg.capture_screen,
+            Action::CaptureSelection => &cfg.capture_selection,
+            Action::CaptureWindow => &cfg.capture_window,
+            Action::CaptureClipboard => &cfg.capture_clipboard,
+            Action::StartReview => &cfg.start_review,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Shortcuts the OS or nearly every application already uses.
+fn reserved(platform: Platform) -> &'static [(&'static str, &'static str)] {
+    match platform {
+        Platform::MacOs => &[
+            ("Cmd+Shift+3", "the macOS screenshot shortcut"),
+            ("Cmd+Shift+4", "the macOS screenshot shortcut"),
+            ("Cmd+Shift+5", "the macOS screenshot shortcut"),
+            ("Cmd+Space", "Spotlight"),
+            ("Cmd+Tab", "the app switcher"),
+            ("Cmd+Q", "Quit"),
+            ("Cmd+W", "Close Window"),
+            ("Cmd+C", "Copy"),
+            ("Cmd+V", "Paste"),
+            ("Cmd+X", "Cut"),
+        ],
+        Platform::Other => &[
+            ("Ctrl+C", "Copy"),
+            ("Ctrl+V", "Paste"),
+            ("Ctrl+X", "Cut"),
+            ("Ctrl+Shift+C", "Copy in terminals"),
+            ("Ctrl+Shift+V", "Paste in terminals"),
+            ("Alt+Tab", "the window switcher"),
+            ("Alt+F4", "Close Window"),
+            ("Ctrl+Alt+Delete", "the session menu"),
+            ("Ctrl+Alt+T", "the terminal launcher"),
+            ("Super+L", "the screen lock"),
+        ],
+    }
+}
+
+/// Accelerator → action table with conflict detection.
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    platform: Platform,
+    entries: Vec<(Accelerator, Action)>,
+}
+
+impl Bindings {
+    pub fn new(platform: Platform) -> Self {
+        Self { platform, entries: Vec::new() }
+    }
+
+    /// Bind every non-empty `[hotkeys]` entry; reports every problem at once,
+    /// naming the offending keys.
+    pub fn from_config(cfg: &HotkeyConfig, platform: Platform) -> Result<Self> {
+        let mut bindings = Self::new(platform);
+        let mut errs = Vec::new();
+        for action in Action::ALL {
+            let raw = action.binding(cfg).trim();
+            if raw.is_empty() {
+                continue;
+            }
+            let bound = Accelerator::parse(raw, platform).and_then(|accel| bindings.bind(action, accel));
+            match bound {
+                Ok(()) => {}
+                Err(Error::Hotkey(msg)) => errs.push(format!("hotkeys.{action}: {msg}")),
+                Err(e) => errs.push(format!("hotkeys.{action}: {e}")),
+            }
+        }
+        if !errs.is_empty() {
+            return Err(Error::Hotkey(format!("invalid hot-keys:\n  - {}", errs.join("\n  - "))));
+        }
+        Ok(bindings)
+    }
+
+    /// Register `accel` for `action`, replacing the action's previous binding.
+    /// Fails when another action or the OS already uses the accelerator.
+    pub fn bind(&mut self, action: Action, accel: Accelerator) -> Result<()> {
+        if let Some((_, other)) = self.entries.iter().find(|(a, other)| *a == accel && *other != action) {
+            return Err(Error::Hotkey(format!("{accel} is already bound to {other}")));
+        }
+        let platform = self.platform;
+        let taken = reserved(platform)
+            .iter()
+            .find(|(s, _)| Accelerator::parse(s, platform).is_ok_and(|r| r == accel));
+        if let Some((_, owner)) = taken {
+            return Err(Error::Hotkey(format!("{accel} is reserved for {owner}")));
+        }
+        self.entries.retain(|(_, a)| *a != action);
+        self.entries.push((accel, action));
+        Ok(())
+    }
+
+    pub fn action_for(&self, accel: &Accelerator) -> Option<Action> {
+        self.entries.iter().find(|(a, _)| a == accel).map(|(_, action)| *action)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Accelerator, Action)> + '_ {
+        self.entries.iter().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Accelerator {
+        Accelerator::parse(s, Platform::Other).unwrap()
+    }
+
+    #[test]
+    fn cmd_maps_to_ctrl_off_macos() {
+        let a = parse("Cmd+Shift+Comma");
+        assert_eq!(a, parse("ctrl+shift+,"));
+        assert_eq!(a, parse("CmdOrCtrl+Shift+Comma"));
+        assert!(a.mods.ctrl && !a.mods.meta);
+        let mac = Accelerator::parse("Cmd+Shift+Comma", Platform::MacOs).unwrap();
+        assert!(mac.mods.meta && !mac.mods.ctrl);
+        assert_eq!(parse("alt+ctrl+f5").to_string(), "Ctrl+Alt+F5");
+        assert_eq!(parse("ctrl+shift+.").to_string(), "Ctrl+Shift+Period");
+    }
+
+    #[test]
+    fn rejects_malformed_accelerators() {
+        for s in ["", "Ctrl+", "Ctrl++K", "Hyper+K", "Ctrl+Banana", "Shift+K", "K", "F25"] {
+            assert!(Accelerator::parse(s, Platform::Other).is_err(), "{s:?} should not parse");
+        }
+        assert_eq!(parse("F9").key, Key::F(9));
+    }
+
+    #[test]
+    fn detects_conflicts() {
+        let cfg = HotkeyConfig {
+            capture_screen: "Ctrl+Shift+Comma".into(),
+            capture_window: "Cmd+Shift+Comma".into(),
+            start_review: "Ctrl+C".into(),
+            ..HotkeyConfig::default()
+        };
+        let msg = Bindings::from_config(&cfg, Platform::Other).unwrap_err().to_string();
+        assert!(msg.contains("hotkeys.capture_window: Ctrl+Shift+Comma is already bound to capture_screen"), "{msg}");
+        assert!(msg.contains("hotkeys.start_review: Ctrl+C is reserved for Copy"), "{msg}");
+
+        // The same config is fine on macOS, where Cmd and Ctrl differ.
+        let mac = Bindings::from_config(&HotkeyConfig { start_review: String::new(), ..cfg }, Platform::MacOs).unwrap();
+        let screen = Accelerator::parse("Ctrl+Shift+Comma", Platform::MacOs).unwrap();
+        assert_eq!(mac.action_for(&screen), Some(Action::CaptureScreen));
+    }
+
+    #[test]
+    fn rebinding_an_action_frees_its_old_accelerator() {
+        let mut b = Bindings::from_config(&HotkeyConfig::default(), Platform::Other).unwrap();
+        let old = parse("Ctrl+Shift+Comma");
+        assert_eq!(b.action_for(&old), Some(Action::CaptureScreen));
+        b.bind(Action::CaptureScreen, parse("Ctrl+Alt+S")).unwrap();
+        assert_eq!(b.action_for(&old), None);
+        b.bind(Action::StartReview, old).unwrap();
+        assert_eq!(b.action_for(&old), Some(Action::StartReview));
+    }
+}
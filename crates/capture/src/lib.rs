@@ -2,188 +2,407 @@
 //! global hot-key listener and region capture; otherwise, exposes no-op impls
 //! for unit testing.
 
-/// A simple wrapper around image::RgbaImage that doesn't need to be serialized
+pub mod clipboard;
+pub mod display;
+mod error;
+pub mod hotkey;
+#[cfg(all(feature = "full", target_os = "linux"))]
+mod linux;
+pub mod phash;
+pub mod region;
+#[cfg(feature = "full")]
+mod screen;
+pub mod source;
+
+pub use display::DisplayMeta;
+pub use error::{Error, Result};
+pub use region::Rect;
+pub use source::Source;
+use std::path::PathBuf;
+
+/// What a capture produced. Every entry point (hot-keys, clipboard, IPC,
+/// HTTP ingest) wraps its input in one of these.
+#[derive(Debug, Clone)]
+pub enum Payload {
+    /// Screen pixels; `region` is where they were in global logical
+    /// coordinates (negative left of or above the primary display), or `None`
+    /// when the source does not say.
+    Image { image: image::RgbaImage, region: Option<Rect> },
+    Text(String),
+    /// Rich text as copied; converted to plain text before generation.
+    Html(String),
+    /// An image, text, Markdown or HTML file on disk.
+    File(PathBuf),
+    /// A web page to fetch; only its article text is used.
+    Url(String),
+}
+
+/// A capture plus the metadata every payload shares.
 #[derive(Debug, Clone)]
 pub struct CaptureEvent {
-    pub image: image::RgbaImage,
-    pub region: (u32, u32, u32, u32), // x,y,w,h
-    pub path: Option<String>,         // optional disk location
+    pub payload: Payload,
+    /// Display the pixels came from; `None` for non-screen captures or when
+    /// the capture tool does not say.
+    pub display: Option<DisplayMeta>,
+    /// Application and window in front when the capture started.
+    pub source: Source,
+    /// Deck to file the resulting card into; `None` leaves it unfiled.
+    pub deck: Option<String>,
+}
+
+impl CaptureEvent {
+    pub fn new(payload: Payload, source: Source) -> Self {
+        Self { payload, display: None, source, deck: None }
+    }
+
+    /// Screen capture stamped now; callers fill in `source` when they probed it.
+    pub fn image(image: image::RgbaImage, region: Option<Rect>, display: Option<DisplayMeta>) -> Self {
+        Self { payload: Payload::Image { image, region }, display, source: Source::now(), deck: None }
+    }
+
+    /// Perceptual hash of a screen capture (see [`phash`]); `None` for other payloads.
+    pub fn phash(&self) -> Option<u64> {
+        match &self.payload {
+            Payload::Image { image, .. } => Some(phash::phash(image)),
+            _ => None,
+        }
+    }
+
+    /// Short payload name for logs.
+    pub fn kind(&self) -> &'static str {
+        match self.payload {
+            Payload::Image { .. } => "image",
+            Payload::Text(_) => "text",
+            Payload::Html(_) => "html",
+            Payload::File(_) => "file",
+            Payload::Url(_) => "url",
+        }
+    }
 }
 
 #[cfg(feature = "full")]
 mod imp {
     use super::*;
-    use tokio::sync::mpsc::Sender;
+    #[cfg(not(target_os = "linux"))]
+    use crate::display;
+    use tokio::sync::{mpsc::Sender, watch};
+    use crate::hotkey::{self, Accelerator, Action, Bindings, Modifiers, Platform};
     use tracing::{info, error};
     use rdev::{listen, EventType, Key};
-    use screenshots::Screen;
-    use std::sync::{Arc, Mutex};
-    use anyhow::{Result, anyhow};
-    use std::process::Command;
-    use tempfile::NamedTempFile;
-    use std::path::PathBuf;
-    use std::fs;
+    use std::sync::{Arc, RwLock};
+    use utils::config::{CaptureConfig, Config};
+    #[cfg(target_os = "macos")]
+    use std::{fs, path::PathBuf, process::Command};
 
-    /// Simple modifier state tracker
-    #[derive(Default)]
-    struct ModState {
-        meta: bool,   // Command / Meta
-        shift: bool,
+    /// Map an rdev key to its accelerator key; modifiers and unbindable keys are `None`.
+    fn accel_key(k: Key) -> Option<hotkey::Key> {
+        use hotkey::Key as K;
+        let letter = |c: char| Some(K::Char(c));
+        match k {
+            Key::KeyA => letter('A'),
+            Key::KeyB => letter('B'),
+            Key::KeyC => letter('C'),
+            Key::KeyD => letter('D'),
+            Key::KeyE => letter('E'),
+            Key::KeyF => letter('F'),
+            Key::KeyG => letter('G'),
+            Key::KeyH => letter('H'),
+            Key::KeyI => letter('I'),
+            Key::KeyJ => letter('J'),
+            Key::KeyK => letter('K'),
+            Key::KeyL => letter('L'),
+            Key::KeyM => letter('M'),
+            Key::KeyN => letter('N'),
+            Key::KeyO => letter('O'),
+            Key::KeyP => letter('P'),
+            Key::KeyQ => letter('Q'),
+            Key::KeyR => letter('R'),
+            Key::KeyS => letter('S'),
+            Key::KeyT => letter('T'),
+            Key::KeyU => letter('U'),
+            Key::KeyV => letter('V'),
+            Key::KeyW => letter('W'),
+            Key::KeyX => letter('X'),
+            Key::KeyY => letter('Y'),
+            Key::KeyZ => letter('Z'),
+            Key::Num0 => letter('0'),
+            Key::Num1 => letter('1'),
+            Key::Num2 => letter('2'),
+            Key::Num3 => letter('3'),
+            Key::Num4 => letter('4'),
+            Key::Num5 => letter('5'),
+            Key::Num6 => letter('6'),
+            Key::Num7 => letter('7'),
+            Key::Num8 => letter('8'),
+            Key::Num9 => letter('9'),
+            Key::F1 => Some(K::F(1)),
+            Key::F2 => Some(K::F(2)),
+            Key::F3 => Some(K::F(3)),
+            Key::F4 => Some(K::F(4)),
+            Key::F5 => Some(K::F(5)),
+            Key::F6 => Some(K::F(6)),
+            Key::F7 => Some(K::F(7)),
+            Key::F8 => Some(K::F(8)),
+            Key::F9 => Some(K::F(9)),
+            Key::F10 => Some(K::F(10)),
+            Key::F11 => Some(K::F(11)),
+            Key::F12 => Some(K::F(12)),
+            Key::Comma => Some(K::Comma),
+            Key::Dot => Some(K::Period),
+            Key::Slash => Some(K::Slash),
+            Key::SemiColon => Some(K::Semicolon),
+            Key::Quote => Some(K::Quote),
+            Key::Minus => Some(K::Minus),
+            Key::Equal => Some(K::Equal),
+            Key::LeftBracket => Some(K::BracketLeft),
+            Key::RightBracket => Some(K::BracketRight),
+            Key::BackSlash => Some(K::Backslash),
+            Key::BackQuote => Some(K::Backquote),
+            Key::Space => Some(K::Space),
+            Key::Return => Some(K::Enter),
+            Key::Tab => Some(K::Tab),
+            Key::Escape => Some(K::Escape),
+            Key::Backspace => Some(K::Backspace),
+            Key::Delete => Some(K::Delete),
+            Key::Insert => Some(K::Insert),
+            Key::Home => Some(K::Home),
+            Key::End => Some(K::End),
+            Key::PageUp => Some(K::PageUp),
+            Key::PageDown => Some(K::PageDown),
+            Key::UpArrow => Some(K::Up),
+            Key::DownArrow => Some(K::Down),
+            Key::LeftArrow => Some(K::Left),
+            Key::RightArrow => Some(K::Right),
+            Key::PrintScreen => Some(K::PrintScreen),
+            _ => None,
+        }
     }
 
-    /// Listen for hot-key (Cmd+Shift+<) and push capture events.
-    pub async fn listen_and_capture(tx: Sender<CaptureEvent>) -> Result<()> {
-        info!("🎯 Capture listener enabled, waiting for ⌘⇧<");
+    /// Track modifier state; `true` when `k` was a modifier.
+    fn update_mods(mods: &mut Modifiers, k: Key, down: bool) -> bool {
+        let slot = match k {
+            Key::ControlLeft | Key::ControlRight => &mut mods.ctrl,
+            Key::Alt | Key::AltGr => &mut mods.alt,
+            Key::ShiftLeft | Key::ShiftRight => &mut mods.shift,
+            Key::MetaLeft | Key::MetaRight => &mut mods.meta,
+            _ => return false,
+        };
+        *slot = down;
+        true
+    }
+
+    /// Listen for the `[hotkeys]` bindings and send the triggered actions.
+    /// Bindings follow config reloads; an invalid edit keeps the previous set.
+    pub async fn listen_hotkeys(tx: Sender<Action>, mut config: watch::Receiver<Config>) -> Result<()> {
+        let mut current = config.borrow().hotkeys.clone();
+        let bindings = Arc::new(RwLock::new(Bindings::from_config(&current, Platform::current())?));
+        for (accel, action) in bindings.read().map(|b| b.iter().collect::<Vec<_>>()).unwrap_or_default() {
+            info!("🎯 Hot-key {accel} → {action}");
+        }
 
         // Channel to bridge between blocking hotkey thread and async world
-        let (evt_tx, mut evt_rx) = tokio::sync::mpsc::channel::<()>(4);
+        let (evt_tx, mut evt_rx) = tokio::sync::mpsc::channel::<Action>(4);
 
         // Spawn blocking thread for rdev::listen (this call blocks)
+        let table = bindings.clone();
         std::thread::spawn(move || {
-            let state = Arc::new(Mutex::new(ModState::default()));
-            let state_clone = state.clone();
-            if let Err(e) = listen(move |event| {
-                let mut st = state_clone.lock().unwrap();
-                match event.event_type {
-                    EventType::KeyPress(k) => {
-                        info!("👆 Key press: {:?}", k);
-                        match k {
-                            Key::MetaLeft | Key::MetaRight => st.meta = true,
-                            Key::ShiftLeft | Key::ShiftRight => st.shift = true,
-                            Key::Comma => {
-                                // '<' requires Shift on comma key
-                                if st.meta && st.shift {
-                                    info!("🔑 Hotkey detected: Command+Shift+<");
-                                    let _ = evt_tx.blocking_send(());
-                                }
-                            }
-                            _ => {}
-                        }
-                    },
-                    EventType::KeyRelease(k) => {
-                        info!("👇 Key release: {:?}", k);
-                        match k {
-                            Key::MetaLeft | Key::MetaRight => st.meta = false,
-                            Key::ShiftLeft | Key::ShiftRight => st.shift = false,
-                            _ => {}
-                        }
-                    },
-                    _ => {}
+            let mut mods = Modifiers::default();
+            if let Err(e) = listen(move |event| match event.event_type {
+                EventType::KeyPress(k) => {
+                    if update_mods(&mut mods, k, true) {
+                        return;
+                    }
+                    let Some(key) = accel_key(k) else { return };
+                    let accel = Accelerator { mods, key };
+                    let action = table.read().ok().and_then(|b| b.action_for(&accel));
+                    if let Some(action) = action {
+                        info!("🔑 Hot-key {accel} → {action}");
+                        let _ = evt_tx.blocking_send(action);
+                    }
+                }
+                EventType::KeyRelease(k) => {
+                    update_mods(&mut mods, k, false);
                 }
+                EventType::MouseMove { x, y } => crate::screen::record_pointer(x, y),
+                _ => {}
             }) {
-                error!(?e, "rdev listen error");
+                error!(error = %Error::Listener(format!("{e:?}")), "rdev listen error");
             }
         });
 
-        // Async loop waiting for trigger events
-        while evt_rx.recv().await.is_some() {
-            match capture_screen() {
-                Ok(capture_event) => {
-                    info!("📸 Captured screen region: {}x{}", capture_event.region.2, capture_event.region.3);
-                    if tx.send(capture_event).await.is_err() {
-                        error!("Main receiver dropped; stopping capture listener");
+        loop {
+            tokio::select! {
+                Some(action) = evt_rx.recv() => {
+                    if tx.send(action).await.is_err() {
+                        error!("Main receiver dropped; stopping hot-key listener");
                         break;
                     }
                 }
-                Err(e) => {
-                    error!(?e, "failed to capture screen");
+                changed = config.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let next = config.borrow().hotkeys.clone();
+                    if next == current {
+                        continue;
+                    }
+                    match Bindings::from_config(&next, Platform::current()) {
+                        Ok(b) => {
+                            if let Ok(mut table) = bindings.write() {
+                                *table = b;
+                            }
+                            info!("🔧 Hot-keys rebound");
+                        }
+                        Err(e) => error!("{e}; keeping previous hot-keys"),
+                    }
+                    current = next;
                 }
+                else => break,
             }
         }
 
         Ok(())
     }
 
-    /// Captures the screen (interactive region grab on macOS, full screen fallback elsewhere).
+    /// Captures the screen (interactive region grab on macOS and Linux, full screen fallback elsewhere).
     /// Exposed publicly so other binaries (e.g. Tauri) can reuse without going through
     /// the hot-key listener.
-    pub fn capture_screen() -> Result<CaptureEvent> {
+    pub fn capture_screen_with(cfg: &CaptureConfig) -> Result<CaptureEvent> {
+        // Probe before the selection UI can take focus.
+        let source = crate::source::active();
+        Ok(CaptureEvent { source, ..region(cfg)? })
+    }
+
+    fn region(cfg: &CaptureConfig) -> Result<CaptureEvent> {
         #[cfg(target_os = "macos")]
         {
-            info!("Launching interactive screencapture utility (-i)");
-            // Create a temporary PNG file with proper .png suffix – screencapture requires an extension
-            let tmp = tempfile::Builder::new()
-                .prefix("oakley_capture_")
-                .suffix(".png")
-                .tempfile()?;
-            let path: PathBuf = tmp.path().into();
-
-            // Run macOS screencapture with interactive selection (-i) and no sounds (-x)
-            // -t png ensures PNG output
-            let status = Command::new("screencapture")
-                .args(["-x", "-i", "-t", "png", path.to_str().unwrap()])
-                .status()?;
-
-            if !status.success() {
-                return Err(anyhow!("screencapture exited with status {status}"));
-            }
+            let _ = cfg; // screencapture -i lets the user pick the display
+            screencapture(&["-i"])
+        }
 
-            // If user cancelled, the file might be zero bytes
-            let metadata = fs::metadata(&path)?;
-            if metadata.len() == 0 {
-                return Err(anyhow!("screenshot cancelled by user"));
-            }
+        #[cfg(target_os = "linux")]
+        {
+            crate::linux::capture_region(cfg)
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+        {
+            info!("Attempting to capture full screen (fallback)");
+            full_display(cfg)
+        }
+    }
 
-            let bytes = fs::read(&path)?;
-            let dyn_img = image::load_from_memory(&bytes)?;
-            let rgba = dyn_img.to_rgba8();
-            let (w, h) = rgba.dimensions();
+    /// [`capture_screen_with`] using the default display choice.
+    pub fn capture_screen() -> Result<CaptureEvent> {
+        capture_screen_with(&CaptureConfig::default())
+    }
 
-            info!("Interactive capture successful: {}x{}", w, h);
+    /// Captures the focused window (X11), or lets the user click one (macOS,
+    /// Wayland portal). Falls back to the configured display elsewhere.
+    pub fn capture_window_with(cfg: &CaptureConfig) -> Result<CaptureEvent> {
+        let source = crate::source::active();
+        Ok(CaptureEvent { source, ..window(cfg)? })
+    }
 
-            return Ok(CaptureEvent {
-                image: rgba,
-                region: (0, 0, w, h), // selection region relative not known – default to full img size
-                path: Some(path.to_string_lossy().to_string()),
-            });
+    fn window(cfg: &CaptureConfig) -> Result<CaptureEvent> {
+        #[cfg(target_os = "macos")]
+        {
+            let _ = cfg;
+            screencapture(&["-i", "-W"])
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            crate::linux::capture_window(cfg)
         }
 
-        #[cfg(not(target_os = "macos"))]
+        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
         {
-            info!("Attempting to capture full screen (non-macOS fallback)");
-            // Capture the screen where cursor is, else first screen.
-            let screen = Screen::all()
-                .map_err(|e| anyhow!("screenshot list failed: {e}"))?
-                .into_iter()
-                .next()
-                .ok_or_else(|| anyhow!("empty screen list"))?;
-            info!("Detected screen: {}x{} at ({},{})", 
-                  screen.display_info.width, screen.display_info.height,
-                  screen.display_info.x, screen.display_info.y);
-            let img = screen
-                .capture()
-                .map_err(|e| anyhow!("capture failed: {e}"))?;
-            let (w, h) = (img.width(), img.height());
-            info!("Captured image: {}x{}", w, h);
-            let rgba = image::RgbaImage::from_raw(w, h, img.rgba().clone())
-                .ok_or_else(|| anyhow!("buffer size mismatch"))?;
-            info!("Converted to RGBA successfully");
-
-            Ok(CaptureEvent {
-                image: rgba,
-                region: (0, 0, w as u32, h as u32),
-                path: None,
-            })
+            info!("Window capture unsupported here; capturing the display");
+            full_display(cfg)
         }
     }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    fn full_display(cfg: &CaptureConfig) -> Result<CaptureEvent> {
+        let displays = crate::screen::displays()?;
+        let metas: Vec<_> = displays.iter().map(|(_, m)| *m).collect();
+        let chosen = display::choose(&metas, cfg.monitor, crate::screen::cursor_position())
+            .ok_or(Error::NoDisplay)?;
+        let (screen, meta) = displays.iter().find(|(_, m)| m.id == chosen.id).ok_or(Error::NoDisplay)?;
+        crate::screen::grab(screen, meta, None)
+    }
+
+    /// Run macOS `screencapture` interactively with `mode` flags (`-i`, `-i -W`).
+    #[cfg(target_os = "macos")]
+    fn screencapture(mode: &[&str]) -> Result<CaptureEvent> {
+        info!("Launching interactive screencapture utility ({})", mode.join(" "));
+        // Create a temporary PNG file with proper .png suffix – screencapture requires an extension
+        let tmp = tempfile::Builder::new()
+            .prefix("oakley_capture_")
+            .suffix(".png")
+            .tempfile()?;
+        let path: PathBuf = tmp.path().into();
+
+        // No sounds (-x); -t png ensures PNG output
+        let status = Command::new("screencapture")
+            .arg("-x")
+            .args(mode)
+            .args(["-t", "png", path.to_str().unwrap()])
+            .status()?;
+
+        if !status.success() {
+            return Err(Error::Failed(format!("screencapture exited with status {status}")));
+        }
+
+        // If user cancelled, the file might be zero bytes
+        let metadata = fs::metadata(&path)?;
+        if metadata.len() == 0 {
+            return Err(Error::Cancelled);
+        }
+
+        let bytes = fs::read(&path)?;
+        let dyn_img = image::load_from_memory(&bytes)?;
+        let rgba = dyn_img.to_rgba8();
+        let (w, h) = rgba.dimensions();
+
+        info!("Interactive capture successful: {}x{}", w, h);
+
+        // The selection ends under the pointer, so that is the display it came from.
+        let displays = crate::screen::display_metas();
+        let cursor = crate::screen::cursor_position();
+        let display = display::choose(&displays, utils::config::MonitorChoice::Cursor, cursor).copied();
+
+        // screencapture does not say where the selection was.
+        Ok(CaptureEvent::image(rgba, None, display))
+    }
 }
 
 #[cfg(not(feature = "full"))]
 mod imp {
     use super::*;
-    use tokio::sync::mpsc::Sender;
-    use anyhow::Result;
+    use crate::hotkey::Action;
+    use tokio::sync::{mpsc::Sender, watch};
+    use utils::config::{CaptureConfig, Config};
 
-    pub async fn listen_and_capture(_tx: Sender<CaptureEvent>) -> Result<()> {
+    pub async fn listen_hotkeys(_tx: Sender<Action>, _config: watch::Receiver<Config>) -> Result<()> {
         // No-op in stub builds.
         Ok(())
     }
 
     /// No-op capture, returns error when full feature is disabled.
+    pub fn capture_screen_with(_cfg: &CaptureConfig) -> Result<CaptureEvent> {
+        Err(Error::Disabled)
+    }
+
     pub fn capture_screen() -> Result<CaptureEvent> {
-        anyhow::bail!("capture feature not enabled");
+        Err(Error::Disabled)
+    }
+
+    pub fn capture_window_with(_cfg: &CaptureConfig) -> Result<CaptureEvent> {
+        Err(Error::Disabled)
     }
 }
 
-pub use imp::listen_and_capture;
-pub use imp::capture_screen; // re-export for external callers 
\ No newline at end of file
+pub use imp::listen_hotkeys;
+pub use imp::{capture_screen, capture_screen_with, capture_window_with}; // re-export for external callers 
\ No newline at end of file
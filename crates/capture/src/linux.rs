@@ -0,0 +1,339 @@
+//! Interactive region capture on Linux: a rubber-band selection on an overlay
+//! window on X11, and on Wayland `slurp` + `grim` (wlroots) or the
+//! xdg-desktop-portal Screenshot API.
+
+use crate::display;
+use crate::region::{detect_session, Rect, Session};
+use crate::screen;
+use crate::{CaptureEvent, Error, Result};
+use std::process::{Command, Stdio};
+use tracing::info;
+use utils::config::CaptureConfig;
+
+/// Let the user pick a region and capture it. Blocks until the selection ends;
+/// call from a blocking thread, never from inside an async task.
+pub fn capture_region(cfg: &CaptureConfig) -> Result<CaptureEvent> {
+    match detect_session(|k| std::env::var(k).ok()) {
+        Session::X11 => {
+            let rect = x11::select()?;
+            grab(rect, cfg)
+        }
+        Session::Wayland if has_command("slurp") && has_command("grim") => wlroots::capture(),
+        Session::Wayland => portal::capture(),
+        Session::Unknown => Err(Error::NoDisplay),
+    }
+}
+
+/// Capture the focused window on X11. Wayland exposes no focused-window
+/// query, so the user picks the area there instead.
+pub fn capture_window(cfg: &CaptureConfig) -> Result<CaptureEvent> {
+    match detect_session(|k| std::env::var(k).ok()) {
+        Session::X11 => {
+            let rect = x11::active_window()?;
+            grab(Some(rect), cfg)
+        }
+        Session::Wayland => capture_region(cfg),
+        Session::Unknown => Err(Error::NoDisplay),
+    }
+}
+
+/// Application (WM_CLASS) and title of the focused window on X11.
+pub fn active_window_info() -> Option<(Option<String>, Option<String>)> {
+    match detect_session(|k| std::env::var(k).ok()) {
+        Session::X11 => x11::active_info(),
+        _ => None,
+    }
+}
+
+/// Pointer position in root coordinates on X11.
+pub fn pointer() -> Option<(i32, i32)> {
+    match detect_session(|k| std::env::var(k).ok()) {
+        Session::X11 => x11::pointer(),
+        _ => None,
+    }
+}
+
+/// Capture `rect` from the display holding most of it; a click without a drag
+/// (`None`) captures the whole configured display (by default the one under the pointer).
+fn grab(rect: Option<Rect>, cfg: &CaptureConfig) -> Result<CaptureEvent> {
+    let displays = screen::displays()?;
+    let metas: Vec<_> = displays.iter().map(|(_, m)| *m).collect();
+    let chosen = match rect {
+        Some(r) => display::containing(&metas, &r),
+        None => display::choose(&metas, cfg.monitor, screen::cursor_position()),
+    }
+    .ok_or(Error::NoDisplay)?;
+    let (scr, meta) = displays.iter().find(|(_, m)| m.id == chosen.id).ok_or(Error::NoDisplay)?;
+    screen::grab(scr, meta, rect)
+}
+
+fn has_command(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+}
+
+mod x11 {
+    use super::*;
+    use x11rb::connection::Connection;
+    use x11rb::errors::{ReplyError, ReplyOrIdError};
+    use x11rb::protocol::shape::{ConnectionExt as _, SK, SO};
+    use x11rb::protocol::xproto::*;
+    use x11rb::protocol::Event;
+    use x11rb::{CURRENT_TIME, NONE};
+
+    /// Glyph index of `XC_crosshair` in the standard cursor font.
+    const XC_CROSSHAIR: u16 = 34;
+
+    /// Width of the selection outline, in pixels.
+    const BORDER: u16 = 2;
+
+    /// Rubber-band selection on an overlay window. `Ok(None)` for a plain click.
+    pub fn select() -> Result<Option<Rect>> {
+        let (conn, screen_num) = x11rb::connect(None).map_err(|e| Error::Failed(format!("X11: {e}")))?;
+        select_on(&conn, screen_num).map_err(|e| Error::Failed(format!("X11: {e}")))?
+    }
+
+    /// Current pointer position in root coordinates.
+    pub fn pointer() -> Option<(i32, i32)> {
+        let (conn, screen_num) = x11rb::connect(None).ok()?;
+        let root = conn.setup().roots[screen_num].root;
+        let reply = conn.query_pointer(root).ok()?.reply().ok()?;
+        Some((reply.root_x as i32, reply.root_y as i32))
+    }
+
+    /// Root-relative bounds of `_NET_ACTIVE_WINDOW`.
+    pub fn active_window() -> Result<Rect> {
+        let (conn, screen_num) = x11rb::connect(None).map_err(|e| Error::Failed(format!("X11: {e}")))?;
+        active_on(&conn, screen_num)
+            .map_err(|e| Error::Failed(format!("X11: {e}")))?
+            .ok_or_else(|| Error::Failed("no focused window".into()))
+    }
+
+    /// Application (WM_CLASS class) and title (`_NET_WM_NAME`, else WM_NAME).
+    pub fn active_info() -> Option<(Option<String>, Option<String>)> {
+        let (conn, screen_num) = x11rb::connect(None).ok()?;
+        let win = active_id(&conn, screen_num).ok()??;
+        let text = |prop: Atom, ty: Atom| -> Option<String> {
+            let reply = conn.get_property(false, win, prop, ty, 0, 1024).ok()?.reply().ok()?;
+            let s = String::from_utf8_lossy(&reply.value).into_owned();
+            (!s.is_empty()).then_some(s)
+        };
+        let atom = |name: &[u8]| conn.intern_atom(false, name).ok()?.reply().ok().map(|r| r.atom);
+        let title = atom(b"_NET_WM_NAME")
+            .zip(atom(b"UTF8_STRING"))
+            .and_then(|(prop, ty)| text(prop, ty))
+            .or_else(|| text(AtomEnum::WM_NAME.into(), AtomEnum::STRING.into()));
+        // WM_CLASS is "instance\0Class\0"; the class is the readable one.
+        let app = text(AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into())
+            .and_then(|c| c.split('\0').rfind(|s| !s.is_empty()).map(str::to_string));
+        Some((app, title))
+    }
+
+    fn active_id(conn: &impl Connection, screen_num: usize) -> std::result::Result<Option<Window>, ReplyError> {
+        let root = conn.setup().roots[screen_num].root;
+        let atom = conn.intern_atom(false, b"_NET_ACTIVE_WINDOW")?.reply()?.atom;
+        let prop = conn.get_property(false, root, atom, AtomEnum::WINDOW, 0, 1)?.reply()?;
+        Ok(prop.value32().and_then(|mut v| v.next()).filter(|w| *w != NONE))
+    }
+
+    fn active_on(conn: &impl Connection, screen_num: usize) -> std::result::Result<Option<Rect>, ReplyError> {
+        let root = conn.setup().roots[screen_num].root;
+        let Some(win) = active_id(conn, screen_num)? else {
+            return Ok(None);
+        };
+        let geo = conn.get_geometry(win)?.reply()?;
+        let pos = conn.translate_coordinates(win, root, 0, 0)?.reply()?;
+        Ok(Some(Rect { x: pos.dst_x as i32, y: pos.dst_y as i32, w: geo.width as u32, h: geo.height as u32 }))
+    }
+
+    fn select_on(conn: &impl Connection, screen_num: usize) -> std::result::Result<Result<Option<Rect>>, ReplyOrIdError> {
+        let screen = &conn.setup().roots[screen_num];
+        let root = screen.root;
+
+        let font = conn.generate_id()?;
+        conn.open_font(font, b"cursor")?;
+        let cursor = conn.generate_id()?;
+        conn.create_glyph_cursor(cursor, font, font, XC_CROSSHAIR, XC_CROSSHAIR + 1, 0, 0, 0, 0xffff, 0xffff, 0xffff)?;
+
+        // An invisible override-redirect window over the whole screen holds the
+        // grabs, so nothing underneath sees the clicks.
+        let overlay = conn.generate_id()?;
+        let events = EventMask::BUTTON_PRESS | EventMask::BUTTON_RELEASE | EventMask::POINTER_MOTION;
+        conn.create_window(
+            0,
+            overlay,
+            root,
+            0,
+            0,
+            screen.width_in_pixels,
+            screen.height_in_pixels,
+            0,
+            WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new().override_redirect(1).event_mask(events | EventMask::KEY_PRESS).cursor(cursor),
+        )?;
+        // The outline is a second one, shaped down to a frame as the drag goes.
+        let frame = conn.generate_id()?;
+        let colour = conn.alloc_color(screen.default_colormap, 0x3b3b, 0x8282, 0xf6f6)?.reply()?.pixel;
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            frame,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            screen.root_visual,
+            &CreateWindowAux::new().override_redirect(1).background_pixel(colour),
+        )?;
+        conn.map_window(overlay)?;
+
+        let grab = conn
+            .grab_pointer(false, overlay, events, GrabMode::ASYNC, GrabMode::ASYNC, NONE, cursor, CURRENT_TIME)?
+            .reply()?;
+        let outcome = if grab.status != GrabStatus::SUCCESS {
+            Err(Error::Failed("another client holds the pointer grab".into()))
+        } else {
+            conn.grab_keyboard(false, overlay, CURRENT_TIME, GrabMode::ASYNC, GrabMode::ASYNC)?.reply()?;
+            let outcome = track(conn, frame)?;
+            conn.ungrab_keyboard(CURRENT_TIME)?;
+            conn.ungrab_pointer(CURRENT_TIME)?;
+            outcome
+        };
+
+        conn.destroy_window(frame)?;
+        conn.destroy_window(overlay)?;
+        conn.free_cursor(cursor)?;
+        conn.close_font(font)?;
+        // Round-trip so the overlay is gone before anything grabs the screen.
+        conn.get_input_focus()?.reply()?;
+        Ok(outcome)
+    }
+
+    /// Follow one press-drag-release; any key cancels.
+    fn track(conn: &impl Connection, frame: Window) -> std::result::Result<Result<Option<Rect>>, ReplyOrIdError> {
+        let mut start = None;
+        let mut shown: Option<Rect> = None;
+        loop {
+            match conn.wait_for_event()? {
+                Event::ButtonPress(e) => start = Some((e.root_x as i32, e.root_y as i32)),
+                Event::MotionNotify(e) => {
+                    if let Some(s) = start {
+                        let next = Rect::from_drag(s, (e.root_x as i32, e.root_y as i32));
+                        if next != shown {
+                            outline(conn, frame, next)?;
+                            shown = next;
+                        }
+                    }
+                }
+                Event::ButtonRelease(e) => {
+                    if let Some(s) = start {
+                        return Ok(Ok(Rect::from_drag(s, (e.root_x as i32, e.root_y as i32))));
+                    }
+                }
+                Event::KeyPress(_) => return Ok(Err(Error::Cancelled)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Fit the frame window around `rect` and cut it down to its border;
+    /// `None` hides it.
+    fn outline(conn: &impl Connection, frame: Window, rect: Option<Rect>) -> std::result::Result<(), ReplyOrIdError> {
+        let Some(r) = rect else {
+            conn.unmap_window(frame)?;
+            conn.flush()?;
+            return Ok(());
+        };
+        let (w, h) = (r.w as u16, r.h as u16);
+        let b = BORDER.min(w / 2).min(h / 2);
+        let sides = [
+            Rectangle { x: 0, y: 0, width: w, height: b },
+            Rectangle { x: 0, y: (h - b) as i16, width: w, height: b },
+            Rectangle { x: 0, y: 0, width: b, height: h },
+            Rectangle { x: (w - b) as i16, y: 0, width: b, height: h },
+        ];
+        let place = ConfigureWindowAux::new().x(r.x).y(r.y).width(r.w).height(r.h).stack_mode(StackMode::ABOVE);
+        conn.configure_window(frame, &place)?;
+        conn.shape_rectangles(SO::SET, SK::BOUNDING, ClipOrdering::UNSORTED, frame, 0, 0, &sides)?;
+        conn.map_window(frame)?;
+        conn.flush()?;
+        Ok(())
+    }
+}
+
+mod wlroots {
+    use super::*;
+
+    /// `slurp` draws the selection, `grim` grabs exactly that geometry.
+    pub fn capture() -> Result<CaptureEvent> {
+        let rect = select()?;
+        let png = Command::new("grim").args(["-g", &rect.to_geometry(), "-t", "png", "-"]).output()?;
+        if !png.status.success() {
+            return Err(Error::Failed(String::from_utf8_lossy(&png.stderr).trim().to_string()));
+        }
+        let image = image::load_from_memory(&png.stdout)?.to_rgba8();
+        let display = display::containing(&screen::display_metas(), &rect).copied();
+        info!("Captured region {}", rect.to_geometry());
+        Ok(CaptureEvent::image(image, Some(rect), display))
+    }
+
+    /// Let the user drag out a region with `slurp`.
+    pub fn select() -> Result<Rect> {
+        let out = Command::new("slurp").stderr(Stdio::null()).output()?;
+        if !out.status.success() {
+            return Err(Error::Cancelled);
+        }
+        let geometry = String::from_utf8_lossy(&out.stdout);
+        Rect::parse_geometry(&geometry).ok_or_else(|| Error::Failed(format!("unexpected slurp output {geometry:?}")))
+    }
+}
+
+mod portal {
+    use super::*;
+    use ashpd::desktop::screenshot::Screenshot;
+    use ashpd::desktop::ResponseError;
+
+    /// Portal screenshot. With `slurp` installed the selection is ours: a
+    /// non-interactive shot of the whole desktop is cropped to it, so the
+    /// region is known. Otherwise the portal's own picker hands back the
+    /// cropped image without saying where it was, and the region is left unset.
+    pub fn capture() -> Result<CaptureEvent> {
+        if !has_command("slurp") {
+            let image = shoot(true)?;
+            info!("Captured portal screenshot {}x{}", image.width(), image.height());
+            return Ok(CaptureEvent::image(image, None, None));
+        }
+        // Shoot first so the picture is what the user selected from.
+        let shot = shoot(false)?;
+        let rect = wlroots::select()?;
+        let metas = screen::display_metas();
+        let desktop = display::desktop(&metas).unwrap_or(Rect { x: 0, y: 0, w: shot.width(), h: shot.height() });
+        let px = rect
+            .pixels_in(&desktop, shot.dimensions())
+            .ok_or_else(|| Error::Failed(format!("{} is off screen", rect.to_geometry())))?;
+        let image = image::imageops::crop_imm(&shot, px.x as u32, px.y as u32, px.w, px.h).to_image();
+        let display = display::containing(&metas, &rect).copied();
+        info!("Captured region {} through the portal", rect.to_geometry());
+        Ok(CaptureEvent::image(image, Some(rect), display))
+    }
+
+    /// Ask the portal for a screenshot; `interactive` lets the user pick the area.
+    fn shoot(interactive: bool) -> Result<image::RgbaImage> {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        let shot = rt.block_on(async {
+            Screenshot::request().interactive(interactive).modal(interactive).send().await?.response()
+        });
+        let uri = match shot {
+            Ok(shot) => shot.uri().clone(),
+            Err(ashpd::Error::Response(ResponseError::Cancelled)) => return Err(Error::Cancelled),
+            Err(e) => return Err(Error::Failed(format!("screenshot portal: {e}"))),
+        };
+        let path = uri
+            .to_file_path()
+            .map_err(|_| Error::Failed(format!("portal returned a non-file URI: {uri}")))?;
+        Ok(image::open(&path)?.to_rgba8())
+    }
+}
@@ -0,0 +1,108 @@
+//! Perceptual hashes of screenshots. Two captures of the same slide hash to
+//! nearly the same 64 bits even after re-encoding, small crops or a cursor
+//! moving across the screen, so near-duplicates are found by Hamming distance.
+
+use image::RgbaImage;
+
+/// Side of the grey thumbnail the DCT runs over.
+const SIZE: usize = 32;
+/// Low-frequency coefficients kept per axis; 8×8 gives 64 bits.
+const KEEP: usize = 8;
+
+/// DCT-based perceptual hash: shrink to a 32×32 grey thumbnail, take the 8×8
+/// lowest frequencies and set a bit for each above their median.
+pub fn phash(image: &RgbaImage) -> u64 {
+    if image.width() == 0 || image.height() == 0 {
+        return 0;
+    }
+    let thumb = image::imageops::thumbnail(image, SIZE as u32, SIZE as u32);
+    let grey: Vec<f32> = thumb
+        .pixels()
+        .map(|p| {
+            let [r, g, b, a] = p.0.map(f32::from);
+            // Transparent pixels read as white, like they render in a viewer.
+            let alpha = a / 255.0;
+            (0.299 * r + 0.587 * g + 0.114 * b) * alpha + 255.0 * (1.0 - alpha)
+        })
+        .collect();
+
+    let cos: Vec<f32> = (0..KEEP * SIZE)
+        .map(|i| {
+            let (k, n) = ((i / SIZE) as f32, (i % SIZE) as f32);
+            (std::f32::consts::PI / SIZE as f32 * (n + 0.5) * k).cos()
+        })
+        .collect();
+    // Separable 2-D DCT-II, rows first, keeping only the low frequencies.
+    let mut rows = [[0f32; KEEP]; SIZE];
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (u, out) in row.iter_mut().enumerate() {
+            *out = (0..SIZE).map(|x| grey[y * SIZE + x] * cos[u * SIZE + x]).sum();
+        }
+    }
+    let mut coeffs = [0f32; KEEP * KEEP];
+    for v in 0..KEEP {
+        for u in 0..KEEP {
+            coeffs[v * KEEP + u] = (0..SIZE).map(|y| rows[y][u] * cos[v * SIZE + y]).sum();
+        }
+    }
+
+    // The DC term only tracks overall brightness; leave it out of the median.
+    let mut ac = coeffs[1..].to_vec();
+    ac.sort_by(f32::total_cmp);
+    let median = ac[ac.len() / 2];
+    coeffs
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| **c > median)
+        .fold(0, |hash, (i, _)| hash | 1 << i)
+}
+
+/// Number of differing bits; 0 for identical images, around 32 for unrelated ones.
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// A "slide": a title bar and a few text-like blocks on a white background.
+    fn slide(width: u32, height: u32, blocks: &[(u32, u32, u32)]) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+        let scale = |v: u32, of: u32| v * of / 100;
+        for y in scale(5, height)..scale(15, height) {
+            for x in scale(10, width)..scale(90, width) {
+                img.put_pixel(x, y, Rgba([30, 60, 120, 255]));
+            }
+        }
+        for &(top, left, len) in blocks {
+            for y in scale(top, height)..scale(top + 6, height) {
+                for x in scale(left, width)..scale(left + len, width) {
+                    img.put_pixel(x, y, Rgba([20, 20, 20, 255]));
+                }
+            }
+        }
+        img
+    }
+
+    #[test]
+    fn near_duplicates_hash_close_and_others_far() {
+        let blocks = [(30, 10, 70), (45, 10, 55), (60, 10, 80)];
+        let original = slide(1280, 720, &blocks);
+
+        // Same slide captured again: a different resolution and a cursor on top.
+        let mut again = slide(1000, 563, &blocks);
+        for y in 300..320 {
+            for x in 500..512 {
+                again.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+            }
+        }
+        let next = slide(1280, 720, &[(30, 50, 45), (70, 5, 30), (80, 40, 55)]);
+
+        let h = phash(&original);
+        assert_eq!(distance(h, phash(&original.clone())), 0);
+        assert!(distance(h, phash(&again)) <= 6, "distance {}", distance(h, phash(&again)));
+        assert!(distance(h, phash(&next)) > 12, "distance {}", distance(h, phash(&next)));
+    }
+}
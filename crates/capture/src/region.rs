@@ -0,0 +1,132 @@
+//! Geometry and environment helpers for interactive region capture. Kept free
+//! of platform dependencies so they are testable in stub builds.
+
+/// Drags shorter than this (in pixels) count as a click.
+pub const MIN_DRAG: u32 = 4;
+
+/// Screen rectangle in global (root-window) coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Rect {
+    /// Normalised rectangle spanned by a drag in any direction; `None` for a click.
+    pub fn from_drag(start: (i32, i32), end: (i32, i32)) -> Option<Rect> {
+        let (x0, x1) = (start.0.min(end.0), start.0.max(end.0));
+        let (y0, y1) = (start.1.min(end.1), start.1.max(end.1));
+        let (w, h) = ((x1 - x0) as u32, (y1 - y0) as u32);
+        (w >= MIN_DRAG && h >= MIN_DRAG).then_some(Rect { x: x0, y: y0, w, h })
+    }
+
+    pub fn contains(&self, (px, py): (i32, i32)) -> bool {
+        px >= self.x && py >= self.y && px < self.x + self.w as i32 && py < self.y + self.h as i32
+    }
+
+    /// Intersection with another rectangle, `None` when they do not overlap.
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x + self.w as i32).min(other.x + other.w as i32);
+        let y1 = (self.y + self.h as i32).min(other.y + other.h as i32);
+        (x1 > x0 && y1 > y0).then(|| Rect { x: x0, y: y0, w: (x1 - x0) as u32, h: (y1 - y0) as u32 })
+    }
+
+    /// Smallest rectangle holding both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x0 = self.x.min(other.x);
+        let y0 = self.y.min(other.y);
+        let x1 = (self.x + self.w as i32).max(other.x + other.w as i32);
+        let y1 = (self.y + self.h as i32).max(other.y + other.h as i32);
+        Rect { x: x0, y: y0, w: (x1 - x0) as u32, h: (y1 - y0) as u32 }
+    }
+
+    /// Where this rectangle lies in a `(width, height)` image of `area`,
+    /// clipped to it. The scale comes from the image itself, so logical
+    /// rectangles land on HiDPI pixels. `None` when they do not overlap.
+    pub fn pixels_in(&self, area: &Rect, (width, height): (u32, u32)) -> Option<Rect> {
+        let r = self.intersect(area)?;
+        let sx = width as f32 / area.w.max(1) as f32;
+        let sy = height as f32 / area.h.max(1) as f32;
+        // Round the edges rather than the size so neighbouring regions tile.
+        let edge = |v: i32, origin: i32, scale: f32, max: u32| (((v - origin) as f32 * scale).round() as u32).min(max);
+        let (x0, x1) = (edge(r.x, area.x, sx, width), edge(r.x + r.w as i32, area.x, sx, width));
+        let (y0, y1) = (edge(r.y, area.y, sy, height), edge(r.y + r.h as i32, area.y, sy, height));
+        (x1 > x0 && y1 > y0).then(|| Rect { x: x0 as i32, y: y0 as i32, w: x1 - x0, h: y1 - y0 })
+    }
+
+    /// `slurp`/`grim` geometry string, `"x,y wxh"`.
+    pub fn to_geometry(&self) -> String {
+        format!("{},{} {}x{}", self.x, self.y, self.w, self.h)
+    }
+
+    /// Parse `slurp`'s default output, `"x,y wxh"`.
+    pub fn parse_geometry(s: &str) -> Option<Rect> {
+        let (pos, size) = s.trim().split_once(' ')?;
+        let (x, y) = pos.split_once(',')?;
+        let (w, h) = size.split_once('x')?;
+        Some(Rect { x: x.parse().ok()?, y: y.parse().ok()?, w: w.parse().ok()?, h: h.parse().ok()? })
+    }
+}
+
+/// Display server the current session runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Session {
+    X11,
+    Wayland,
+    Unknown,
+}
+
+/// Detect the session from environment variables (`std::env::var` in production).
+pub fn detect_session(env: impl Fn(&str) -> Option<String>) -> Session {
+    let set = |k: &str| env(k).is_some_and(|v| !v.is_empty());
+    match env("XDG_SESSION_TYPE").as_deref() {
+        Some("wayland") => Session::Wayland,
+        Some("x11") => Session::X11,
+        _ if set("WAYLAND_DISPLAY") => Session::Wayland,
+        _ if set("DISPLAY") => Session::X11,
+        _ => Session::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_normalises_and_ignores_clicks() {
+        let r = Rect::from_drag((300, 200), (100, 50)).unwrap();
+        assert_eq!(r, Rect { x: 100, y: 50, w: 200, h: 150 });
+        assert!(Rect::from_drag((10, 10), (12, 40)).is_none());
+    }
+
+    #[test]
+    fn clamps_to_display() {
+        let display = Rect { x: 1920, y: 0, w: 1280, h: 1024 };
+        let sel = Rect { x: 1800, y: -20, w: 300, h: 100 };
+        assert_eq!(sel.intersect(&display), Some(Rect { x: 1920, y: 0, w: 180, h: 80 }));
+        assert_eq!(Rect { x: 0, y: 0, w: 10, h: 10 }.intersect(&display), None);
+    }
+
+    #[test]
+    fn slurp_geometry_round_trips() {
+        let r = Rect::parse_geometry("-1280,24 640x480\n").unwrap();
+        assert_eq!(r, Rect { x: -1280, y: 24, w: 640, h: 480 });
+        assert_eq!(Rect::parse_geometry(&r.to_geometry()), Some(r));
+        assert!(Rect::parse_geometry("selection cancelled").is_none());
+    }
+
+    #[test]
+    fn detects_session_type() {
+        let env = |pairs: &'static [(&'static str, &'static str)]| {
+            move |k: &str| pairs.iter().find(|(n, _)| *n == k).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(detect_session(env(&[("XDG_SESSION_TYPE", "wayland")])), Session::Wayland);
+        assert_eq!(detect_session(env(&[("XDG_SESSION_TYPE", "tty"), ("DISPLAY", ":0")])), Session::X11);
+        assert_eq!(detect_session(env(&[("WAYLAND_DISPLAY", "wayland-0"), ("DISPLAY", ":0")])), Session::Wayland);
+        assert_eq!(detect_session(env(&[])), Session::Unknown);
+    }
+}
@@ -0,0 +1,76 @@
+//! Display enumeration, pointer tracking and pixel grabs via `screenshots`.
+
+use crate::display::DisplayMeta;
+use crate::region::Rect;
+use crate::{CaptureEvent, Error, Result};
+use screenshots::Screen;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Last pointer position seen by the hot-key listener (global logical coords).
+static LAST_POINTER: Mutex<Option<(i32, i32)>> = Mutex::new(None);
+
+pub(crate) fn record_pointer(x: f64, y: f64) {
+    if let Ok(mut p) = LAST_POINTER.lock() {
+        *p = Some((x as i32, y as i32));
+    }
+}
+
+/// Current pointer position: queried from X11 when available, otherwise the
+/// last position reported to the hot-key listener.
+pub fn cursor_position() -> Option<(i32, i32)> {
+    #[cfg(target_os = "linux")]
+    if let Some(p) = crate::linux::pointer() {
+        return Some(p);
+    }
+    LAST_POINTER.lock().ok().and_then(|p| *p)
+}
+
+/// Every connected display with its metadata.
+pub fn displays() -> Result<Vec<(Screen, DisplayMeta)>> {
+    let screens = Screen::all().map_err(|e| Error::Failed(format!("screenshot list failed: {e}")))?;
+    if screens.is_empty() {
+        return Err(Error::NoDisplay);
+    }
+    Ok(screens
+        .into_iter()
+        .map(|s| {
+            let i = &s.display_info;
+            let meta = DisplayMeta {
+                id: i.id,
+                bounds: Rect { x: i.x, y: i.y, w: i.width, h: i.height },
+                scale_factor: i.scale_factor,
+                is_primary: i.is_primary,
+            };
+            (s, meta)
+        })
+        .collect())
+}
+
+/// Metadata only, for captures taken by external tools.
+pub fn display_metas() -> Vec<DisplayMeta> {
+    displays().map(|d| d.into_iter().map(|(_, m)| m).collect()).unwrap_or_default()
+}
+
+/// Grab `rect` (global logical coordinates, clipped to the display) or the whole display.
+pub fn grab(screen: &Screen, meta: &DisplayMeta, rect: Option<Rect>) -> Result<CaptureEvent> {
+    // Shoot the whole display and crop here: `capture_area` scales the
+    // display's global offset by its own factor, which lands on the wrong
+    // pixels when displays differ in scale.
+    let shot = screen.capture().map_err(|e| Error::Failed(e.to_string()))?;
+    let full = image::RgbaImage::from_raw(shot.width(), shot.height(), shot.rgba().clone())
+        .ok_or_else(|| Error::Failed("buffer size mismatch".into()))?;
+    let (rect, px) = meta
+        .clip(rect, full.dimensions())
+        .ok_or_else(|| Error::Failed(format!("display {} has no pixels", meta.id)))?;
+    let image = image::imageops::crop_imm(&full, px.x as u32, px.y as u32, px.w, px.h).to_image();
+    info!(
+        display = meta.id,
+        scale = meta.scale_factor,
+        "Captured {} ({}x{} px)",
+        rect.to_geometry(),
+        px.w,
+        px.h
+    );
+    Ok(CaptureEvent::image(image, Some(rect), Some(*meta)))
+}
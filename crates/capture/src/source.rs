@@ -0,0 +1,110 @@
+//! Where a capture came from: frontmost application, its window title and,
+//! for browsers, the page URL. Probed before any selection UI takes focus.
+
+pub use utils::source::Source;
+
+/// Chromium-based browsers answering `URL of active tab of front window`.
+const CHROMIUM_BROWSERS: &[&str] =
+    &["Google Chrome", "Chromium", "Brave Browser", "Microsoft Edge", "Arc", "Vivaldi", "Opera"];
+
+/// AppleScript that prints the current page URL of `app`, if it is a
+/// scriptable browser. Firefox exposes no URL to AppleScript.
+pub fn browser_url_script(app: &str) -> Option<String> {
+    if app.starts_with("Safari") {
+        Some(format!("tell application \"{app}\" to get URL of front document"))
+    } else if CHROMIUM_BROWSERS.contains(&app) {
+        Some(format!("tell application \"{app}\" to get URL of active tab of front window"))
+    } else {
+        None
+    }
+}
+
+/// First `http(s)://` address in a window title. Some browsers (and
+/// title-rewriting extensions) put it there when nothing else reports it.
+pub fn url_from_title(title: &str) -> Option<String> {
+    title
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| matches!(c, '(' | ')' | '[' | ']' | '<' | '>' | '"' | '\'')))
+        .find(|w| (w.starts_with("https://") || w.starts_with("http://")) && w.len() > "https://".len())
+        .map(str::to_string)
+}
+
+/// Describe the frontmost window. Fields stay `None` where the platform does
+/// not tell (Wayland, missing accessibility permission).
+#[cfg(feature = "full")]
+pub fn active() -> Source {
+    let mut src = Source::now();
+
+    #[cfg(target_os = "macos")]
+    {
+        if let Some((app, title)) = macos::frontmost() {
+            src.url = browser_url_script(&app).and_then(|s| macos::osascript(&s));
+            src.app = Some(app);
+            src.window_title = title;
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if let Some((app, title)) = crate::linux::active_window_info() {
+            src.app = app;
+            src.window_title = title;
+        }
+    }
+
+    if src.url.is_none() {
+        src.url = src.window_title.as_deref().and_then(url_from_title);
+    }
+    src
+}
+
+#[cfg(not(feature = "full"))]
+pub fn active() -> Source {
+    Source::now()
+}
+
+#[cfg(all(feature = "full", target_os = "macos"))]
+mod macos {
+    use std::process::Command;
+
+    const FRONTMOST: &str = r#"tell application "System Events"
+        set p to first application process whose frontmost is true
+        set t to ""
+        try
+            set t to name of front window of p
+        end try
+        return (name of p) & linefeed & t
+    end tell"#;
+
+    /// Run a script and return its trimmed output, `None` on failure or empty output.
+    pub fn osascript(script: &str) -> Option<String> {
+        let out = Command::new("osascript").args(["-e", script]).output().ok()?;
+        let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        (out.status.success() && !text.is_empty()).then_some(text)
+    }
+
+    /// Frontmost application name and its front window title.
+    pub fn frontmost() -> Option<(String, Option<String>)> {
+        let out = osascript(FRONTMOST)?;
+        let (app, title) = out.split_once('\n').unwrap_or((&out, ""));
+        let title = title.trim();
+        Some((app.trim().to_string(), (!title.is_empty()).then(|| title.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_browser_urls() {
+        assert!(browser_url_script("Safari").unwrap().contains("front document"));
+        assert!(browser_url_script("Google Chrome").unwrap().contains("active tab"));
+        assert_eq!(browser_url_script("Firefox"), None);
+
+        let title = "Ownership - The Rust Book (https://doc.rust-lang.org/book/ch04-01.html) — Mozilla Firefox";
+        assert_eq!(url_from_title(title).as_deref(), Some("https://doc.rust-lang.org/book/ch04-01.html"));
+        assert_eq!(url_from_title("Inbox (3) — Mail"), None);
+        assert_eq!(url_from_title("https://"), None);
+    }
+}
@@ -0,0 +1,95 @@
+//! Grading typed (or transcribed) answers. The string check in
+//! [`scheduler::grading`] runs first; answers it rejects are compared by
+//! embedding and then put to the LLM judge when `[grading]` enables them,
+//! unless they got a number wrong.
+
+use crate::Context;
+use anyhow::Result;
+use data::embeddings::cosine;
+use llm::JudgeVerdict;
+use scheduler::grading::{self, Grade, Method, Verdict};
+use tracing::{debug, warn};
+
+/// Suggest a grade for `answer` to card `card_id`.
+pub async fn check(ctx: &Context, card_id: i64, answer: &str) -> Result<Verdict> {
+    let card = data::fetch_card(&ctx.db, card_id)?;
+    let cfg = ctx.config();
+    let mut verdict = grading::check(answer, &card.back, &cfg.grading);
+    if verdict.grade == Grade::Good || verdict.wrong_number || answer.trim().is_empty() {
+        return Ok(verdict);
+    }
+
+    if cfg.grading.semantic > 0.0 {
+        let embedder = llm::embed::embedder(&cfg.dedupe, &cfg.llm);
+        match embedder.embed(&[answer.to_string(), card.back.clone()]).await {
+            Ok(v) if v.len() == 2 => {
+                let similarity = cosine(&v[0], &v[1]);
+                debug!(card_id, similarity, "semantic answer check");
+                if similarity >= cfg.grading.semantic {
+                    return Ok(Verdict {
+                        grade: Grade::Good,
+                        score: similarity,
+                        method: Method::Semantic,
+                        explanation: None,
+                        wrong_number: false,
+                    });
+                }
+            }
+            Ok(_) => warn!(card_id, "embedder returned the wrong number of vectors"),
+            Err(e) => warn!(error = %e, card_id, "could not compare answer by meaning"),
+        }
+    }
+
+    if cfg.grading.judge {
+        match llm::judge_answer(&card.front, &card.back, answer, &cfg.llm).await {
+            Ok(judgement) => {
+                verdict.grade = match judgement.verdict {
+                    JudgeVerdict::Correct => Grade::Good,
+                    JudgeVerdict::Partial => Grade::Hard,
+                    JudgeVerdict::Wrong => Grade::Again,
+                };
+                verdict.method = Method::Judge;
+                // The string check's notes no longer apply to an accepted answer.
+                let explanation = Some(judgement.explanation).filter(|e| !e.trim().is_empty());
+                verdict.explanation = match verdict.grade {
+                    Grade::Good => explanation,
+                    _ => explanation.or(verdict.explanation),
+                };
+            }
+            Err(e) => warn!(error = %e, card_id, "answer judge failed; keeping the string check"),
+        }
+    }
+    Ok(verdict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::config::Config;
+
+    #[tokio::test]
+    async fn wrong_numbers_are_not_rescued_by_meaning() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.grading.semantic = 0.5;
+        let ctx = Context::for_tests(dir.path(), config);
+        let card = data::CardJson {
+            id: 0,
+            front: "When did the French Revolution begin?".into(),
+            back: "The French Revolution began in 1789 with the storming of the Bastille".into(),
+            tags: vec![],
+            source: None,
+            media: None,
+            deck: None,
+        };
+        let card_id = data::insert_card(&ctx.db, &card).unwrap();
+
+        let answer = "the storming of the Bastille in 1798 began the French Revolution";
+        let verdict = check(&ctx, card_id, answer).await.unwrap();
+        assert_eq!((verdict.grade, verdict.method), (Grade::Again, Method::Fuzzy), "{verdict:?}");
+        assert!(verdict.wrong_number);
+
+        let reworded = check(&ctx, card_id, &answer.replace("1798", "1789")).await.unwrap();
+        assert_eq!(reworded.grade, Grade::Good, "{reworded:?}");
+    }
+}
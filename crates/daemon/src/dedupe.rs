@@ -0,0 +1,71 @@
+//! Semantic duplicate detection. New cards are embedded as they are created
+//! and compared with the collection; `oakley cards dedupe` embeds whatever is
+//! missing and reports every pair above `dedupe.threshold`.
+
+use crate::error::INVALID_REQUEST;
+use crate::Context;
+use anyhow::{bail, Result};
+use data::embeddings::{self, card_text};
+use data::{CardJson, DuplicatePair, Similar};
+use tracing::{info, warn};
+use utils::error::ErrorPayload;
+
+/// Cards embedded per provider request while backfilling.
+const BATCH: usize = 64;
+
+/// Index a new card and return existing cards it may duplicate. Embedding
+/// failures (e.g. the API provider offline) only cost the warning.
+pub async fn check(ctx: &Context, card: &CardJson) -> Vec<Similar> {
+    let cfg = ctx.config();
+    let embedder = llm::embed::embedder(&cfg.dedupe, &cfg.llm);
+    let found = async {
+        let vector = embedder.embed(&[card_text(card)]).await?.pop().unwrap_or_default();
+        embeddings::put_embedding(&ctx.db, card.id, embedder.model(), &vector)?;
+        let similar = embeddings::similar_cards(&ctx.db, embedder.model(), &vector, cfg.dedupe.threshold, card.id)?;
+        anyhow::Ok(similar)
+    };
+    match found.await {
+        Ok(similar) => {
+            if let Some(best) = similar.first() {
+                let (like, similarity) = (best.card.id, best.similarity);
+                info!(card = card.id, like, similarity, "👯 Card may duplicate an existing one");
+            }
+            similar
+        }
+        Err(e) => {
+            warn!(error = %e, card = card.id, "could not check card for duplicates");
+            Vec::new()
+        }
+    }
+}
+
+/// Embed every card still missing a vector, then list likely duplicates.
+pub async fn report(ctx: &Context, threshold: Option<f32>) -> Result<Vec<DuplicatePair>> {
+    let cfg = ctx.config();
+    let threshold = threshold.unwrap_or(cfg.dedupe.threshold);
+    if !(0.0..=1.0).contains(&threshold) {
+        bail!(ErrorPayload::new(INVALID_REQUEST, format!("threshold must be between 0 and 1 (got {threshold})")));
+    }
+    let embedder = llm::embed::embedder(&cfg.dedupe, &cfg.llm);
+    let missing = embeddings::unembedded_cards(&ctx.db, embedder.model())?;
+    if !missing.is_empty() {
+        info!(cards = missing.len(), model = embedder.model(), "Embedding cards for duplicate detection");
+    }
+    for batch in missing.chunks(BATCH) {
+        let texts: Vec<String> = batch.iter().map(card_text).collect();
+        for (card, vector) in batch.iter().zip(embedder.embed(&texts).await?) {
+            embeddings::put_embedding(&ctx.db, card.id, embedder.model(), &vector)?;
+        }
+    }
+    Ok(embeddings::duplicate_pairs(&ctx.db, embedder.model(), threshold)?)
+}
+
+/// Fold `duplicate` into `keep` (see [`data::merge_cards`]).
+pub fn merge(ctx: &Context, keep: i64, duplicate: i64) -> Result<CardJson> {
+    if keep == duplicate {
+        bail!(ErrorPayload::new(INVALID_REQUEST, "cannot merge a card into itself"));
+    }
+    let card = data::merge_cards(&ctx.db, keep, duplicate)?;
+    info!(keep, duplicate, "🔗 Merged duplicate cards");
+    Ok(card)
+}
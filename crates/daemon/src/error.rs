@@ -0,0 +1,74 @@
+//! Maps `anyhow` chains from daemon tasks onto wire-level [`ErrorPayload`]s.
+
+use utils::error::{Coded, ErrorPayload};
+
+/// Code for malformed or empty client requests.
+pub const INVALID_REQUEST: &str = "request.invalid";
+/// Code for HTTP requests without a valid token.
+pub const UNAUTHORIZED: &str = "request.unauthorized";
+
+/// Find the first typed error in the chain; fall back to `internal`.
+pub fn payload(e: &anyhow::Error) -> ErrorPayload {
+    for cause in e.chain() {
+        if let Some(err) = cause.downcast_ref::<data::Error>() {
+            return err.payload();
+        }
+        if let Some(err) = cause.downcast_ref::<scheduler::Error>() {
+            return err.payload();
+        }
+        if let Some(err) = cause.downcast_ref::<llm::Error>() {
+            return err.payload();
+        }
+        if let Some(err) = cause.downcast_ref::<capture::Error>() {
+            return err.payload();
+        }
+        if let Some(err) = cause.downcast_ref::<ocr::Error>() {
+            return err.payload();
+        }
+        if let Some(err) = cause.downcast_ref::<ingest::Error>() {
+            return err.payload();
+        }
+        if let Some(err) = cause.downcast_ref::<speech::Error>() {
+            return err.payload();
+        }
+        if let Some(err) = cause.downcast_ref::<ErrorPayload>() {
+            return err.clone();
+        }
+    }
+    ErrorPayload::internal(e.to_string())
+}
+
+/// HTTP status for a payload: retryable → 503, missing → 404, wrong state →
+/// 409, bad input → 400, bad token → 401.
+pub fn http_status(err: &ErrorPayload) -> u16 {
+    match err.code.as_str() {
+        _ if err.retryable => 503,
+        "db.not_found" | "scheduler.unknown_card" => 404,
+        "db.conflict" => 409,
+        INVALID_REQUEST => 400,
+        UNAUTHORIZED => 401,
+        _ => 500,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context as _;
+
+    #[test]
+    fn finds_typed_error_behind_context() {
+        let e = Err::<(), _>(data::Error::Locked).context("grading card").unwrap_err();
+        let p = payload(&e);
+        assert_eq!((p.code.as_str(), p.retryable), ("db.locked", true));
+        assert_eq!(http_status(&p), 503);
+
+        let e = Err::<(), _>(scheduler::Error::UnknownCard(7)).context("grading card").unwrap_err();
+        assert_eq!(http_status(&payload(&e)), 404);
+        let e = anyhow::Error::from(scheduler::Error::from(data::Error::Locked));
+        assert_eq!(payload(&e).code, "db.locked", "database failures keep their code");
+
+        let p = payload(&anyhow::anyhow!("boom"));
+        assert_eq!((p.code.as_str(), http_status(&p)), (utils::error::INTERNAL, 500));
+    }
+}
@@ -0,0 +1,519 @@
+//! HTTP JSON endpoint for external browsers. Cards are readable by anyone on
+//! localhost; everything else needs the `http.token` secret, as header or
+//! `?token=`. Screenshots are at `GET /media/<hash>`. Browser extensions push
+//! captures to `POST /ingest` and follow them via `GET /jobs/<id>` or the
+//! `GET /events` stream. Screenshots held as near-duplicates are settled with
+//! `POST /jobs/<id>/resolve`. Review statistics are at `GET /stats`, or one
+//! section of them at e.g. `GET /stats/retention`, both taking `?days=`.
+
+use crate::error::{INVALID_REQUEST, UNAUTHORIZED};
+use crate::{pipeline, Context};
+use anyhow::bail;
+use base64::Engine as _;
+use capture::{CaptureEvent, Payload};
+use data::{CardJson, Job};
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info};
+use utils::error::{Coded, ErrorPayload};
+use utils::source::Source;
+use warp::{http::StatusCode, hyper::Body, reply::Response, Filter, Reply};
+
+/// Origin of the browser web interface (`npm run dev`), the one page allowed
+/// to read `GET /cards` across origins.
+const WEB_ORIGIN: &str = "http://localhost:5173";
+
+/// Largest accepted `POST /ingest` body; full-page screenshots fit easily.
+const MAX_INGEST_BYTES: u64 = 20 * 1024 * 1024;
+
+pub async fn serve(ctx: Context, port: u16) {
+    warp::serve(routes(ctx)).run(([127, 0, 0, 1], port)).await;
+}
+
+fn routes(ctx: Context) -> impl Filter<Extract = (Response,), Error = warp::Rejection> + Clone {
+    let db = ctx.db.clone();
+    let get_cards = warp::path("cards").and(warp::get()).and_then(move || {
+        let db = db.clone();
+        async move {
+            let reply = match data::fetch_all_cards(&db) {
+                Ok(cards) => warp::reply::with_status(warp::reply::json(&cards), StatusCode::OK),
+                Err(e) => {
+                    error!(?e, "fetch_all_cards failed");
+                    let err = e.payload();
+                    let status = StatusCode::from_u16(crate::error::http_status(&err))
+                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                    warp::reply::with_status(warp::reply::json(&err), status)
+                }
+            };
+            Ok::<_, Infallible>(reply.into_response())
+        }
+    });
+
+    let media_ctx = ctx.clone();
+    let get_media = warp::path!("media" / String)
+        .and(warp::get())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |hash: String, auth: Option<String>, query: HashMap<String, String>| {
+            match authorize(&media_ctx, bearer(auth, &query).as_deref()) {
+                Ok(()) => media(&media_ctx, &hash, false),
+                Err(err) => error_reply(&err),
+            }
+        });
+    let thumb_ctx = ctx.clone();
+    let get_thumb = warp::path!("media" / String / "thumb")
+        .and(warp::get())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |hash: String, auth: Option<String>, query: HashMap<String, String>| {
+            match authorize(&thumb_ctx, bearer(auth, &query).as_deref()) {
+                Ok(()) => media(&thumb_ctx, &hash, true),
+                Err(err) => error_reply(&err),
+            }
+        });
+
+    let stats_ctx = ctx.clone();
+    let get_stats = warp::path!("stats")
+        .and(warp::get())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |auth: Option<String>, query: HashMap<String, String>| {
+            match authorize(&stats_ctx, bearer(auth, &query).as_deref()) {
+                Ok(()) => stats(&stats_ctx, None, &query),
+                Err(err) => error_reply(&err),
+            }
+        });
+    let section_ctx = ctx.clone();
+    let get_stats_section = warp::path!("stats" / String)
+        .and(warp::get())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |section: String, auth: Option<String>, query: HashMap<String, String>| {
+            match authorize(&section_ctx, bearer(auth, &query).as_deref()) {
+                Ok(()) => stats(&section_ctx, Some(&section), &query),
+                Err(err) => error_reply(&err),
+            }
+        });
+
+    let ingest_ctx = ctx.clone();
+    let post_ingest = warp::path!("ingest")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::content_length_limit(MAX_INGEST_BYTES))
+        .and(warp::body::bytes())
+        .and_then(move |auth: Option<String>, body: warp::hyper::body::Bytes| {
+            let ctx = ingest_ctx.clone();
+            async move { Ok::<_, Infallible>(ingest(&ctx, auth.as_deref(), &body).await) }
+        });
+
+    let job_ctx = ctx.clone();
+    let get_job = warp::path!("jobs" / i64)
+        .and(warp::get())
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(move |id: i64, auth: Option<String>| {
+            let ctx = job_ctx.clone();
+            async move { Ok::<_, Infallible>(job(&ctx, auth.as_deref(), id)) }
+        });
+
+    let resolve_ctx = ctx.clone();
+    let post_resolve = warp::path!("jobs" / i64 / "resolve")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::content_length_limit(1024))
+        .and(warp::body::bytes())
+        .and_then(move |id: i64, auth: Option<String>, body: warp::hyper::body::Bytes| {
+            let ctx = resolve_ctx.clone();
+            async move { Ok::<_, Infallible>(resolve(&ctx, auth.as_deref(), id, &body)) }
+        });
+
+    let events_ctx = ctx;
+    let get_events = warp::path!("events")
+        .and(warp::get())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |auth: Option<String>, query: HashMap<String, String>| {
+            match authorize(&events_ctx, bearer(auth, &query).as_deref()) {
+                Ok(()) => events(&events_ctx),
+                Err(err) => error_reply(&err),
+            }
+        });
+
+    // Only the web interface reads across origins. Extensions are exempt from
+    // CORS for hosts they have permission for, and other pages get nothing.
+    let cors = warp::cors().allow_origin(WEB_ORIGIN).allow_methods(["GET"]);
+    get_cards
+        .with(cors)
+        .map(Reply::into_response)
+        .or(get_media)
+        .unify()
+        .or(get_thumb)
+        .unify()
+        .or(get_stats)
+        .unify()
+        .or(get_stats_section)
+        .unify()
+        .or(post_ingest)
+        .unify()
+        .or(get_job)
+        .unify()
+        .or(post_resolve)
+        .unify()
+        .or(get_events)
+        .unify()
+}
+
+/// Body of `POST /ingest`: exactly one of `text`, `html` or `screenshot`,
+/// plus what the extension knows about the page.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IngestRequest {
+    /// Selected text.
+    text: Option<String>,
+    /// Selection as HTML; cleaned to text before generation.
+    html: Option<String>,
+    /// PNG, JPEG or WebP, base64-encoded, bare or as a `data:` URL.
+    screenshot: Option<String>,
+    url: Option<String>,
+    /// Page title.
+    title: Option<String>,
+    /// Browser name, recorded as the source application.
+    browser: Option<String>,
+    deck: Option<String>,
+}
+
+/// Reply to `POST /ingest`.
+#[derive(Debug, Serialize, Deserialize)]
+struct Ingested {
+    /// Queue entry to poll at `GET /jobs/<id>`; `card_created` events carry it too.
+    job_id: i64,
+}
+
+/// Reply to `GET /jobs/<id>`: the queue entry, and its card once generated.
+#[derive(Debug, Serialize, Deserialize)]
+struct JobReply {
+    job: Job,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    card: Option<CardJson>,
+}
+
+async fn ingest(ctx: &Context, auth: Option<&str>, body: &[u8]) -> Response {
+    if let Err(err) = authorize(ctx, auth) {
+        return error_reply(&err);
+    }
+    let req: IngestRequest = match serde_json::from_slice(body) {
+        Ok(req) => req,
+        Err(e) => return error_reply(&ErrorPayload::new(INVALID_REQUEST, format!("bad ingest request: {e}"))),
+    };
+    let queued = async {
+        let payload = match (req.text, req.html, req.screenshot) {
+            (Some(text), None, None) => Payload::Text(text),
+            (None, Some(html), None) => Payload::Html(html),
+            (None, None, Some(b64)) => {
+                let image = tokio::task::spawn_blocking(move || decode_screenshot(&b64)).await??;
+                Payload::Image { image, region: None }
+            }
+            _ => bail!(ErrorPayload::new(INVALID_REQUEST, "send exactly one of text, html or screenshot")),
+        };
+        let source = Source { app: req.browser, window_title: req.title, url: req.url, ..Source::now() };
+        let evt = CaptureEvent { deck: req.deck, ..CaptureEvent::new(payload, source) };
+        pipeline::submit(ctx, evt).await
+    };
+    match queued.await {
+        Ok(job_id) => {
+            info!(job = job_id, "🌐 Browser capture queued");
+            let mut res = warp::reply::with_status(warp::reply::json(&Ingested { job_id }), StatusCode::ACCEPTED)
+                .into_response();
+            res.headers_mut().insert("location", format!("/jobs/{job_id}").parse().expect("valid header"));
+            res
+        }
+        Err(e) => {
+            let err = crate::error::payload(&e);
+            if err.code != INVALID_REQUEST {
+                error!(error = %format!("{e:#}"), "browser capture failed");
+            }
+            error_reply(&err)
+        }
+    }
+}
+
+fn decode_screenshot(b64: &str) -> anyhow::Result<image::RgbaImage> {
+    let data = b64.split_once(";base64,").map_or(b64, |(_, data)| data);
+    let bad = |what: String| ErrorPayload::new(INVALID_REQUEST, format!("screenshot is not {what}"));
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| bad(format!("valid base64: {e}")))?;
+    Ok(image::load_from_memory(&bytes).map_err(|e| bad(format!("a readable image: {e}")))?.to_rgba8())
+}
+
+fn job(ctx: &Context, auth: Option<&str>, id: i64) -> Response {
+    let found = authorize(ctx, auth).and_then(|()| {
+        let job = data::jobs::fetch_job(&ctx.db, id).map_err(|e| e.payload())?;
+        let card = job.card_id.map(|card| data::fetch_card(&ctx.db, card)).transpose().map_err(|e| e.payload())?;
+        Ok(JobReply { job, card })
+    });
+    match found {
+        Ok(reply) => warp::reply::json(&reply).into_response(),
+        Err(err) => error_reply(&err),
+    }
+}
+
+/// Body of `POST /jobs/<id>/resolve`.
+#[derive(Debug, Deserialize)]
+struct Resolve {
+    /// Keep the earlier capture's card instead of generating a new one.
+    reuse: bool,
+}
+
+/// Settle a held duplicate and reply like `GET /jobs/<id>`.
+fn resolve(ctx: &Context, auth: Option<&str>, id: i64, body: &[u8]) -> Response {
+    let resolved = authorize(ctx, auth).and_then(|()| {
+        let Resolve { reuse } = serde_json::from_slice(body)
+            .map_err(|e| ErrorPayload::new(INVALID_REQUEST, format!("bad resolve request: {e}")))?;
+        crate::jobs::resolve(ctx, id, reuse).map_err(|e| crate::error::payload(&e))
+    });
+    match resolved {
+        Ok(_) => job(ctx, auth, id),
+        Err(err) => error_reply(&err),
+    }
+}
+
+/// Daemon events as server-sent events, one JSON object each.
+fn events(ctx: &Context) -> Response {
+    let rx = ctx.events.subscribe();
+    let stream = stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(evt) => {
+                    let sse = warp::sse::Event::default().json_data(&evt).unwrap_or_default();
+                    return Some((Ok::<_, Infallible>(sse), rx));
+                }
+                // A slow reader misses events rather than stalling the daemon.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    warp::sse::reply(warp::sse::keep_alive().stream(stream)).into_response()
+}
+
+/// Check `Authorization: Bearer <http.token>`. An empty token disables the
+/// routes that need one.
+/// The `Authorization` header, or the `?token=` query for clients that cannot
+/// set headers (`EventSource`, `<img>`).
+fn bearer(auth: Option<String>, query: &HashMap<String, String>) -> Option<String> {
+    auth.or_else(|| query.get("token").map(|t| format!("Bearer {t}")))
+}
+
+fn authorize(ctx: &Context, header: Option<&str>) -> Result<(), ErrorPayload> {
+    let token = ctx.config().http.token;
+    if token.is_empty() {
+        return Err(ErrorPayload::new(UNAUTHORIZED, "the HTTP API is locked; set http.token in the config"));
+    }
+    let given = header.and_then(|h| h.strip_prefix("Bearer ")).unwrap_or_default();
+    // Constant-time comparison so the token cannot be guessed byte by byte.
+    let same = given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0;
+    if same {
+        Ok(())
+    } else {
+        Err(ErrorPayload::new(UNAUTHORIZED, "missing or wrong token"))
+    }
+}
+
+fn error_reply(err: &ErrorPayload) -> Response {
+    let status = StatusCode::from_u16(crate::error::http_status(err)).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    warp::reply::with_status(warp::reply::json(err), status).into_response()
+}
+
+/// All review statistics, or the one section named (`reviews`, `retention`,
+/// `streak`, `time`, `maturity`, `forecast`, `decks` or `tags`).
+fn stats(ctx: &Context, section: Option<&str>, query: &HashMap<String, String>) -> Response {
+    let days = match query.get("days").map(|d| d.parse::<u32>()) {
+        None => data::stats::DEFAULT_DAYS,
+        Some(Ok(days)) if days > 0 => days,
+        Some(_) => return error_reply(&ErrorPayload::new(INVALID_REQUEST, "days must be a positive whole number")),
+    };
+    let clock = utils::clock::StudyClock::from_config(&ctx.config().scheduler);
+    let stats = match data::stats::stats(&ctx.db, chrono::Utc::now(), &clock, days) {
+        Ok(stats) => stats,
+        Err(e) => {
+            error!(error = %e, "computing stats failed");
+            return error_reply(&e.payload());
+        }
+    };
+    let Some(name) = section else {
+        return warp::reply::json(&stats).into_response();
+    };
+    let mut all = match serde_json::to_value(&stats) {
+        Ok(serde_json::Value::Object(all)) => all,
+        _ => unreachable!("stats serialize to an object"),
+    };
+    match all.remove(name) {
+        Some(value) if name != "days" => warp::reply::json(&value).into_response(),
+        _ => error_reply(&data::Error::NotFound(format!("stats section {name}")).payload()),
+    }
+}
+
+/// Serve a stored screenshot (or its PNG thumbnail). Content never changes
+/// under a hash, so clients may cache it forever.
+fn media(ctx: &Context, hash: &str, thumb: bool) -> Response {
+    match ctx.read_media(hash, thumb) {
+        Ok((bytes, content_type)) => {
+            let mut res = Response::new(Body::from(bytes));
+            let headers = res.headers_mut();
+            headers.insert("content-type", content_type.parse().expect("static header"));
+            headers.insert("cache-control", "private, max-age=31536000, immutable".parse().expect("static header"));
+            res
+        }
+        Err(e) => {
+            let err = e.payload();
+            if err.code != "db.not_found" {
+                error!(error = %e, "reading media failed");
+            }
+            error_reply(&err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn ingest_needs_the_token_and_returns_a_pollable_job() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = utils::config::Config::default();
+        config.http.token = "s3cret".into();
+        let ctx = Context::for_tests(dir.path(), config);
+        let routes = routes(ctx.clone());
+        let post = |auth: &str, body: &str| {
+            warp::test::request().method("POST").path("/ingest").header("authorization", auth).body(body)
+        };
+
+        let res = post("Bearer wrong", r#"{"text": "x"}"#).reply(&routes).await;
+        assert_eq!(res.status(), 401);
+
+        let res = post("Bearer s3cret", r#"{"text": "x", "html": "<p>y</p>"}"#).reply(&routes).await;
+        assert_eq!(res.status(), 400);
+
+        let body = r#"{"text": "Ownership moves values", "url": "https://doc.rust-lang.org/book/", "browser": "Firefox", "deck": "Rust"}"#;
+        let res = post("Bearer s3cret", body).reply(&routes).await;
+        assert_eq!(res.status(), 202);
+        let Ingested { job_id } = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(res.headers()["location"], format!("/jobs/{job_id}"));
+
+        let res = warp::test::request().path(&format!("/jobs/{job_id}")).reply(&routes).await;
+        assert_eq!(res.status(), 401);
+        let res = warp::test::request()
+            .path(&format!("/jobs/{job_id}"))
+            .header("authorization", "Bearer s3cret")
+            .reply(&routes)
+            .await;
+        let reply: JobReply = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!((reply.job.id, reply.job.kind.as_str()), (job_id, "text"));
+        let (_, payload) = data::jobs::claim_job(&ctx.db, job_id).unwrap().unwrap();
+        assert_eq!(payload.deck(), Some("Rust"));
+        assert_eq!(payload.source().and_then(|s| s.app.as_deref()), Some("Firefox"));
+    }
+
+    #[tokio::test]
+    async fn screenshots_need_the_token_and_stay_same_origin() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = utils::config::Config::default();
+        config.http.token = "s3cret".into();
+        let ctx = Context::for_tests(dir.path(), config);
+        let routes = routes(ctx.clone());
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image::RgbaImage::new(8, 8))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .unwrap();
+        let hash = ctx.media.put(&ctx.db, &png).unwrap().hash;
+
+        let get = |path: String| warp::test::request().path(&path).header("origin", "https://evil.example");
+        assert_eq!(get(format!("/media/{hash}")).reply(&routes).await.status(), 401);
+        assert_eq!(get(format!("/media/{hash}/thumb?token=wrong")).reply(&routes).await.status(), 401);
+        let res = get(format!("/media/{hash}")).header("authorization", "Bearer s3cret").reply(&routes).await;
+        assert_eq!((res.status().as_u16(), res.body().as_ref()), (200, png.as_slice()));
+        assert!(!res.headers().contains_key("access-control-allow-origin"));
+        let res = get(format!("/media/{hash}/thumb?token=s3cret")).reply(&routes).await;
+        assert_eq!(res.headers()["content-type"], "image/png");
+
+        assert_eq!(get("/cards".into()).reply(&routes).await.status(), 403);
+        let res = warp::test::request().path("/cards").header("origin", WEB_ORIGIN).reply(&routes).await;
+        assert_eq!(res.headers()["access-control-allow-origin"], WEB_ORIGIN);
+    }
+
+    #[tokio::test]
+    async fn serves_stats_and_their_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = utils::config::Config::default();
+        config.http.token = "s3cret".into();
+        let ctx = Context::for_tests(dir.path(), config);
+        let routes = routes(ctx);
+        let get = |path: &str| warp::test::request().path(path).header("authorization", "Bearer s3cret");
+
+        assert_eq!(warp::test::request().path("/stats").reply(&routes).await.status(), 401);
+        assert_eq!(warp::test::request().path("/stats/decks?token=wrong").reply(&routes).await.status(), 401);
+
+        let res = get("/stats?days=7").reply(&routes).await;
+        assert_eq!(res.status(), 200);
+        let stats: data::Stats = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!((stats.days, stats.reviews.len()), (7, 7));
+
+        let res = warp::test::request().path("/stats/streak?token=s3cret").reply(&routes).await;
+        let streak: data::stats::Streak = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(streak.current, 0);
+        assert_eq!(get("/stats/nope").reply(&routes).await.status(), 404);
+        assert_eq!(get("/stats?days=0").reply(&routes).await.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn repeated_screenshots_are_held_until_resolved() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = utils::config::Config::default();
+        config.http.token = "s3cret".into();
+        let ctx = Context::for_tests(dir.path(), config);
+        let routes = routes(ctx.clone());
+        let post = |path: &str, body: String| {
+            warp::test::request().method("POST").path(path).header("authorization", "Bearer s3cret").body(body)
+        };
+
+        let slide =
+            image::RgbaImage::from_fn(320, 180, |x, y| image::Rgba([(x / 40 * 30) as u8, (y / 30 * 40) as u8, 90, 255]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(slide)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .unwrap();
+        let body = serde_json::json!({ "screenshot": base64::engine::general_purpose::STANDARD.encode(&png) }).to_string();
+
+        let res = post("/ingest", body.clone()).reply(&routes).await;
+        let Ingested { job_id: first } = serde_json::from_slice(res.body()).unwrap();
+        let res = post("/ingest", body).reply(&routes).await;
+        let Ingested { job_id: again } = serde_json::from_slice(res.body()).unwrap();
+        let job = data::jobs::fetch_job(&ctx.db, again).unwrap();
+        assert_eq!((job.status, job.duplicate_of), (data::JobStatus::Held, Some(first)));
+
+        let res = post(&format!("/jobs/{again}/resolve"), r#"{"reuse": true}"#.into()).reply(&routes).await;
+        assert_eq!(res.status(), 409, "the earlier capture has no card yet");
+
+        data::jobs::claim_job(&ctx.db, first).unwrap().unwrap();
+        let card = data::CardJson {
+            id: 0,
+            front: "slide".into(),
+            back: "b".into(),
+            tags: vec![],
+            source: None,
+            media: None,
+            deck: None,
+        };
+        let card_id = data::insert_card(&ctx.db, &card).unwrap();
+        data::jobs::complete_job(&ctx.db, first, card_id).unwrap();
+        let res = post(&format!("/jobs/{again}/resolve"), r#"{"reuse": true}"#.into()).reply(&routes).await;
+        let reply: JobReply = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!((reply.job.status, reply.job.card_id), (data::JobStatus::Done, Some(card_id)));
+        let res = post(&format!("/jobs/{again}/resolve"), r#"{"reuse": false}"#.into()).reply(&routes).await;
+        assert_eq!(res.status(), 404, "already resolved");
+    }
+}
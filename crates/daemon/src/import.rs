@@ -0,0 +1,150 @@
+//! Document imports: extract text, split it into sections and queue one
+//! generation job per section. Progress lives in `data::imports`, so an
+//! interrupted or repeated import only queues what is missing. Watched
+//! folders ([`watch`]) feed new files through the same path.
+
+use crate::error::INVALID_REQUEST;
+use crate::{pipeline, Context};
+use anyhow::{bail, Result};
+use capture::{CaptureEvent, Payload};
+use data::{imports, Import, JobPayload};
+use ingest::folder::{self, Kind};
+use ingest::{Document, Limits, Origin};
+use ipc::Event;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+use utils::error::ErrorPayload;
+use utils::source::Source;
+
+/// Files modified more recently than this may still be copying in.
+const SETTLE: Duration = Duration::from_secs(5);
+
+/// Import a PDF, saved web page, Markdown, text or image file, filing its
+/// cards into `deck`. Returns the import and how many sections this call
+/// queued.
+pub async fn file(ctx: &Context, path: PathBuf, deck: Option<String>) -> Result<(Import, usize)> {
+    let ocr = ctx.config().ocr;
+    // Text extraction and OCR of scanned pages are CPU-bound.
+    let doc = match folder::kind(&path) {
+        Some(Kind::Pdf) => tokio::task::spawn_blocking(move || Document::open_pdf(&path, &ocr)).await??,
+        Some(Kind::Text) => tokio::task::spawn_blocking(move || Document::open_text(&path)).await??,
+        Some(Kind::Html) => tokio::task::spawn_blocking(move || Document::open_html(&path)).await??,
+        Some(Kind::Image) => return image(ctx, path, deck).await,
+        None => bail!(ErrorPayload::new(INVALID_REQUEST, format!("cannot import {}", path.display()))),
+    };
+    queue(ctx, &doc, deck)
+}
+
+/// Fetch a web page and import its article, one card per section.
+pub async fn url(ctx: &Context, url: &str) -> Result<(Import, usize)> {
+    let html = pipeline::fetch(url).await?;
+    let origin = Origin::Web(url.to_string());
+    let doc = tokio::task::spawn_blocking(move || Document::from_html(&html, origin)).await??;
+    queue(ctx, &doc, None)
+}
+
+fn queue(ctx: &Context, doc: &Document, deck: Option<String>) -> Result<(Import, usize)> {
+    let chunks = doc.chunks(Limits::default());
+    let import = imports::begin_import(&ctx.db, &doc.location(), &doc.hash, chunks.len())?;
+    let done = imports::queued_chunks(&ctx.db, import.id)?;
+
+    let mut added = 0;
+    for chunk in chunks.iter().filter(|c| !done.contains(&c.index)) {
+        let text = match &chunk.heading {
+            Some(h) => format!("{h}\n\n{}", chunk.text),
+            None => chunk.text.clone(),
+        };
+        let payload = JobPayload::Text { text, source: Some(doc.source(chunk)), deck: deck.clone() };
+        if imports::enqueue_chunk(&ctx.db, import.id, chunk.index, &payload)?.is_some() {
+            added += 1;
+        }
+    }
+    wake(ctx, added);
+    info!(
+        document = %doc.name(),
+        sections = chunks.len(),
+        added,
+        "📄 Document imported"
+    );
+    Ok((Import { queued: import.queued + added, ..import }, added))
+}
+
+/// An image file is one capture: a single-section import, so it is only
+/// ever queued once.
+async fn image(ctx: &Context, path: PathBuf, deck: Option<String>) -> Result<(Import, usize)> {
+    let bytes = tokio::fs::read(&path).await?;
+    let import = imports::begin_import(&ctx.db, &path.to_string_lossy(), &ingest::content_hash(&bytes), 1)?;
+    if import.queued > 0 {
+        return Ok((import, 0));
+    }
+    let source = Source { url: Some(ingest::file_url(&path)), ..Source::now() };
+    let evt = CaptureEvent { deck, ..CaptureEvent::new(Payload::File(path.clone()), source) };
+    let payload = pipeline::prepare(evt).await?;
+    let added = usize::from(imports::enqueue_chunk(&ctx.db, import.id, 0, &payload)?.is_some());
+    wake(ctx, added);
+    info!(image = %path.display(), added, "🖼️ Image imported");
+    Ok((Import { queued: import.queued + added, ..import }, added))
+}
+
+fn wake(ctx: &Context, added: usize) {
+    if added > 0 {
+        ctx.emit(Event::Generating);
+        ctx.jobs.notify_one();
+    }
+}
+
+/// Poll `ingest.watch_dirs` and import new or changed files into the deck
+/// named after their folder. Files are recognised by content hash, so
+/// renames, copies and daemon restarts do not import anything twice.
+pub async fn watch(ctx: Context) {
+    // Size and mtime of every file already looked at, to avoid re-hashing.
+    let mut seen: HashMap<PathBuf, (SystemTime, u64)> = HashMap::new();
+    loop {
+        let cfg = ctx.config().ingest;
+        for root in &cfg.watch_dirs {
+            let dir = root.clone();
+            let files = match tokio::task::spawn_blocking(move || folder::scan(&dir)).await {
+                Ok(Ok(files)) => files,
+                Ok(Err(e)) => {
+                    warn!(dir = %root.display(), error = %e, "cannot read watched folder");
+                    continue;
+                }
+                Err(e) => {
+                    warn!(error = %e, "folder scan panicked");
+                    continue;
+                }
+            };
+            for path in files {
+                let Ok(meta) = std::fs::metadata(&path) else { continue };
+                let Ok(modified) = meta.modified() else { continue };
+                let stamp = (modified, meta.len());
+                if seen.get(&path) == Some(&stamp) {
+                    continue;
+                }
+                if modified.elapsed().is_ok_and(|age| age < SETTLE) {
+                    continue;
+                }
+                // Failures are not retried until the file changes.
+                seen.insert(path.clone(), stamp);
+                let deck = folder::deck(root, &path);
+                if let Err(e) = import_new(&ctx, &path, deck).await {
+                    warn!(file = %path.display(), error = %format!("{e:#}"), "watched file not imported");
+                }
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(cfg.poll_secs.max(1))).await;
+    }
+}
+
+async fn import_new(ctx: &Context, path: &Path, deck: String) -> Result<()> {
+    let hash = ingest::content_hash(&tokio::fs::read(path).await?);
+    if let Some(done) = imports::find_import(&ctx.db, &hash)?.filter(|i| i.queued >= i.chunks) {
+        debug!(file = %path.display(), import = done.id, "already imported");
+        return Ok(());
+    }
+    let (import, added) = file(ctx, path.to_path_buf(), Some(deck.clone())).await?;
+    info!(file = %path.display(), deck, import = import.id, added, "📂 Watched file imported");
+    Ok(())
+}
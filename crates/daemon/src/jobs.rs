@@ -0,0 +1,235 @@
+//! Background worker that drains the generation queue, retrying failed jobs
+//! with exponential backoff (or the provider's `Retry-After`) and moving
+//! hopeless ones to the dead-letter state.
+
+use crate::{dedupe, error, notify, pipeline, Context};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use data::jobs::{self as queue, Job, JobPayload};
+use data::{CardJson, Similar};
+use ipc::Event;
+use std::time::Duration;
+use tokio::select;
+use tracing::{info, warn};
+use utils::error::ErrorPayload;
+
+/// Attempts (excluding offline ones within `MAX_OFFLINE_AGE`) before a job is dead-lettered.
+pub const MAX_ATTEMPTS: u32 = 6;
+const BASE_DELAY: Duration = Duration::from_secs(5);
+const MAX_DELAY: Duration = Duration::from_secs(15 * 60);
+/// How often to probe for connectivity while the provider is unreachable.
+const OFFLINE_POLL: Duration = Duration::from_secs(30);
+/// How long a job may wait out an outage before offline attempts start to count.
+const MAX_OFFLINE_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Upper bound on idle sleep, so edits made by other processes are noticed.
+const IDLE_POLL: Duration = Duration::from_secs(60);
+
+/// Result of running a claimed job once.
+#[derive(Debug)]
+pub enum Attempt {
+    /// `similar` lists existing cards the new one may duplicate.
+    Created { card: CardJson, similar: Vec<Similar> },
+    /// Failed; `retry_at` is `None` once the job is dead-lettered.
+    Failed { error: ErrorPayload, retry_at: Option<DateTime<Utc>> },
+}
+
+/// What to do with a job after a failed attempt.
+#[derive(Debug, PartialEq)]
+enum Plan {
+    Retry { delay: Duration, count_attempt: bool },
+    Bury,
+}
+
+/// Exponential backoff: 5s, 10s, 20s … capped at 15 minutes.
+pub fn backoff(attempt: u32) -> Duration {
+    BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_DELAY)
+}
+
+/// `age` is how long ago the job was queued.
+fn plan(err: &ErrorPayload, attempts: u32, age: Duration) -> Plan {
+    if !err.retryable {
+        return Plan::Bury;
+    }
+    // Offline captures wait for connectivity without using up attempts, but
+    // only for so long: past `MAX_OFFLINE_AGE` they back off and die like any
+    // other failure.
+    if err.code == "llm.offline" && age < MAX_OFFLINE_AGE {
+        return Plan::Retry { delay: OFFLINE_POLL, count_attempt: false };
+    }
+    if attempts >= MAX_ATTEMPTS {
+        return Plan::Bury;
+    }
+    let delay = err.retry_after_secs.map(Duration::from_secs).unwrap_or_else(|| backoff(attempts));
+    Plan::Retry { delay, count_attempt: true }
+}
+
+/// Queue a payload and wake the worker. Returns the job id.
+pub fn submit(ctx: &Context, payload: &JobPayload) -> Result<i64> {
+    let id = queue::enqueue_job(&ctx.db, payload)?;
+    ctx.emit(Event::Generating);
+    ctx.jobs.notify_one();
+    Ok(id)
+}
+
+/// Queue a screenshot with its perceptual hash. One that looks like a capture
+/// taken inside `capture.duplicate_window_mins` is held rather than sent to
+/// the LLM, and the user is asked whether to reuse that capture's card.
+pub fn submit_capture(ctx: &Context, payload: &JobPayload, phash: u64) -> Result<i64> {
+    let cfg = ctx.config().capture;
+    let original = match cfg.duplicate_window_mins {
+        0 => None,
+        mins => {
+            let since = Utc::now() - chrono::Duration::minutes(mins.into());
+            // Newest first, so ties go to the latest capture.
+            queue::recent_captures(&ctx.db, since)?
+                .into_iter()
+                .map(|(job, hash)| (capture::phash::distance(hash, phash), job))
+                .filter(|(distance, _)| *distance <= cfg.duplicate_distance)
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(_, job)| job)
+        }
+    };
+    let Some(original) = original else {
+        let id = queue::enqueue_capture(&ctx.db, payload, phash, None)?;
+        ctx.emit(Event::Generating);
+        ctx.jobs.notify_one();
+        return Ok(id);
+    };
+
+    let id = queue::enqueue_capture(&ctx.db, payload, phash, Some(original.id))?;
+    info!(job = id, duplicate_of = original.id, "♻️ Screenshot looks like a recent capture; held");
+    let card = original.card_id.map(|card| data::fetch_card(&ctx.db, card)).transpose()?;
+    if !ctx.has_subscribers() {
+        notify::duplicate(ctx, id, card.as_ref());
+    }
+    ctx.emit(Event::DuplicateCapture { job_id: id, duplicate_of: original.id, card });
+    Ok(id)
+}
+
+/// Settle a held duplicate. `reuse` completes it with the earlier capture's
+/// card, and fails while there is none; otherwise it is queued for generation
+/// after all.
+pub fn resolve(ctx: &Context, id: i64, reuse: bool) -> Result<Job> {
+    if reuse {
+        let job = queue::reuse_job(&ctx.db, id)?;
+        info!(job = id, card = ?job.card_id, "♻️ Reusing the earlier capture's card");
+        return Ok(job);
+    }
+    queue::release_job(&ctx.db, id)?;
+    ctx.emit(Event::Generating);
+    ctx.jobs.notify_one();
+    Ok(queue::fetch_job(&ctx.db, id)?)
+}
+
+/// Run a freshly submitted job inline (for IPC callers waiting on a card).
+/// `None` when the worker already picked it up.
+pub async fn run_now(ctx: &Context, id: i64) -> Result<Option<Attempt>> {
+    match queue::claim_job(&ctx.db, id)? {
+        Some((job, payload)) => Ok(Some(attempt(ctx, job, payload).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Run a claimed job once and record the outcome in the queue.
+pub async fn attempt(ctx: &Context, job: Job, payload: JobPayload) -> Result<Attempt> {
+    let e = match pipeline::generate(ctx, &payload).await {
+        Ok(card) => {
+            queue::complete_job(&ctx.db, job.id, card.id)?;
+            let similar = dedupe::check(ctx, &card).await;
+            ctx.emit(Event::CardCreated { card: card.clone(), job_id: job.id, similar: similar.clone() });
+            return Ok(Attempt::Created { card, similar });
+        }
+        Err(e) => e,
+    };
+
+    let err = error::payload(&e);
+    let age = (Utc::now() - job.created_at).to_std().unwrap_or_default();
+    let retry_at = match plan(&err, job.attempts, age) {
+        Plan::Retry { delay, count_attempt } => {
+            let at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+            queue::defer_job(&ctx.db, job.id, at, &err.code, &err.message, count_attempt)?;
+            warn!(job = job.id, attempt = job.attempts, code = %err.code, retry_at = %at, "⏳ Generation failed; will retry");
+            Some(at)
+        }
+        Plan::Bury => {
+            queue::bury_job(&ctx.db, job.id, &err.code, &err.message)?;
+            warn!(job = job.id, code = %err.code, "🪦 Generation failed; job moved to dead-letter queue");
+            None
+        }
+    };
+    ctx.emit(Event::JobFailed { job_id: job.id, retry_at, error: err.clone() });
+    Ok(Attempt::Failed { error: err, retry_at })
+}
+
+/// Drain the queue forever, sleeping until the next job is due or a new one arrives.
+pub async fn run(ctx: Context) {
+    match queue::requeue_running_jobs(&ctx.db) {
+        Ok(0) => {}
+        Ok(n) => info!(n, "Re-queued jobs interrupted by the previous shutdown"),
+        Err(e) => warn!(error = %e, "could not re-queue interrupted jobs"),
+    }
+
+    loop {
+        match queue::claim_next_job(&ctx.db, Utc::now()) {
+            Ok(Some((job, payload))) => {
+                info!(job = job.id, attempt = job.attempts, "⚙️ Processing generation job");
+                if let Err(e) = attempt(&ctx, job, payload).await {
+                    warn!(error = %e, "job bookkeeping failed");
+                }
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => warn!(error = %e, "could not claim next job"),
+        }
+
+        let wait = match queue::next_job_due(&ctx.db) {
+            Ok(Some(at)) => (at - Utc::now()).to_std().unwrap_or_default().min(IDLE_POLL),
+            _ => IDLE_POLL,
+        };
+        select! {
+            _ = ctx.jobs.notified() => {}
+            _ = tokio::time::sleep(wait.max(Duration::from_secs(1))) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn err(code: &str, retryable: bool) -> ErrorPayload {
+        let mut e = ErrorPayload::new(code, "boom");
+        e.retryable = retryable;
+        e
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff(1), Duration::from_secs(5));
+        assert_eq!(backoff(3), Duration::from_secs(20));
+        assert_eq!(backoff(40), MAX_DELAY);
+    }
+
+    #[test]
+    fn plans_follow_error_class() {
+        let fresh = Duration::ZERO;
+        assert_eq!(plan(&err("llm.unauthorized", false), 1, fresh), Plan::Bury);
+        assert_eq!(
+            plan(&err("llm.offline", true), 99, fresh),
+            Plan::Retry { delay: OFFLINE_POLL, count_attempt: false }
+        );
+        let mut limited = err("llm.rate_limited", true);
+        limited.retry_after_secs = Some(42);
+        assert_eq!(plan(&limited, 2, fresh), Plan::Retry { delay: Duration::from_secs(42), count_attempt: true });
+        assert_eq!(plan(&err("db.locked", true), MAX_ATTEMPTS, fresh), Plan::Bury);
+    }
+
+    #[test]
+    fn offline_jobs_stop_waiting_after_max_age() {
+        let offline = err("llm.offline", true);
+        assert_eq!(plan(&offline, 2, MAX_OFFLINE_AGE), Plan::Retry { delay: backoff(2), count_attempt: true });
+        assert_eq!(plan(&offline, MAX_ATTEMPTS, MAX_OFFLINE_AGE), Plan::Bury);
+    }
+}
@@ -0,0 +1,244 @@
+//! Long-running Oakley process. Owns the database, the hot-key capture
+//! listener, the scheduler, the HTTP API and the IPC socket that the CLI and
+//! the Tauri shell talk to, so only one copy of the pipeline ever runs.
+
+pub mod answers;
+pub mod dedupe;
+pub mod error;
+mod http;
+pub mod import;
+pub mod jobs;
+mod notify;
+pub mod pipeline;
+mod server;
+pub mod voice;
+
+use anyhow::Result;
+use capture::hotkey::{Action, Bindings, Platform};
+use capture::CaptureEvent;
+use data::{DbPool, MediaStore};
+use ipc::Event;
+use scheduler::{DueSummary, ReminderPolicy, Reminders, Scheduler};
+use std::sync::Arc;
+use tokio::{select, sync::{broadcast, mpsc, watch, Notify}};
+use tracing::{error, info, warn};
+use utils::config::{CaptureConfig, Config, ConfigLoader};
+use utils::error::Coded;
+
+/// State shared by every daemon task.
+#[derive(Clone)]
+pub struct Context {
+    pub db: DbPool,
+    pub media: MediaStore,
+    pub config: watch::Receiver<Config>,
+    pub reminders: Reminders,
+    pub events: broadcast::Sender<Event>,
+    /// Wakes the job worker when work is queued.
+    pub jobs: Arc<Notify>,
+    /// Whether this daemon listens for global hot-keys itself. Fixed at
+    /// startup; a shell attaching to it must not bind them a second time.
+    pub hotkeys: bool,
+}
+
+impl Context {
+    /// Snapshot of the current (possibly hot-reloaded) configuration.
+    pub fn config(&self) -> Config {
+        self.config.borrow().clone()
+    }
+
+    /// Broadcast an event to all IPC subscribers (no-op when nobody listens).
+    pub fn emit(&self, evt: Event) {
+        let _ = self.events.send(evt);
+    }
+
+    /// Whether any client is currently subscribed to events.
+    pub fn has_subscribers(&self) -> bool {
+        self.events.receiver_count() > 0
+    }
+
+    /// Bytes and content type of a stored screenshot, or of its thumbnail.
+    pub fn read_media(&self, hash: &str, thumb: bool) -> data::Result<(Vec<u8>, &'static str)> {
+        let m = self.media.get(&self.db, hash)?;
+        let (path, content_type) =
+            if thumb { (self.media.thumb_path(&m), "image/png") } else { (self.media.path(&m), m.format.content_type()) };
+        match std::fs::read(path) {
+            Ok(bytes) => Ok((bytes, content_type)),
+            // The row outlived its file; treat it like any other missing media.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(data::Error::NotFound(format!("media {hash}"))),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Context over a fresh database and media store in `dir`.
+    #[cfg(test)]
+    pub(crate) fn for_tests(dir: &std::path::Path, config: Config) -> Self {
+        let db = data::new_pool(dir.join("t.db").to_str().unwrap()).unwrap();
+        Self {
+            db,
+            media: MediaStore::open(dir.join("media")).unwrap(),
+            config: watch::channel(config).1,
+            reminders: Reminders::new(ReminderPolicy::default()),
+            events: broadcast::channel(8).0,
+            jobs: Default::default(),
+            hotkeys: false,
+        }
+    }
+}
+
+/// Run the daemon until Ctrl-C. The config file is watched for hot-reloadable edits.
+pub async fn run(loader: ConfigLoader) -> Result<()> {
+    let cfg = loader.load()?;
+    info!(config = %loader.path().display(), db = %cfg.db_path.display(), "Loaded configuration");
+    ocr::check(&cfg.ocr)?;
+
+    // ── bootstrap shared state ──
+    if let Some(dir) = cfg.db_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let db = data::new_pool(&cfg.db_path.to_string_lossy())?;
+    let media = MediaStore::open(&cfg.media_dir)?;
+    let (events, _) = broadcast::channel::<Event>(64);
+    let reminders = Reminders::new(ReminderPolicy::from(&cfg.reminders));
+    let config = loader.watch(cfg.clone());
+    let ctx = Context {
+        db: db.clone(),
+        media,
+        config: config.clone(),
+        reminders: reminders.clone(),
+        events,
+        jobs: Arc::new(Notify::new()),
+        hotkeys: cfg.hotkeys.enabled,
+    };
+
+    // Bind first so a second daemon fails fast instead of duplicating work.
+    let listener = server::bind(&cfg.socket_path).await?;
+
+    let (cap_tx, mut cap_rx) = mpsc::channel::<CaptureEvent>(16);
+    let (due_tx, mut due_rx) = mpsc::channel::<DueSummary>(4);
+
+    // ── task: global hot-keys ──
+    let (key_tx, mut key_rx) = mpsc::channel::<Action>(8);
+    if cfg.hotkeys.enabled {
+        // Reject typos and clashes at startup; reloads only log them.
+        Bindings::from_config(&cfg.hotkeys, Platform::current())?;
+        let config = config.clone();
+        tokio::spawn(async move {
+            if let Err(e) = capture::listen_hotkeys(key_tx, config).await {
+                error!(error = %e, "hot-key listener stopped");
+            }
+        });
+    }
+
+    // ── task: clipboard watch mode (idle unless `capture.clipboard_watch`) ──
+    {
+        let (config, cap_tx) = (config.clone(), cap_tx.clone());
+        tokio::spawn(async move {
+            if let Err(e) = capture::clipboard::watch(cap_tx, config).await {
+                warn!(error = %e, "clipboard watch stopped");
+            }
+        });
+    }
+
+    // ── task: generation queue (retries, offline captures) ──
+    tokio::spawn(jobs::run(ctx.clone()));
+
+    // ── task: watched folders (idle while `ingest.watch_dirs` is empty) ──
+    tokio::spawn(import::watch(ctx.clone()));
+
+    // ── task: drop screenshots no card references any more ──
+    tokio::spawn(collect_media(ctx.clone()));
+
+    // ── task: scheduler tick ──
+    tokio::spawn(Scheduler::new(db, config, reminders, due_tx).run());
+
+    // ── task: HTTP JSON endpoint for external browsers ──
+    if cfg.http.enabled {
+        tokio::spawn(http::serve(ctx.clone(), cfg.http.port));
+    }
+
+    // ── task: IPC socket ──
+    tokio::spawn(server::serve(ctx.clone(), listener));
+    info!(socket = %cfg.socket_path.display(), "🌳 Oakley daemon ready");
+
+    // ── main bus ──
+    loop {
+        select! {
+            Some(evt) = cap_rx.recv() => queue(&ctx, evt),
+            Some(action) = key_rx.recv() => on_hotkey(&ctx, action, &cap_tx),
+            Some(summary) = due_rx.recv() => {
+                info!(due = summary.due, "⏰ Cards due – sending reminder");
+                notify::due(&ctx, &summary);
+                ctx.emit(Event::CardsDue { summary });
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("shutting down");
+                break;
+            }
+        }
+    }
+
+    let _ = std::fs::remove_file(&cfg.socket_path);
+    Ok(())
+}
+
+/// Run a hot-key action. Captures may block on user interaction, so they run
+/// on the blocking pool and re-enter the main bus as capture events.
+fn on_hotkey(ctx: &Context, action: Action, cap_tx: &mpsc::Sender<CaptureEvent>) {
+    let grab: fn(&CaptureConfig) -> capture::Result<CaptureEvent> = match action {
+        Action::CaptureScreen => capture::capture_screen_with,
+        Action::CaptureWindow => capture::capture_window_with,
+        Action::StartReview => return ctx.emit(Event::ReviewRequested),
+        Action::CaptureClipboard => |_| capture::clipboard::read(),
+        Action::CaptureSelection => {
+            warn!("hot-key {action} is handled by the desktop app, not the daemon");
+            return;
+        }
+    };
+    let cfg = ctx.config().capture;
+    let (ctx, cap_tx) = (ctx.clone(), cap_tx.clone());
+    tokio::spawn(async move {
+        let captured = tokio::task::spawn_blocking(move || grab(&cfg))
+            .await
+            .unwrap_or_else(|e| Err(capture::Error::Failed(e.to_string())));
+        match captured {
+            Ok(evt) => {
+                let _ = cap_tx.send(evt).await;
+            }
+            Err(capture::Error::Cancelled) => info!("capture cancelled"),
+            Err(capture::Error::EmptyClipboard) => warn!("clipboard holds no text or image"),
+            Err(e) => {
+                error!(error = %e, "{action} failed");
+                ctx.emit(Event::Error { error: e.payload() });
+            }
+        }
+    });
+}
+
+/// Prepare and queue a capture off the main bus (file reads, fetches and
+/// PNG encoding can take a while).
+fn queue(ctx: &Context, evt: CaptureEvent) {
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        if let Err(e) = pipeline::submit(&ctx, evt).await {
+            error!(error = %e, "could not queue capture");
+            ctx.emit(Event::Error { error: error::payload(&e) });
+        }
+    });
+}
+
+/// Hourly media garbage collection; the store keeps recent orphans for a grace period.
+async fn collect_media(ctx: Context) {
+    let mut tick = tokio::time::interval(std::time::Duration::from_secs(3600));
+    loop {
+        tick.tick().await;
+        let (media, db) = (ctx.media.clone(), ctx.db.clone());
+        match tokio::task::spawn_blocking(move || media.gc(&db, chrono::Utc::now())).await {
+            Ok(Ok(stats)) if stats.files > 0 || stats.bytes > 0 => {
+                info!(files = stats.files, bytes = stats.bytes, "🧹 Removed unreferenced media")
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => warn!(error = %e, "media collection failed"),
+            Err(e) => warn!(error = %e, "media collection panicked"),
+        }
+    }
+}
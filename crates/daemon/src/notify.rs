@@ -0,0 +1,106 @@
+//! Desktop notifications via `notify-rust`.
+
+use crate::Context;
+use data::CardJson;
+use notify_rust::Notification;
+use scheduler::DueSummary;
+use tracing::warn;
+
+/// Fire a system notification so the user knows a card was created.
+pub fn card_saved(id: i64) {
+    let _ = Notification::new()
+        .summary("Oakley – Card Saved")
+        .body(&format!("Card #{id} created."))
+        .icon("dialog-information")
+        .show();
+}
+
+/// Show a notification summarising due cards. Where the notification server
+/// supports actions, "Review now" broadcasts [`ipc::Event::ReviewRequested`] to
+/// connected clients and "Snooze" silences reminders for an hour.
+pub fn due(ctx: &Context, summary: &DueSummary) {
+    let body = match summary.due {
+        1 => "1 card is due for review.".to_string(),
+        n => format!("{n} cards are due for review."),
+    };
+    let mut notification = Notification::new();
+    notification
+        .summary("Oakley – Time to review")
+        .body(&body)
+        .icon("dialog-information")
+        .action("review", "Review now")
+        .action("snooze", "Snooze 1h");
+
+    let handle = match notification.show() {
+        Ok(h) => h,
+        Err(e) => {
+            warn!(?e, "failed to show due-card notification");
+            return;
+        }
+    };
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            handle.wait_for_action(|action| match action {
+                "review" | "default" => ctx.emit(ipc::Event::ReviewRequested),
+                "snooze" => ctx.reminders.snooze(chrono::Duration::hours(1)),
+                _ => {}
+            });
+        });
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    {
+        // No action callbacks on this platform; the user starts reviews from the app.
+        let _ = (handle, ctx);
+    }
+}
+
+/// Ask what to do with a screenshot held as a near-duplicate. Without action
+/// support the job stays held until resolved from the CLI or the app.
+pub fn duplicate(ctx: &Context, job_id: i64, card: Option<&CardJson>) {
+    let body = match card {
+        Some(card) => format!("This looks like the capture behind card #{}: “{}”.", card.id, card.front),
+        None => "This looks like a capture you just took; its card is still being made.".to_string(),
+    };
+    let mut notification = Notification::new();
+    notification.summary("Oakley – Already captured").body(&body).icon("dialog-information");
+    // Reuse needs the earlier card; until it exists the capture stays held.
+    if card.is_some() {
+        notification.action("reuse", "Use existing card");
+    }
+    notification.action("generate", "Create new card");
+
+    let handle = match notification.show() {
+        Ok(h) => h,
+        Err(e) => {
+            warn!(?e, "failed to show duplicate-capture notification");
+            return;
+        }
+    };
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let (ctx, reusable) = (ctx.clone(), card.is_some());
+        std::thread::spawn(move || {
+            handle.wait_for_action(|action| {
+                let resolved = match action {
+                    "reuse" => crate::jobs::resolve(&ctx, job_id, true),
+                    "default" if reusable => crate::jobs::resolve(&ctx, job_id, true),
+                    "generate" => crate::jobs::resolve(&ctx, job_id, false),
+                    _ => return,
+                };
+                if let Err(e) = resolved {
+                    warn!(error = %e, job_id, "could not resolve duplicate capture");
+                }
+            });
+        });
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    {
+        let _ = (handle, ctx, job_id);
+    }
+}
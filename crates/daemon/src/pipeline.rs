@@ -0,0 +1,218 @@
+//! Capture → LLM → DB pipeline. Every entry point (hot-keys, clipboard, IPC,
+//! HTTP) hands a [`CaptureEvent`] to [`submit`]; the job worker then runs
+//! [`generate`].
+
+use crate::error::INVALID_REQUEST;
+use crate::{jobs, notify, Context};
+use anyhow::{bail, Context as _, Result};
+use capture::{CaptureEvent, Payload};
+use data::{CardJson, JobPayload};
+use image::ImageEncoder;
+use llm::{gen_card, gen_card_from_image, CardFields};
+use std::path::Path;
+use tracing::{info, warn};
+use utils::config::OcrConfig;
+use utils::error::ErrorPayload;
+use utils::source::Source;
+
+/// Queue a capture for generation and wake the worker. Returns the job id.
+/// Queueing first lets a capture survive LLM failures and restarts.
+/// Screenshots are hashed first so near-duplicates can be held back.
+pub async fn submit(ctx: &Context, evt: CaptureEvent) -> Result<i64> {
+    info!(kind = evt.kind(), display = ?evt.display.map(|d| d.id), "📥 Capture received");
+    let (evt, phash) = tokio::task::spawn_blocking(move || {
+        let phash = evt.phash();
+        (evt, phash)
+    })
+    .await?;
+    let payload = prepare(evt).await?;
+    match phash {
+        Some(phash) => jobs::submit_capture(ctx, &payload, phash),
+        None => jobs::submit(ctx, &payload),
+    }
+}
+
+/// Reduce a capture to what the generator takes: PNG bytes or plain text.
+pub async fn prepare(evt: CaptureEvent) -> Result<JobPayload> {
+    let (mut source, deck) = (evt.source, evt.deck);
+    match evt.payload {
+        Payload::Image { image, .. } => image_job(image, source, deck).await,
+        Payload::Text(text) => text_job(text, source, deck),
+        Payload::Html(html) => text_job(utils::html::to_text(&html), source, deck),
+        Payload::File(path) => file_job(&path, source, deck).await,
+        Payload::Url(url) => {
+            let html = fetch(&url).await?;
+            source.url = Some(url);
+            text_job(ingest::web::article(&html).text(), source, deck)
+        }
+    }
+}
+
+fn text_job(text: String, source: Source, deck: Option<String>) -> Result<JobPayload> {
+    if text.trim().is_empty() {
+        bail!(ErrorPayload::new(INVALID_REQUEST, "no text to generate a card from"));
+    }
+    Ok(JobPayload::Text { text, source: Some(source), deck })
+}
+
+async fn image_job(image: image::RgbaImage, source: Source, deck: Option<String>) -> Result<JobPayload> {
+    // Always build PNG from in-memory image to avoid temp-file lifetime issues.
+    let png = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+        let mut png = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut png).write_image(
+            &image,
+            image.width(),
+            image.height(),
+            image::ColorType::Rgba8,
+        )?;
+        Ok(png)
+    })
+    .await??;
+    info!(size = png.len(), "📸 Screenshot bytes prepared");
+    Ok(JobPayload::Image { png, source: Some(source), deck })
+}
+
+/// Images by content, HTML by extension, anything else must be UTF-8 text.
+async fn file_job(path: &Path, mut source: Source, deck: Option<String>) -> Result<JobPayload> {
+    let bytes = tokio::fs::read(path).await.with_context(|| format!("reading {}", path.display()))?;
+    if source.window_title.is_none() {
+        source.window_title = path.file_name().map(|n| n.to_string_lossy().into_owned());
+    }
+    match image::guess_format(&bytes) {
+        Ok(image::ImageFormat::Png) => return Ok(JobPayload::Image { png: bytes, source: Some(source), deck }),
+        Ok(_) => {
+            let image = image::load_from_memory(&bytes)?.to_rgba8();
+            return image_job(image, source, deck).await;
+        }
+        Err(_) => {}
+    }
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match String::from_utf8(bytes) {
+        Ok(html) if ext == "html" || ext == "htm" => text_job(utils::html::to_text(&html), source, deck),
+        Ok(text) => text_job(text, source, deck),
+        Err(_) => bail!(ErrorPayload::new(INVALID_REQUEST, format!("{} is neither an image nor text", path.display()))),
+    }
+}
+
+pub(crate) async fn fetch(url: &str) -> Result<String> {
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        bail!(ErrorPayload::new(INVALID_REQUEST, format!("not a web address: {url}")));
+    }
+    let res = reqwest::get(url).await.with_context(|| format!("fetching {url}"))?.error_for_status()?;
+    Ok(res.text().await?)
+}
+
+/// Call the LLM for a queued payload and store the resulting card.
+pub async fn generate(ctx: &Context, payload: &JobPayload) -> Result<CardJson> {
+    let cfg = ctx.config().llm;
+    match payload {
+        JobPayload::Image { png, source, deck } => {
+            let ocr = ctx.config().ocr;
+            let text = screenshot_text(png, &ocr).await?;
+            let fields = if !text.trim().is_empty() {
+                // Only the recognised text leaves the machine.
+                info!(length = text.len(), "Generating card from OCR text");
+                gen_card(&text, &cfg).await?
+            } else if !ocr.enabled || ocr.fallback_to_vision {
+                gen_card_from_image(png, &cfg).await?
+            } else {
+                return Err(ocr::Error::NoText.into());
+            };
+            info!("🧠 Generated card from image");
+            // Keep the screenshot itself; temp-file paths do not outlive the capture.
+            let media = ctx.media.put(&ctx.db, png)?;
+            store(ctx, fields, Some(media.hash), source.clone(), deck.clone())
+        }
+        JobPayload::Text { text, source, deck } => {
+            info!(length = text.len(), "Generating card from text");
+            let fields = gen_card(text, &cfg).await?;
+            store(ctx, fields, None, source.clone(), deck.clone())
+        }
+    }
+}
+
+/// Text to generate a screenshot's card from; empty when OCR is off or finds
+/// nothing. With `fallback_to_vision` a failing engine also counts as no text,
+/// so the image still reaches the vision model.
+async fn screenshot_text(png: &[u8], cfg: &OcrConfig) -> Result<String> {
+    if !cfg.enabled {
+        return Ok(String::new());
+    }
+    match read_text(png, cfg).await {
+        Err(e) if cfg.fallback_to_vision && e.downcast_ref::<ocr::Error>().is_some() => {
+            warn!(error = %e, "OCR failed; sending the screenshot to the vision model");
+            Ok(String::new())
+        }
+        read => read,
+    }
+}
+
+/// OCR a PNG off the async runtime; returns only words above the confidence floor.
+async fn read_text(png: &[u8], cfg: &OcrConfig) -> Result<String> {
+    let png = png.to_vec();
+    let cfg = cfg.clone();
+    tokio::task::spawn_blocking(move || -> Result<String> {
+        let img = image::load_from_memory(&png)?.to_rgba8();
+        let engine = ocr::engine(&cfg.language)?;
+        Ok(ocr::recognize(engine.as_ref(), &img)?.text(cfg.min_confidence))
+    })
+    .await?
+}
+
+fn store(
+    ctx: &Context,
+    fields: CardFields,
+    media: Option<String>,
+    source: Option<Source>,
+    deck: Option<String>,
+) -> Result<CardJson> {
+    let mut card = CardJson { id: 0, front: fields.front, back: fields.back, tags: fields.tags, source, media, deck };
+    card.id = data::insert_card(&ctx.db, &card)?;
+    info!(id = card.id, "🧠 Card saved");
+
+    // A subscribed UI shows its own modal; otherwise fall back to a desktop notification.
+    if !ctx.has_subscribers() {
+        notify::card_saved(card.id);
+    }
+    Ok(card)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn prepares_files_and_html_as_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let page = dir.path().join("notes.html");
+        std::fs::write(&page, "<h1>Title</h1><p>Body &amp; more</p>").unwrap();
+
+        let evt = CaptureEvent::new(Payload::File(page), Source::now());
+        match prepare(evt).await.unwrap() {
+            JobPayload::Text { text, source, .. } => {
+                assert_eq!(text, "Title\n\nBody & more");
+                assert_eq!(source.unwrap().window_title.as_deref(), Some("notes.html"));
+            }
+            other => panic!("expected text, got {other:?}"),
+        }
+
+        let blank = CaptureEvent::new(Payload::Html("<p> </p>".into()), Source::now());
+        let err = prepare(blank).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<ErrorPayload>().unwrap().code, INVALID_REQUEST);
+    }
+
+    #[tokio::test]
+    #[cfg(not(feature = "tesseract"))]
+    async fn missing_ocr_engine_falls_back_to_vision_when_allowed() {
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image::RgbaImage::new(8, 8))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .unwrap();
+        let mut cfg = OcrConfig { enabled: true, ..OcrConfig::default() };
+        let err = screenshot_text(&png, &cfg).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ocr::Error>(), Some(ocr::Error::Unavailable)));
+
+        cfg.fallback_to_vision = true;
+        assert_eq!(screenshot_text(&png, &cfg).await.unwrap(), "");
+    }
+}
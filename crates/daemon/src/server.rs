@@ -0,0 +1,230 @@
+//! Unix-domain-socket IPC server.
+
+use crate::error::{self as err, INVALID_REQUEST};
+use crate::jobs::{self, Attempt};
+use crate::{answers, dedupe, import, pipeline, voice, Context};
+use anyhow::{bail, Result};
+use base64::Engine as _;
+use chrono::Utc;
+use capture::{CaptureEvent, Payload};
+use data::{Job, JobStatus};
+use ipc::{read_frame, write_frame, Request, Response};
+use scheduler::{LearningSteps, QueueBuilder, ReviewOutcome, Scheduler};
+use std::path::Path;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, warn};
+use utils::clock::StudyClock;
+use utils::error::ErrorPayload;
+use utils::source::Source;
+
+/// Bind the socket, refusing to start when another daemon is already serving it.
+pub async fn bind(path: &Path) -> Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            bail!("an oakley daemon is already listening on {}", path.display());
+        }
+        // Stale socket left behind by a crashed daemon.
+        std::fs::remove_file(path)?;
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(UnixListener::bind(path)?)
+}
+
+/// Accept connections forever.
+pub async fn serve(ctx: Context, listener: UnixListener) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let ctx = ctx.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_conn(ctx, stream).await {
+                        debug!(?e, "ipc connection closed with error");
+                    }
+                });
+            }
+            Err(e) => {
+                error!(?e, "ipc accept failed");
+                break;
+            }
+        }
+    }
+}
+
+async fn handle_conn(ctx: Context, stream: UnixStream) -> Result<()> {
+    let (mut rd, mut wr) = stream.into_split();
+    while let Some(req) = read_frame::<_, Request>(&mut rd).await? {
+        if let Request::Subscribe = req {
+            let mut events = ctx.events.subscribe();
+            write_frame(&mut wr, &Response::Subscribed).await?;
+            loop {
+                match events.recv().await {
+                    Ok(evt) => write_frame(&mut wr, &evt).await?,
+                    Err(RecvError::Lagged(n)) => warn!(n, "ipc subscriber lagged; events dropped"),
+                    Err(RecvError::Closed) => return Ok(()),
+                }
+            }
+        }
+
+        let resp = handle(&ctx, req).await.unwrap_or_else(|e| {
+            debug!(error = %e, "ipc request failed");
+            Response::Error { error: err::payload(&e) }
+        });
+        write_frame(&mut wr, &resp).await?;
+    }
+    Ok(())
+}
+
+async fn handle(ctx: &Context, req: Request) -> Result<Response> {
+    Ok(match req {
+        Request::Ping => Response::Pong { version: env!("CARGO_PKG_VERSION").into(), hotkeys: ctx.hotkeys },
+        Request::ListCards => Response::Cards { cards: data::fetch_all_cards(&ctx.db)? },
+        Request::DueCards => {
+            Response::Cards { cards: QueueBuilder::from_config(&ctx.config()).build(&ctx.db, Utc::now())? }
+        }
+        Request::DedupeCards { threshold } => Response::Duplicates { pairs: dedupe::report(ctx, threshold).await? },
+        Request::MergeCards { keep, duplicate } => {
+            Response::Card { card: dedupe::merge(ctx, keep, duplicate)?, similar: Vec::new() }
+        }
+        Request::Grade { card_id, passed, grade, duration_ms } => {
+            let outcome = ReviewOutcome { card_id, passed, reviewed_at: Utc::now(), grade, duration_ms };
+            let steps = LearningSteps::from_config(&ctx.config().scheduler);
+            Response::Graded { next_due: Scheduler::record(&ctx.db, &steps, &outcome)? }
+        }
+        Request::Stats { days } => {
+            let days = days.unwrap_or(data::stats::DEFAULT_DAYS);
+            let clock = StudyClock::from_config(&ctx.config().scheduler);
+            Response::Stats { stats: data::stats::stats(&ctx.db, Utc::now(), &clock, days)? }
+        }
+        Request::CheckAnswer { card_id, answer } => {
+            Response::Checked { verdict: answers::check(ctx, card_id, &answer).await? }
+        }
+        Request::Transcribe { wav } => {
+            if let Some(path) = wav.as_ref().filter(|p| !p.is_absolute()) {
+                bail!(ErrorPayload::new(INVALID_REQUEST, format!("path must be absolute: {}", path.display())));
+            }
+            Response::Transcript { text: voice::transcribe(ctx, wav).await? }
+        }
+        Request::Speak { text } => Response::Spoken { path: voice::speak(ctx, text).await? },
+        Request::GenerateFromText { text, source } => {
+            generate(ctx, CaptureEvent::new(Payload::Text(text), source.unwrap_or_else(Source::now))).await?
+        }
+        Request::CaptureScreen => {
+            // screencapture -i blocks until the user finishes selecting.
+            let cfg = ctx.config().capture;
+            generate(ctx, tokio::task::spawn_blocking(move || capture::capture_screen_with(&cfg)).await??).await?
+        }
+        Request::CaptureWindow => {
+            let cfg = ctx.config().capture;
+            generate(ctx, tokio::task::spawn_blocking(move || capture::capture_window_with(&cfg)).await??).await?
+        }
+        Request::CaptureClipboard => {
+            generate(ctx, tokio::task::spawn_blocking(capture::clipboard::read).await??).await?
+        }
+        Request::ImportFile { path } => {
+            if !path.is_absolute() {
+                bail!(ErrorPayload::new(INVALID_REQUEST, format!("path must be absolute: {}", path.display())));
+            }
+            let (import, added) = import::file(ctx, path, None).await?;
+            Response::Imported { import, added }
+        }
+        Request::ImportUrl { url } => {
+            let (import, added) = import::url(ctx, &url).await?;
+            Response::Imported { import, added }
+        }
+        Request::SnoozeReminders { minutes } => {
+            ctx.reminders.snooze(chrono::Duration::minutes(minutes));
+            Response::Ok
+        }
+        Request::Media { hash, thumb } => {
+            let (bytes, content_type) = ctx.read_media(&hash, thumb)?;
+            let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+            Response::Media { content_type: content_type.into(), data }
+        }
+        Request::ListJobs { status } => Response::Jobs { jobs: data::jobs::list_jobs(&ctx.db, status)? },
+        Request::RetryJobs { id } => {
+            let count = match id {
+                Some(id) => data::jobs::retry_job(&ctx.db, id).map(|_| 1)?,
+                None => data::jobs::retry_dead_jobs(&ctx.db)?,
+            };
+            ctx.jobs.notify_one();
+            Response::Retried { count }
+        }
+        Request::ResolveDuplicate { job_id, reuse: true } => {
+            let job = jobs::resolve(ctx, job_id, true)?;
+            let card = job.card_id.ok_or_else(|| data::Error::NotFound(format!("card of job #{job_id}")))?;
+            Response::Card { card: data::fetch_card(&ctx.db, card)?, similar: Vec::new() }
+        }
+        Request::ResolveDuplicate { job_id, reuse: false } => {
+            jobs::resolve(ctx, job_id, false)?;
+            finish(ctx, job_id).await?
+        }
+        Request::Subscribe => unreachable!("handled by handle_conn"),
+    })
+}
+
+/// Queue a capture and try it right away so online callers get their card back.
+async fn generate(ctx: &Context, evt: CaptureEvent) -> Result<Response> {
+    let job_id = pipeline::submit(ctx, evt).await?;
+    finish(ctx, job_id).await
+}
+
+/// Run a queued job inline, or say why it did not produce a card yet.
+async fn finish(ctx: &Context, job_id: i64) -> Result<Response> {
+    Ok(match jobs::run_now(ctx, job_id).await? {
+        Some(Attempt::Created { card, similar }) => Response::Card { card, similar },
+        Some(Attempt::Failed { error, retry_at: None }) => bail!(error),
+        Some(Attempt::Failed { error, retry_at }) => Response::Queued { job_id, retry_at, error: Some(error) },
+        None => match data::jobs::fetch_job(&ctx.db, job_id)? {
+            Job { status: JobStatus::Held, duplicate_of: Some(original), .. } => {
+                let card = data::jobs::fetch_job(&ctx.db, original)?.card_id;
+                let card = card.map(|card| data::fetch_card(&ctx.db, card)).transpose()?;
+                Response::Duplicate { job_id, duplicate_of: original, card }
+            }
+            _ => Response::Queued { job_id, retry_at: None, error: None },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn serves_requests_over_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Context::for_tests(dir.path(), utils::config::Config::default());
+
+        let sock = dir.path().join("oakley.sock");
+        let listener = bind(&sock).await.unwrap();
+        tokio::spawn(serve(ctx, listener));
+        assert!(bind(&sock).await.is_err(), "second daemon must not bind");
+
+        let mut client = ipc::Client::connect(&sock).await.unwrap();
+        assert!(matches!(client.call(&Request::Ping).await.unwrap(), Response::Pong { hotkeys: false, .. }));
+        let card = match client.call(&Request::GenerateFromText { text: "Rust ownership".into(), source: None }).await.unwrap() {
+            Response::Card { card, .. } => card,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        let again = client.call(&Request::GenerateFromText { text: "Rust ownership".into(), source: None }).await.unwrap();
+        let again = match again {
+            Response::Card { card: again, similar } => {
+                assert_eq!(similar.iter().map(|s| s.card.id).collect::<Vec<_>>(), [card.id]);
+                again
+            }
+            other => panic!("unexpected response: {other:?}"),
+        };
+        match client.call(&Request::MergeCards { keep: card.id, duplicate: again.id }).await.unwrap() {
+            Response::Card { card: merged, .. } => assert_eq!(merged.id, card.id),
+            other => panic!("unexpected response: {other:?}"),
+        }
+        match client.call(&Request::ListCards).await.unwrap() {
+            Response::Cards { cards } => assert_eq!(cards[0].id, card.id),
+            other => panic!("unexpected response: {other:?}"),
+        }
+        let err = client.call(&Request::GenerateFromText { text: " ".into(), source: None }).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<ErrorPayload>().unwrap().code, INVALID_REQUEST);
+    }
+}
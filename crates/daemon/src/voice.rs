@@ -0,0 +1,52 @@
+//! Spoken answers and cards read aloud. The daemon records from the
+//! microphone (only with `speech.microphone` on) or reads a WAV file, and
+//! transcribes with the whisper model, which is loaded on first use and kept
+//! until the configured model changes. Speech goes through the `[tts]` engine.
+
+use crate::Context;
+use anyhow::Result;
+use speech::{Audio, Transcriber};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use utils::config::SpeechConfig;
+
+/// The loaded model and the settings it was loaded with.
+static MODEL: Mutex<Option<(SpeechConfig, Arc<dyn Transcriber>)>> = Mutex::new(None);
+
+/// Record an answer, or read `wav`, and return what was said.
+pub async fn transcribe(ctx: &Context, wav: Option<PathBuf>) -> Result<String> {
+    let cfg = ctx.config().speech;
+    let text = tokio::task::spawn_blocking(move || -> Result<String> {
+        let audio = match wav {
+            Some(path) => Audio::read_wav(&path)?,
+            None => speech::record(&cfg)?,
+        };
+        Ok(speech::transcribe(model(&cfg)?.as_ref(), &audio)?)
+    })
+    .await??;
+    Ok(text)
+}
+
+/// Read `text` aloud and return once it has been spoken; with
+/// `tts.output_dir` set, returns the WAV file written instead.
+pub async fn speak(ctx: &Context, text: String) -> Result<Option<PathBuf>> {
+    let cfg = ctx.config().tts;
+    let path = tokio::task::spawn_blocking(move || {
+        let dir = Some(cfg.output_dir.as_path()).filter(|d| *d != Path::new(""));
+        speech::tts::say(speech::tts::synthesizer(&cfg).as_ref(), &text, dir)
+    })
+    .await??;
+    Ok(path)
+}
+
+fn model(cfg: &SpeechConfig) -> speech::Result<Arc<dyn Transcriber>> {
+    let mut slot = MODEL.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((loaded, model)) = slot.as_ref() {
+        if loaded.model == cfg.model && loaded.language == cfg.language && loaded.threads == cfg.threads {
+            return Ok(model.clone());
+        }
+    }
+    let model: Arc<dyn Transcriber> = speech::transcriber(cfg)?.into();
+    *slot = Some((cfg.clone(), model.clone()));
+    Ok(model)
+}
@@ -0,0 +1,179 @@
+//! Embedding vectors of cards (front and back together), for finding cards
+//! that ask the same thing in different words. Search is a linear cosine scan,
+//! which stays fast for personal collections of tens of thousands of cards.
+
+use crate::{CardJson, DbPool, Result, CARD_COLUMNS};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+pub(crate) const SCHEMA: &str = r#"
+    CREATE TABLE embeddings (
+        card_id INTEGER PRIMARY KEY REFERENCES cards(id),
+        model   TEXT    NOT NULL,
+        vector  BLOB    NOT NULL
+    );
+"#;
+
+/// A card close to the one being checked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Similar {
+    pub card: CardJson,
+    /// Cosine similarity, 1 for identical wording.
+    pub similarity: f32,
+}
+
+/// Two cards above the duplicate threshold; `a` is the older one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicatePair {
+    pub a: CardJson,
+    pub b: CardJson,
+    pub similarity: f32,
+}
+
+/// What a card is embedded as.
+pub fn card_text(card: &CardJson) -> String {
+    format!("{}\n{}", card.front, card.back)
+}
+
+/// Store the vector of a card, replacing any earlier one; after a model
+/// change cards are re-embedded one by one.
+pub fn put_embedding(pool: &DbPool, card_id: i64, model: &str, vector: &[f32]) -> Result<()> {
+    let conn = pool.get()?;
+    let blob: Vec<u8> = vector.iter().flat_map(|x| x.to_le_bytes()).collect();
+    conn.execute(
+        "INSERT OR REPLACE INTO embeddings (card_id, model, vector) VALUES (?1, ?2, ?3)",
+        params![card_id, model, blob],
+    )?;
+    Ok(())
+}
+
+/// Cards with no vector from `model` yet, oldest first.
+pub fn unembedded_cards(pool: &DbPool, model: &str) -> Result<Vec<CardJson>> {
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {CARD_COLUMNS} FROM cards
+         WHERE id NOT IN (SELECT card_id FROM embeddings WHERE model = ?1) ORDER BY id"
+    ))?;
+    let rows = stmt.query_map([model], crate::card_from_row)?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+/// Cards at least `threshold` similar to `vector`, most similar first.
+pub fn similar_cards(pool: &DbPool, model: &str, vector: &[f32], threshold: f32, exclude: i64) -> Result<Vec<Similar>> {
+    let mut hits: Vec<(i64, f32)> = vectors(pool, model)?
+        .into_iter()
+        .filter(|(id, _)| *id != exclude)
+        .map(|(id, v)| (id, cosine(vector, &v)))
+        .filter(|(_, s)| *s >= threshold)
+        .collect();
+    hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+    hits.into_iter()
+        .map(|(id, similarity)| Ok(Similar { card: crate::fetch_card(pool, id)?, similarity }))
+        .collect()
+}
+
+/// Every pair of cards at least `threshold` similar, most similar first.
+pub fn duplicate_pairs(pool: &DbPool, model: &str, threshold: f32) -> Result<Vec<DuplicatePair>> {
+    let all = vectors(pool, model)?;
+    let mut hits = Vec::new();
+    for (i, (a, va)) in all.iter().enumerate() {
+        for (b, vb) in &all[i + 1..] {
+            let s = cosine(va, vb);
+            if s >= threshold {
+                hits.push((*a, *b, s));
+            }
+        }
+    }
+    hits.sort_by(|x, y| y.2.total_cmp(&x.2));
+    hits.into_iter()
+        .map(|(a, b, similarity)| {
+            Ok(DuplicatePair { a: crate::fetch_card(pool, a)?, b: crate::fetch_card(pool, b)?, similarity })
+        })
+        .collect()
+}
+
+/// Cosine similarity; 0 when either vector is empty or the sizes differ.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        0.0
+    } else {
+        dot / norm
+    }
+}
+
+/// All vectors of `model`, by card id.
+fn vectors(pool: &DbPool, model: &str) -> Result<Vec<(i64, Vec<f32>)>> {
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare("SELECT card_id, vector FROM embeddings WHERE model = ?1 ORDER BY card_id")?;
+    let rows = stmt.query_map([model], |row| {
+        let blob: Vec<u8> = row.get(1)?;
+        let v = blob.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+        Ok((row.get(0)?, v))
+    })?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_similar_cards_and_merges_them() {
+        let (_dir, pool) = crate::testing::temp_pool();
+        let card = |front: &str, tags: &[&str]| CardJson {
+            id: 0,
+            front: front.into(),
+            back: "b".into(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            source: None,
+            media: None,
+            deck: None,
+        };
+        let a = crate::insert_card(&pool, &card("ownership", &["rust"])).unwrap();
+        let b = crate::insert_card(&pool, &card("ownership rules", &["memory"])).unwrap();
+        let c = crate::insert_card(&pool, &card("capital of France", &[])).unwrap();
+        assert_eq!(unembedded_cards(&pool, "m").unwrap().len(), 3);
+        put_embedding(&pool, a, "m", &[1.0, 0.0, 0.0]).unwrap();
+        put_embedding(&pool, b, "m", &[0.9, 0.1, 0.0]).unwrap();
+        put_embedding(&pool, c, "m", &[0.0, 0.0, 1.0]).unwrap();
+        assert_eq!(unembedded_cards(&pool, "m").unwrap().len(), 0);
+        assert_eq!(unembedded_cards(&pool, "other").unwrap().len(), 3, "models are not mixed");
+
+        let hits = similar_cards(&pool, "m", &[1.0, 0.0, 0.0], 0.9, a).unwrap();
+        assert_eq!(hits.iter().map(|h| h.card.id).collect::<Vec<_>>(), [b]);
+        let pairs = duplicate_pairs(&pool, "m", 0.9).unwrap();
+        assert_eq!(pairs.iter().map(|p| (p.a.id, p.b.id)).collect::<Vec<_>>(), [(a, b)]);
+
+        let review = crate::ReviewLog {
+            card_id: b,
+            reviewed_at: chrono::Utc::now(),
+            passed: true,
+            grade: None,
+            duration_ms: None,
+        };
+        crate::log_review(&pool, &review).unwrap();
+        let merged = crate::merge_cards(&pool, a, b).unwrap();
+        assert_eq!(merged.tags, ["rust", "memory"]);
+        assert!(crate::fetch_card(&pool, b).is_err());
+        assert!(duplicate_pairs(&pool, "m", 0.9).unwrap().is_empty());
+        let reviews: i64 = pool
+            .get()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM reviews WHERE card_id = ?1", [a], |r| r.get(0))
+            .unwrap();
+        assert_eq!(reviews, 1, "review history moves to the kept card");
+    }
+}
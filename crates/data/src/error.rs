@@ -0,0 +1,55 @@
+//! Errors returned by the `data` crate.
+
+use utils::error::Coded;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Another connection holds a write lock (SQLITE_BUSY / SQLITE_LOCKED).
+    #[error("database is locked by another process")]
+    Locked,
+    #[error("{0} not found")]
+    NotFound(String),
+    /// The row exists but is not in a state that allows the change.
+    #[error("{0}")]
+    Conflict(String),
+    #[error("database connection unavailable: {0}")]
+    Pool(#[from] r2d2::Error),
+    #[error("database error: {0}")]
+    Sqlite(rusqlite::Error),
+    #[error("media store I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Not a PNG/WebP image, or one that does not decode.
+    #[error("unsupported media: {0}")]
+    BadMedia(String),
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode::{DatabaseBusy, DatabaseLocked};
+        match e {
+            rusqlite::Error::SqliteFailure(f, _) if matches!(f.code, DatabaseBusy | DatabaseLocked) => Error::Locked,
+            rusqlite::Error::QueryReturnedNoRows => Error::NotFound("row".into()),
+            other => Error::Sqlite(other),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Locked => "db.locked",
+            Error::NotFound(_) => "db.not_found",
+            Error::Conflict(_) => "db.conflict",
+            Error::Pool(_) => "db.unavailable",
+            Error::Sqlite(_) => "db.error",
+            Error::Io(_) => "media.io",
+            Error::BadMedia(_) => "media.unsupported",
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        matches!(self, Error::Locked | Error::Pool(_))
+    }
+}
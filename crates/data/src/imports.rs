@@ -0,0 +1,128 @@
+//! Progress of document imports. Each section of a document is queued as a
+//! generation job exactly once, so re-running an import resumes it.
+
+use crate::jobs::{insert_job, JobPayload};
+use crate::{DbPool, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Row, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub(crate) const SCHEMA: &str = r#"
+    CREATE TABLE imports (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        path       TEXT    NOT NULL,
+        hash       TEXT    NOT NULL UNIQUE,
+        chunks     INTEGER NOT NULL,
+        created_at INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+    );
+    CREATE TABLE import_chunks (
+        import_id INTEGER NOT NULL REFERENCES imports(id) ON DELETE CASCADE,
+        idx       INTEGER NOT NULL,
+        job_id    INTEGER REFERENCES jobs(id) ON DELETE SET NULL,
+        PRIMARY KEY (import_id, idx)
+    );
+"#;
+
+/// One imported document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Import {
+    pub id: i64,
+    pub path: String,
+    /// Content hash; the same file under another name resumes this import.
+    pub hash: String,
+    /// Sections the document was split into.
+    pub chunks: usize,
+    /// Sections already handed to the generation queue.
+    pub queued: usize,
+    pub created_at: DateTime<Utc>,
+}
+
+fn from_row(row: &Row) -> rusqlite::Result<Import> {
+    let (chunks, queued): (i64, i64) = (row.get(3)?, row.get(4)?);
+    Ok(Import {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        hash: row.get(2)?,
+        chunks: chunks as usize,
+        queued: queued as usize,
+        created_at: DateTime::from_timestamp(row.get(5)?, 0).unwrap_or_default(),
+    })
+}
+
+const IMPORT_QUERY: &str = "SELECT id, path, hash, chunks,
+        (SELECT COUNT(*) FROM import_chunks WHERE import_id = imports.id), created_at
+     FROM imports";
+
+/// Start an import of the document with content `hash`, or pick up the
+/// existing one (recording its current path and section count).
+pub fn begin_import(pool: &DbPool, path: &str, hash: &str, chunks: usize) -> Result<Import> {
+    let conn = pool.get()?;
+    conn.execute(
+        "INSERT INTO imports (path, hash, chunks) VALUES (?1, ?2, ?3)
+         ON CONFLICT(hash) DO UPDATE SET path = excluded.path, chunks = excluded.chunks",
+        params![path, hash, chunks as i64],
+    )?;
+    Ok(conn.query_row(&format!("{IMPORT_QUERY} WHERE hash = ?1"), [hash], from_row)?)
+}
+
+/// Import of the document with content `hash`, if any.
+pub fn find_import(pool: &DbPool, hash: &str) -> Result<Option<Import>> {
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(&format!("{IMPORT_QUERY} WHERE hash = ?1"))?;
+    let mut rows = stmt.query_map([hash], from_row)?;
+    Ok(rows.next().transpose()?)
+}
+
+/// Indexes of the sections already queued.
+pub fn queued_chunks(pool: &DbPool, import_id: i64) -> Result<HashSet<usize>> {
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare("SELECT idx FROM import_chunks WHERE import_id = ?1")?;
+    let rows = stmt.query_map([import_id], |row| row.get::<_, i64>(0))?;
+    Ok(rows.map(|r| r.map(|i| i as usize)).collect::<rusqlite::Result<_>>()?)
+}
+
+/// Queue one section and mark it done in a single transaction, so an
+/// interrupted import neither loses nor duplicates it. Returns the job id,
+/// or `None` when the section was already queued.
+pub fn enqueue_chunk(pool: &DbPool, import_id: i64, index: usize, payload: &JobPayload) -> Result<Option<i64>> {
+    let mut conn = pool.get()?;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let done: bool = tx.query_row(
+        "SELECT EXISTS (SELECT 1 FROM import_chunks WHERE import_id = ?1 AND idx = ?2)",
+        params![import_id, index as i64],
+        |row| row.get(0),
+    )?;
+    if done {
+        return Ok(None);
+    }
+    let job_id = insert_job(&tx, payload)?;
+    tx.execute(
+        "INSERT INTO import_chunks (import_id, idx, job_id) VALUES (?1, ?2, ?3)",
+        params![import_id, index as i64, job_id],
+    )?;
+    tx.commit()?;
+    Ok(Some(job_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumes_without_requeueing() {
+        let (_dir, pool) = crate::testing::temp_pool();
+        let text = |t: &str| JobPayload::Text { text: t.into(), source: None, deck: None };
+
+        let imp = begin_import(&pool, "/a.pdf", "h1", 3).unwrap();
+        assert!(enqueue_chunk(&pool, imp.id, 0, &text("one")).unwrap().is_some());
+        assert!(enqueue_chunk(&pool, imp.id, 0, &text("one")).unwrap().is_none());
+
+        // Same content under a new name: same import, progress kept.
+        let again = begin_import(&pool, "/b.pdf", "h1", 3).unwrap();
+        assert_eq!((again.id, again.path.as_str(), again.queued), (imp.id, "/b.pdf", 1));
+        assert_eq!(queued_chunks(&pool, imp.id).unwrap(), HashSet::from([0]));
+        assert_eq!(find_import(&pool, "h2").unwrap(), None);
+        assert_eq!(crate::jobs::list_jobs(&pool, None).unwrap().len(), 1);
+    }
+}
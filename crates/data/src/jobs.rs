@@ -0,0 +1,508 @@
+//! Durable card-generation queue. A capture is written here before any network
+//! call so it survives LLM failures, restarts and offline periods.
+
+use crate::{DbPool, Error, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use utils::source::Source;
+
+pub(crate) const SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS jobs (
+        id              INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind            TEXT    NOT NULL,
+        payload         BLOB    NOT NULL,
+        source_image    TEXT,
+        status          TEXT    NOT NULL DEFAULT 'pending',
+        attempts        INTEGER NOT NULL DEFAULT 0,
+        next_attempt_at INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+        error_code      TEXT,
+        last_error      TEXT,
+        card_id         INTEGER REFERENCES cards(id),
+        created_at      INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+    );
+    CREATE INDEX IF NOT EXISTS jobs_pending ON jobs(status, next_attempt_at);
+"#;
+
+/// Lifecycle of a queued job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    /// Gave up; waits for the user to retry.
+    Dead,
+    /// Looks like a recent screenshot; waits for the user to reuse that
+    /// capture's card or generate a new one.
+    Held,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Dead => "dead",
+            JobStatus::Held => "held",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "running" => JobStatus::Running,
+            "done" => JobStatus::Done,
+            "dead" => JobStatus::Dead,
+            "held" => JobStatus::Held,
+            _ => JobStatus::Pending,
+        }
+    }
+}
+
+/// Input to card generation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobPayload {
+    /// PNG-encoded screenshot.
+    Image { png: Vec<u8>, source: Option<Source>, deck: Option<String> },
+    Text { text: String, source: Option<Source>, deck: Option<String> },
+}
+
+impl JobPayload {
+    fn kind(&self) -> &'static str {
+        match self {
+            JobPayload::Image { .. } => "image",
+            JobPayload::Text { .. } => "text",
+        }
+    }
+
+    /// Where the capture came from, carried through to the card.
+    pub fn source(&self) -> Option<&Source> {
+        match self {
+            JobPayload::Image { source, .. } | JobPayload::Text { source, .. } => source.as_ref(),
+        }
+    }
+
+    /// Deck the resulting card is filed into.
+    pub fn deck(&self) -> Option<&str> {
+        match self {
+            JobPayload::Image { deck, .. } | JobPayload::Text { deck, .. } => deck.as_deref(),
+        }
+    }
+}
+
+/// Queue entry without its (potentially large) payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    pub status: JobStatus,
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub error_code: Option<String>,
+    pub last_error: Option<String>,
+    pub card_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    /// Earlier job this screenshot was held as a near-duplicate of.
+    #[serde(default)]
+    pub duplicate_of: Option<i64>,
+}
+
+const JOB_COLUMNS: &str =
+    "id, kind, status, attempts, next_attempt_at, error_code, last_error, card_id, created_at, duplicate_of";
+
+/// Queue a job for immediate processing.
+pub fn enqueue_job(pool: &DbPool, payload: &JobPayload) -> Result<i64> {
+    insert_job(&*pool.get()?, payload)
+}
+
+/// [`enqueue_job`] on a caller's connection, so it can share a transaction.
+pub(crate) fn insert_job(conn: &Connection, payload: &JobPayload) -> Result<i64> {
+    let body = match payload {
+        JobPayload::Image { png, .. } => png.clone(),
+        JobPayload::Text { text, .. } => text.as_bytes().to_vec(),
+    };
+    let source = payload
+        .source()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+    conn.execute(
+        "INSERT INTO jobs (kind, payload, source, deck) VALUES (?1, ?2, ?3, ?4)",
+        params![payload.kind(), body, source, payload.deck()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Queue a screenshot with its perceptual hash. With `duplicate_of` the job is
+/// [`JobStatus::Held`] instead of pending, so no LLM call is made until the
+/// user calls [`release_job`].
+pub fn enqueue_capture(pool: &DbPool, payload: &JobPayload, phash: u64, duplicate_of: Option<i64>) -> Result<i64> {
+    let mut conn = pool.get()?;
+    // One transaction, so the worker never claims a duplicate before it is held.
+    let tx = conn.transaction()?;
+    let id = insert_job(&tx, payload)?;
+    tx.execute(
+        "UPDATE jobs SET phash = ?2, duplicate_of = ?3, status = CASE WHEN ?3 IS NULL THEN status ELSE 'held' END
+         WHERE id = ?1",
+        params![id, phash as i64, duplicate_of],
+    )?;
+    tx.commit()?;
+    Ok(id)
+}
+
+/// Hashed screenshots queued since `since`, newest first, that a new capture
+/// may duplicate. Dead jobs and held duplicates do not count.
+pub fn recent_captures(pool: &DbPool, since: DateTime<Utc>) -> Result<Vec<(Job, u64)>> {
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {JOB_COLUMNS}, phash FROM jobs
+         WHERE phash IS NOT NULL AND created_at >= ?1 AND duplicate_of IS NULL AND status <> 'dead'
+         ORDER BY id DESC"
+    ))?;
+    let rows = stmt.query_map([since.timestamp()], |row| Ok((job_from_row(row)?, row.get::<_, i64>(10)? as u64)))?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+/// Settle a held duplicate without generating: it is done and shares the card
+/// of the job it duplicates. Refused while that job has no card – still
+/// generating, or failed – so the duplicate stays held and can be released.
+pub fn reuse_job(pool: &DbPool, id: i64) -> Result<Job> {
+    let held = fetch_job(pool, id)?;
+    if held.status != JobStatus::Held {
+        return Err(Error::NotFound(format!("held job #{id}")));
+    }
+    let original = held.duplicate_of.map(|o| fetch_job(pool, o)).transpose()?;
+    let card_id = match original {
+        Some(Job { status: JobStatus::Done, card_id: Some(card_id), .. }) => card_id,
+        Some(Job { id: o, status: JobStatus::Dead, .. }) => {
+            return Err(Error::Conflict(format!("job #{o} failed; generate a new card for job #{id} instead")));
+        }
+        Some(Job { id: o, .. }) => {
+            return Err(Error::Conflict(format!("job #{o} has no card yet; reuse it once it finishes")));
+        }
+        None => return Err(Error::Conflict(format!("job #{id} is not a duplicate of another job"))),
+    };
+    let conn = pool.get()?;
+    let n = conn.execute(
+        "UPDATE jobs SET status = 'done', card_id = ?2 WHERE id = ?1 AND status = 'held'",
+        params![id, card_id],
+    )?;
+    drop(conn);
+    if n == 0 {
+        return Err(Error::NotFound(format!("held job #{id}")));
+    }
+    fetch_job(pool, id)
+}
+
+/// Queue a held duplicate for generation after all.
+pub fn release_job(pool: &DbPool, id: i64) -> Result<()> {
+    let conn = pool.get()?;
+    let n = conn.execute(
+        "UPDATE jobs SET status = 'pending', duplicate_of = NULL, next_attempt_at = strftime('%s','now')
+         WHERE id = ?1 AND status = 'held'",
+        [id],
+    )?;
+    if n == 0 {
+        return Err(Error::NotFound(format!("held job #{id}")));
+    }
+    Ok(())
+}
+
+/// Atomically take the oldest job that is due at `now`, marking it running.
+pub fn claim_next_job(pool: &DbPool, now: DateTime<Utc>) -> Result<Option<(Job, JobPayload)>> {
+    let conn = pool.get()?;
+    let id: Option<i64> = conn
+        .query_row(
+            "UPDATE jobs SET status = 'running', attempts = attempts + 1
+             WHERE id = (SELECT id FROM jobs WHERE status = 'pending' AND next_attempt_at <= ?1
+                         ORDER BY next_attempt_at, id LIMIT 1)
+             RETURNING id",
+            [now.timestamp()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    drop(conn);
+    id.map(|id| fetch_job_with_payload(pool, id)).transpose()
+}
+
+/// Claim a specific pending job; `None` if someone else already took it.
+pub fn claim_job(pool: &DbPool, id: i64) -> Result<Option<(Job, JobPayload)>> {
+    let conn = pool.get()?;
+    let n = conn.execute(
+        "UPDATE jobs SET status = 'running', attempts = attempts + 1 WHERE id = ?1 AND status = 'pending'",
+        [id],
+    )?;
+    drop(conn);
+    if n == 0 {
+        return Ok(None);
+    }
+    fetch_job_with_payload(pool, id).map(Some)
+}
+
+/// Mark a job finished with the card it produced.
+pub fn complete_job(pool: &DbPool, id: i64, card_id: i64) -> Result<()> {
+    let conn = pool.get()?;
+    conn.execute(
+        "UPDATE jobs SET status = 'done', card_id = ?2, error_code = NULL, last_error = NULL WHERE id = ?1",
+        params![id, card_id],
+    )?;
+    Ok(())
+}
+
+/// Put a failed job back in the queue until `next_attempt_at`.
+/// `count_attempt = false` refunds the attempt (e.g. while offline).
+pub fn defer_job(
+    pool: &DbPool,
+    id: i64,
+    next_attempt_at: DateTime<Utc>,
+    code: &str,
+    message: &str,
+    count_attempt: bool,
+) -> Result<()> {
+    let conn = pool.get()?;
+    conn.execute(
+        "UPDATE jobs SET status = 'pending', next_attempt_at = ?2, error_code = ?3, last_error = ?4,
+                attempts = attempts - ?5
+         WHERE id = ?1",
+        params![id, next_attempt_at.timestamp(), code, message, !count_attempt as i64],
+    )?;
+    Ok(())
+}
+
+/// Move a job to the dead-letter state.
+pub fn bury_job(pool: &DbPool, id: i64, code: &str, message: &str) -> Result<()> {
+    let conn = pool.get()?;
+    conn.execute(
+        "UPDATE jobs SET status = 'dead', error_code = ?2, last_error = ?3 WHERE id = ?1",
+        params![id, code, message],
+    )?;
+    Ok(())
+}
+
+/// Re-queue a dead job with a fresh attempt budget.
+pub fn retry_job(pool: &DbPool, id: i64) -> Result<()> {
+    let conn = pool.get()?;
+    let n = conn.execute(
+        "UPDATE jobs SET status = 'pending', attempts = 0, next_attempt_at = strftime('%s','now')
+         WHERE id = ?1 AND status = 'dead'",
+        [id],
+    )?;
+    if n == 0 {
+        return Err(Error::NotFound(format!("dead job #{id}")));
+    }
+    Ok(())
+}
+
+/// Re-queue every dead job; returns how many were revived.
+pub fn retry_dead_jobs(pool: &DbPool) -> Result<usize> {
+    let conn = pool.get()?;
+    Ok(conn.execute(
+        "UPDATE jobs SET status = 'pending', attempts = 0, next_attempt_at = strftime('%s','now')
+         WHERE status = 'dead'",
+        [],
+    )?)
+}
+
+/// Jobs left `running` by a crashed daemon go back to `pending`.
+pub fn requeue_running_jobs(pool: &DbPool) -> Result<usize> {
+    let conn = pool.get()?;
+    Ok(conn.execute("UPDATE jobs SET status = 'pending' WHERE status = 'running'", [])?)
+}
+
+/// Earliest time a pending job becomes due, if any.
+pub fn next_job_due(pool: &DbPool) -> Result<Option<DateTime<Utc>>> {
+    let conn = pool.get()?;
+    let ts: Option<i64> = conn.query_row(
+        "SELECT MIN(next_attempt_at) FROM jobs WHERE status = 'pending'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(ts.and_then(|t| DateTime::from_timestamp(t, 0)))
+}
+
+/// Fetch one job without its payload.
+pub fn fetch_job(pool: &DbPool, id: i64) -> Result<Job> {
+    let conn = pool.get()?;
+    conn.query_row(&format!("SELECT {JOB_COLUMNS} FROM jobs WHERE id = ?1"), [id], job_from_row)
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Error::NotFound(format!("job #{id}")),
+            other => other.into(),
+        })
+}
+
+/// List jobs, newest first, optionally filtered by status.
+pub fn list_jobs(pool: &DbPool, status: Option<JobStatus>) -> Result<Vec<Job>> {
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {JOB_COLUMNS} FROM jobs WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC"
+    ))?;
+    let rows = stmt.query_map([status.map(JobStatus::as_str)], job_from_row)?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+fn fetch_job_with_payload(pool: &DbPool, id: i64) -> Result<(Job, JobPayload)> {
+    let conn = pool.get()?;
+    conn.query_row(
+        &format!("SELECT {JOB_COLUMNS}, payload, source, deck FROM jobs WHERE id = ?1"),
+        [id],
+        |row| {
+            let job = job_from_row(row)?;
+            let body: Vec<u8> = row.get(10)?;
+            // A source that no longer parses is dropped rather than failing the job.
+            let source = row.get::<_, Option<String>>(11)?.and_then(|s| serde_json::from_str(&s).ok());
+            let deck = row.get(12)?;
+            let payload = match job.kind.as_str() {
+                "image" => JobPayload::Image { png: body, source, deck },
+                _ => JobPayload::Text { text: String::from_utf8_lossy(&body).into_owned(), source, deck },
+            };
+            Ok((job, payload))
+        },
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => Error::NotFound(format!("job #{id}")),
+        other => other.into(),
+    })
+}
+
+fn job_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Job> {
+    let status: String = row.get(2)?;
+    let attempts: i64 = row.get(3)?;
+    Ok(Job {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        status: JobStatus::parse(&status),
+        attempts: attempts.max(0) as u32,
+        next_attempt_at: DateTime::from_timestamp(row.get(4)?, 0).unwrap_or_default(),
+        error_code: row.get(5)?,
+        last_error: row.get(6)?,
+        card_id: row.get(7)?,
+        created_at: DateTime::from_timestamp(row.get(8)?, 0).unwrap_or_default(),
+        duplicate_of: row.get(9)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_pool;
+
+    #[test]
+    fn job_lifecycle() {
+        let (_dir, pool) = temp_pool();
+        let now = Utc::now();
+        let source = Source { app: Some("Firefox".into()), ..Source::now() };
+        let queued = JobPayload::Text {
+            text: "ownership".into(),
+            source: Some(source.clone()),
+            deck: Some("Rust::Basics".into()),
+        };
+        let id = enqueue_job(&pool, &queued).unwrap();
+
+        let (job, payload) = claim_next_job(&pool, now).unwrap().unwrap();
+        assert_eq!((job.id, job.attempts, job.status), (id, 1, JobStatus::Running));
+        assert_eq!(payload.source().and_then(|s| s.app.as_deref()), Some("Firefox"));
+        assert_eq!(payload.deck(), Some("Rust::Basics"));
+        assert!(claim_next_job(&pool, now).unwrap().is_none(), "running jobs are not re-claimed");
+
+        let later = now + chrono::Duration::seconds(30);
+        defer_job(&pool, id, later, "llm.offline", "no route", false).unwrap();
+        assert!(claim_next_job(&pool, now).unwrap().is_none(), "not due yet");
+        let (job, _) = claim_next_job(&pool, later).unwrap().unwrap();
+        assert_eq!(job.attempts, 1, "offline deferral does not consume an attempt");
+
+        bury_job(&pool, id, "llm.unauthorized", "bad key").unwrap();
+        assert_eq!(list_jobs(&pool, Some(JobStatus::Dead)).unwrap().len(), 1);
+        retry_job(&pool, id).unwrap();
+        let (job, _) = claim_job(&pool, id).unwrap().unwrap();
+        assert_eq!(job.attempts, 1);
+        let card = crate::CardJson {
+            id: 0,
+            front: "f".into(),
+            back: "b".into(),
+            tags: vec![],
+            source: payload.source().cloned(),
+            media: None,
+            deck: payload.deck().map(str::to_owned),
+        };
+        let card_id = crate::insert_card(&pool, &card).unwrap();
+        complete_job(&pool, id, card_id).unwrap();
+        assert_eq!(fetch_job(&pool, id).unwrap().card_id, Some(card_id));
+        assert_eq!(crate::fetch_card(&pool, card_id).unwrap().front, "f");
+        assert!(matches!(fetch_job(&pool, id + 1), Err(Error::NotFound(_))));
+        let stored = crate::fetch_all_cards(&pool).unwrap().remove(0);
+        assert_eq!(stored.deck.as_deref(), Some("Rust::Basics"));
+        let stored = stored.source.unwrap();
+        assert_eq!(stored.app.as_deref(), Some("Firefox"));
+        assert_eq!(stored.captured_at.timestamp(), source.captured_at.timestamp());
+        assert!(retry_job(&pool, id).is_err(), "only dead jobs can be retried");
+    }
+
+    #[test]
+    fn held_duplicates_are_reused_or_released() {
+        let (_dir, pool) = temp_pool();
+        let shot = JobPayload::Image { png: vec![1, 2, 3], source: None, deck: None };
+        let hour_ago = Utc::now() - chrono::Duration::hours(1);
+
+        let first = enqueue_capture(&pool, &shot, 0xF0F0, None).unwrap();
+        let recent = recent_captures(&pool, hour_ago).unwrap();
+        assert_eq!(recent.iter().map(|(j, h)| (j.id, *h)).collect::<Vec<_>>(), [(first, 0xF0F0)]);
+
+        let again = enqueue_capture(&pool, &shot, 0xF0F1, Some(first)).unwrap();
+        let held = fetch_job(&pool, again).unwrap();
+        assert_eq!((held.status, held.duplicate_of), (JobStatus::Held, Some(first)));
+        assert_eq!(recent_captures(&pool, hour_ago).unwrap().len(), 1, "held duplicates are not originals");
+        assert!(claim_job(&pool, again).unwrap().is_none(), "held jobs are not generated");
+
+        // Not while the original is still generating: nothing would fill the card in.
+        assert!(matches!(reuse_job(&pool, again), Err(Error::Conflict(_))));
+        assert_eq!(fetch_job(&pool, again).unwrap().status, JobStatus::Held);
+        claim_job(&pool, first).unwrap().unwrap();
+        let card = crate::CardJson {
+            id: 0,
+            front: "f".into(),
+            back: "b".into(),
+            tags: vec![],
+            source: None,
+            media: None,
+            deck: None,
+        };
+        let card_id = crate::insert_card(&pool, &card).unwrap();
+        complete_job(&pool, first, card_id).unwrap();
+        let reused = reuse_job(&pool, again).unwrap();
+        assert_eq!((reused.status, reused.card_id), (JobStatus::Done, Some(card_id)));
+        assert!(matches!(reuse_job(&pool, again), Err(Error::NotFound(_))));
+
+        let third = enqueue_capture(&pool, &shot, 0xF0F0, Some(first)).unwrap();
+        release_job(&pool, third).unwrap();
+        let (job, _) = claim_next_job(&pool, Utc::now()).unwrap().unwrap();
+        assert_eq!((job.id, job.duplicate_of), (third, None));
+    }
+
+    #[test]
+    fn duplicates_of_a_failed_capture_stay_held() {
+        let (_dir, pool) = temp_pool();
+        let shot = JobPayload::Image { png: vec![1, 2, 3], source: None, deck: None };
+        let first = enqueue_capture(&pool, &shot, 0xF0F0, None).unwrap();
+        let again = enqueue_capture(&pool, &shot, 0xF0F0, Some(first)).unwrap();
+
+        claim_job(&pool, first).unwrap().unwrap();
+        bury_job(&pool, first, "llm.unauthorized", "bad key").unwrap();
+        let err = reuse_job(&pool, again).unwrap_err();
+        assert!(matches!(err, Error::Conflict(_)) && err.to_string().contains("failed"));
+        let held = fetch_job(&pool, again).unwrap();
+        assert_eq!((held.status, held.card_id), (JobStatus::Held, None));
+
+        // Still settled the other way.
+        release_job(&pool, again).unwrap();
+        assert_eq!(claim_job(&pool, again).unwrap().unwrap().0.id, again);
+    }
+}
@@ -1,9 +1,26 @@
 //! Lightweight SQLite helpers backed by `rusqlite` + `r2d2`.
 
-use anyhow::Result;
+pub mod embeddings;
+mod error;
+pub mod imports;
+pub mod jobs;
+pub mod media;
+mod migrations;
+pub mod stats;
+#[cfg(test)]
+mod testing;
+
+pub use embeddings::{DuplicatePair, Similar};
+pub use error::{Error, Result};
+pub use imports::Import;
+pub use jobs::{Job, JobPayload, JobStatus};
+pub use media::{Media, MediaStore};
+pub use stats::Stats;
+
 use chrono::{DateTime, Utc};
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
+use utils::source::Source;
 
 pub type DbPool = r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>;
 
@@ -14,15 +31,27 @@ pub struct CardJson {
     pub front: String,
     pub back: String,
     pub tags: Vec<String>,
+    /// Where the captured text or screenshot came from, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    /// Hash of the source screenshot in the [`MediaStore`] (`GET /media/<hash>`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<String>,
+    /// Deck the card was filed into, e.g. `"Lectures::Biology"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deck: Option<String>,
 }
 
+const CARD_COLUMNS: &str =
+    "id, front_text, back_text, tags, source_app, source_title, source_url, captured_at, media, deck";
+
 /// Connect / create database pool.
 pub fn new_pool(path: &str) -> Result<DbPool> {
     let mgr = r2d2_sqlite::SqliteConnectionManager::file(path);
     let pool = r2d2::Pool::new(mgr)?;
     // Basic migration – create tables if they do not exist.
     {
-        let conn = pool.get()?;
+        let mut conn = pool.get()?;
         conn.execute_batch(
             r#"CREATE TABLE IF NOT EXISTS cards (
                     id           INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -43,40 +72,99 @@ pub fn new_pool(path: &str) -> Result<DbPool> {
               );
             "#,
         )?;
+        conn.execute_batch(jobs::SCHEMA)?;
+        migrations::run(&mut conn)?;
     }
     Ok(pool)
 }
 
-/// Insert new card and return its rowid.
-pub fn insert_card(pool: &DbPool, c: &CardJson, img_path: Option<&str>) -> Result<i64> {
+/// Insert new card and return its rowid. `c.media` must already be in the media store.
+pub fn insert_card(pool: &DbPool, c: &CardJson) -> Result<i64> {
     let conn = pool.get()?;
+    let src = c.source.as_ref();
     conn.execute(
-        "INSERT INTO cards (front_text, back_text, tags, source_image) VALUES (?1, ?2, ?3, ?4)",
-        params![c.front, c.back, c.tags.join(","), img_path],
+        "INSERT INTO cards (front_text, back_text, tags, media, deck, source_app, source_title, source_url, captured_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            c.front,
+            c.back,
+            c.tags.join(","),
+            c.media,
+            c.deck,
+            src.and_then(|s| s.app.as_deref()),
+            src.and_then(|s| s.window_title.as_deref()),
+            src.and_then(|s| s.url.as_deref()),
+            src.map(|s| s.captured_at.timestamp()),
+        ],
     )?;
     Ok(conn.last_insert_rowid())
 }
 
-/// Fetch cards due before given timestamp.
-pub fn fetch_due_cards(pool: &DbPool, ts: DateTime<Utc>) -> Result<Vec<CardJson>> {
+/// A due card with what the review queue orders and limits it by.
+#[derive(Debug, Clone)]
+pub struct DueCard {
+    pub card: CardJson,
+    pub next_due: DateTime<Utc>,
+    /// Days between the last two reviews as scheduled.
+    pub interval: u32,
+    pub phase: Phase,
+}
+
+/// Cards due before `ts`, oldest first, for the queue builder.
+pub fn fetch_due(pool: &DbPool, ts: DateTime<Utc>) -> Result<Vec<DueCard>> {
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {CARD_COLUMNS}, next_due, interval, phase FROM cards WHERE next_due <= ?1 ORDER BY next_due, id"
+    ))?;
+    let rows = stmt.query_map([ts.timestamp()], |row| {
+        Ok(DueCard {
+            card: card_from_row(row)?,
+            next_due: DateTime::from_timestamp(row.get(10)?, 0).unwrap_or_default(),
+            interval: row.get::<_, i64>(11)?.max(0) as u32,
+            phase: Phase::parse(&row.get::<_, String>(12)?),
+        })
+    })?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+/// When the next card still in (re)learning comes due after `after`, if any.
+pub fn next_learning_due(pool: &DbPool, after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+    let conn = pool.get()?;
+    let next: Option<i64> = conn.query_row(
+        "SELECT MIN(next_due) FROM cards WHERE phase IN ('learning', 'relearning') AND next_due > ?1",
+        [after.timestamp()],
+        |row| row.get(0),
+    )?;
+    Ok(next.and_then(|t| DateTime::from_timestamp(t, 0)))
+}
+
+/// Cards of one deck studied since some moment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Studied {
+    pub deck: Option<String>,
+    /// Cards reviewed for the first time.
+    pub new: u32,
+    /// Cards first seen earlier and reviewed again.
+    pub reviews: u32,
+}
+
+/// What was studied since `since` (usually the start of the study day), per deck.
+pub fn studied_since(pool: &DbPool, since: DateTime<Utc>) -> Result<Vec<Studied>> {
     let conn = pool.get()?;
     let mut stmt = conn.prepare(
-        "SELECT id, front_text, back_text, tags FROM cards WHERE next_due <= ?1 ORDER BY id",
+        "SELECT c.deck, COUNT(CASE WHEN f.first >= ?1 THEN 1 END), COUNT(CASE WHEN f.first < ?1 THEN 1 END)
+         FROM (SELECT card_id, MIN(reviewed_at) AS first FROM reviews
+               GROUP BY card_id HAVING MAX(reviewed_at) >= ?1) f
+         JOIN cards c ON c.id = f.card_id
+         GROUP BY c.deck",
     )?;
-    let rows = stmt
-        .query_map([ts.timestamp()], |row| {
-            let tags: String = row.get(3)?;
-            Ok(CardJson {
-                id: row.get(0)?,
-                front: row.get(1)?,
-                back: row.get(2)?,
-                tags: if tags.is_empty() {
-                    Vec::new()
-                } else {
-                    tags.split(',').map(|s| s.trim().to_owned()).collect()
-                },
-            })
-        })?;
+    let rows = stmt.query_map([since.timestamp()], |row| {
+        Ok(Studied { deck: row.get(0)?, new: row.get(1)?, reviews: row.get(2)? })
+    })?;
     let mut out = Vec::new();
     for r in rows {
         out.push(r?);
@@ -84,26 +172,195 @@ pub fn fetch_due_cards(pool: &DbPool, ts: DateTime<Utc>) -> Result<Vec<CardJson>
     Ok(out)
 }
 
-/// Fetch all cards (front/back/tags).
+/// Fetch all cards (front/back/tags/source).
 pub fn fetch_all_cards(pool: &DbPool) -> Result<Vec<CardJson>> {
     let conn = pool.get()?;
-    let mut stmt = conn.prepare("SELECT id, front_text, back_text, tags FROM cards ORDER BY id DESC")?;
-    let rows = stmt.query_map([], |row| {
-        let tags: String = row.get(3)?;
-        Ok(CardJson {
-            id: row.get(0)?,
-            front: row.get(1)?,
-            back: row.get(2)?,
-            tags: if tags.is_empty() {
-                Vec::new()
-            } else {
-                tags.split(',').map(|s| s.trim().to_owned()).collect()
-            },
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!("SELECT {CARD_COLUMNS} FROM cards ORDER BY id DESC"))?;
+    let rows = stmt.query_map([], card_from_row)?;
     let mut out = Vec::new();
     for r in rows {
         out.push(r?);
     }
     Ok(out)
-} 
\ No newline at end of file
+}
+
+/// Fetch one card.
+pub fn fetch_card(pool: &DbPool, card_id: i64) -> Result<CardJson> {
+    let conn = pool.get()?;
+    conn.query_row(&format!("SELECT {CARD_COLUMNS} FROM cards WHERE id = ?1"), [card_id], card_from_row)
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Error::NotFound(format!("card #{card_id}")),
+            other => other.into(),
+        })
+}
+
+/// Fold card `duplicate` into `keep`: tags are combined, `keep` takes over the
+/// screenshot and deck if it had none, and the review history and jobs move
+/// across. `keep`'s text and schedule win; `duplicate` is deleted.
+pub fn merge_cards(pool: &DbPool, keep: i64, duplicate: i64) -> Result<CardJson> {
+    let (kept, dup) = (fetch_card(pool, keep)?, fetch_card(pool, duplicate)?);
+    if keep == duplicate {
+        return Ok(kept);
+    }
+    let mut tags = kept.tags;
+    for tag in dup.tags {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    let mut conn = pool.get()?;
+    let tx = conn.transaction()?;
+    tx.execute(
+        "UPDATE cards SET tags = ?2, media = COALESCE(media, ?3), deck = COALESCE(deck, ?4) WHERE id = ?1",
+        params![keep, tags.join(","), dup.media, dup.deck],
+    )?;
+    tx.execute("UPDATE reviews SET card_id = ?1 WHERE card_id = ?2", params![keep, duplicate])?;
+    tx.execute("UPDATE jobs SET card_id = ?1 WHERE card_id = ?2", params![keep, duplicate])?;
+    tx.execute("DELETE FROM embeddings WHERE card_id = ?1", [duplicate])?;
+    tx.execute("DELETE FROM cards WHERE id = ?1", [duplicate])?;
+    tx.commit()?;
+    drop(conn);
+    fetch_card(pool, keep)
+}
+
+/// Where a card is in learning: new cards step through short learning
+/// delays before graduating to day-based review; forgotten ones relearn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Never reviewed.
+    #[default]
+    New,
+    Learning,
+    Review,
+    /// Forgotten in review and being shown again before its next interval.
+    Relearning,
+}
+
+impl Phase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::New => "new",
+            Phase::Learning => "learning",
+            Phase::Review => "review",
+            Phase::Relearning => "relearning",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "learning" => Phase::Learning,
+            "review" => Phase::Review,
+            "relearning" => Phase::Relearning,
+            _ => Phase::New,
+        }
+    }
+
+    /// Shown again within the day, on learning steps rather than intervals.
+    pub fn is_learning(self) -> bool {
+        matches!(self, Phase::Learning | Phase::Relearning)
+    }
+}
+
+/// Scheduling fields stored alongside a card.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CardSchedule {
+    pub efactor: f32,
+    pub interval: u32, // days
+    pub next_due: DateTime<Utc>,
+    pub phase: Phase,
+    /// Index of the current (re)learning step.
+    pub step: u32,
+}
+
+/// Fetch scheduling fields for a card.
+pub fn fetch_schedule(pool: &DbPool, card_id: i64) -> Result<CardSchedule> {
+    let conn = pool.get()?;
+    let (efactor, interval, next_due, phase, step): (f64, i64, i64, String, i64) = conn
+        .query_row(
+            "SELECT efactor, interval, next_due, phase, step FROM cards WHERE id = ?1",
+            [card_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Error::NotFound(format!("card #{card_id}")),
+            other => other.into(),
+        })?;
+    Ok(CardSchedule {
+        efactor: efactor as f32,
+        interval: interval.max(0) as u32,
+        next_due: DateTime::from_timestamp(next_due, 0).unwrap_or_default(),
+        phase: Phase::parse(&phase),
+        step: step.max(0) as u32,
+    })
+}
+
+/// Persist new scheduling fields for a card.
+pub fn update_schedule(pool: &DbPool, card_id: i64, sched: &CardSchedule) -> Result<()> {
+    let conn = pool.get()?;
+    conn.execute(
+        "UPDATE cards SET efactor = ?1, interval = ?2, next_due = ?3, phase = ?4, step = ?5 WHERE id = ?6",
+        params![
+            sched.efactor as f64,
+            sched.interval as i64,
+            sched.next_due.timestamp(),
+            sched.phase.as_str(),
+            sched.step,
+            card_id
+        ],
+    )?;
+    Ok(())
+}
+
+/// One entry in the review log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewLog {
+    pub card_id: i64,
+    pub reviewed_at: DateTime<Utc>,
+    pub passed: bool,
+    /// `again`, `hard`, `good` or `easy` when the reviewer picked one.
+    pub grade: Option<String>,
+    /// Time spent on the card, when the client measured it.
+    pub duration_ms: Option<u32>,
+}
+
+/// Append a review to the log.
+pub fn log_review(pool: &DbPool, review: &ReviewLog) -> Result<i64> {
+    let conn = pool.get()?;
+    conn.execute(
+        "INSERT INTO reviews (card_id, reviewed_at, passed, grade, duration_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![review.card_id, review.reviewed_at.timestamp(), review.passed, review.grade, review.duration_ms],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn card_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<CardJson> {
+    let tags: Option<String> = row.get(3)?;
+    let tags = tags.unwrap_or_default();
+    Ok(CardJson {
+        id: row.get(0)?,
+        front: row.get(1)?,
+        back: row.get(2)?,
+        tags: if tags.is_empty() {
+            Vec::new()
+        } else {
+            tags.split(',').map(|s| s.trim().to_owned()).collect()
+        },
+        source: source_from_row(row)?,
+        media: row.get(8)?,
+        deck: row.get(9)?,
+    })
+}
+
+/// Source columns 4–7; `None` for cards made before sources were recorded.
+fn source_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Option<Source>> {
+    let Some(captured_at) = row.get::<_, Option<i64>>(7)? else {
+        return Ok(None);
+    };
+    Ok(Some(Source {
+        app: row.get(4)?,
+        window_title: row.get(5)?,
+        url: row.get(6)?,
+        captured_at: DateTime::from_timestamp(captured_at, 0).unwrap_or_default(),
+    }))
+}
@@ -0,0 +1,314 @@
+//! Content-addressed store for source screenshots. Files live under
+//! `<root>/<first two hash chars>/<sha256>.<ext>` next to a PNG thumbnail, are
+//! written once per distinct content, and are reference-counted from
+//! `cards.media` by triggers so unreferenced files can be collected.
+
+use crate::{DbPool, Error, Result};
+use chrono::{DateTime, Utc};
+use image::ImageFormat;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Longest thumbnail edge in pixels.
+pub const THUMB_MAX: u32 = 320;
+
+/// Unreferenced media younger than this survives collection, so a file stored
+/// for a card that is still being written is not swept from under it.
+pub const GC_GRACE_SECS: i64 = 3600;
+
+pub(crate) const SCHEMA: &str = r#"
+    CREATE TABLE media (
+        hash       TEXT    PRIMARY KEY,
+        format     TEXT    NOT NULL,
+        bytes      INTEGER NOT NULL,
+        width      INTEGER NOT NULL,
+        height     INTEGER NOT NULL,
+        refs       INTEGER NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+    );
+    ALTER TABLE cards ADD COLUMN media TEXT REFERENCES media(hash);
+    CREATE TRIGGER cards_media_insert AFTER INSERT ON cards WHEN NEW.media IS NOT NULL BEGIN
+        UPDATE media SET refs = refs + 1 WHERE hash = NEW.media;
+    END;
+    CREATE TRIGGER cards_media_delete AFTER DELETE ON cards WHEN OLD.media IS NOT NULL BEGIN
+        UPDATE media SET refs = refs - 1 WHERE hash = OLD.media;
+    END;
+    CREATE TRIGGER cards_media_update AFTER UPDATE OF media ON cards BEGIN
+        UPDATE media SET refs = refs - 1 WHERE hash = OLD.media;
+        UPDATE media SET refs = refs + 1 WHERE hash = NEW.media;
+    END;
+"#;
+
+/// Stored encodings; inputs are kept as-is, thumbnails are always PNG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaFormat {
+    Png,
+    WebP,
+}
+
+impl MediaFormat {
+    pub fn ext(self) -> &'static str {
+        match self {
+            MediaFormat::Png => "png",
+            MediaFormat::WebP => "webp",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            MediaFormat::Png => "image/png",
+            MediaFormat::WebP => "image/webp",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "webp" => MediaFormat::WebP,
+            _ => MediaFormat::Png,
+        }
+    }
+
+    fn image_format(self) -> ImageFormat {
+        match self {
+            MediaFormat::Png => ImageFormat::Png,
+            MediaFormat::WebP => ImageFormat::WebP,
+        }
+    }
+}
+
+/// A stored file and how many cards use it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Media {
+    /// Lower-case hex SHA-256 of the file contents.
+    pub hash: String,
+    pub format: MediaFormat,
+    pub bytes: u64,
+    pub width: u32,
+    pub height: u32,
+    pub refs: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a collection pass removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// Media entries dropped (stray files only add to `bytes`).
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Whether `s` looks like a media hash (guards paths built from user input).
+pub fn is_hash(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Handle to the media directory; cheap to clone.
+#[derive(Debug, Clone)]
+pub struct MediaStore {
+    root: PathBuf,
+}
+
+impl MediaStore {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Store an encoded PNG or WebP image; identical content is written once.
+    pub fn put(&self, pool: &DbPool, data: &[u8]) -> Result<Media> {
+        let format = match image::guess_format(data) {
+            Ok(ImageFormat::Png) => MediaFormat::Png,
+            Ok(ImageFormat::WebP) => MediaFormat::WebP,
+            Ok(other) => return Err(Error::BadMedia(format!("unsupported format {other:?}"))),
+            Err(e) => return Err(Error::BadMedia(e.to_string())),
+        };
+        let hash: String = Sha256::digest(data).iter().map(|b| format!("{b:02x}")).collect();
+
+        match self.get(pool, &hash) {
+            Ok(media) => {
+                // Row present but files lost: rewrite them.
+                if !self.path(&media).exists() {
+                    self.write_files(&hash, format, data)?;
+                }
+                return Ok(media);
+            }
+            Err(Error::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let (width, height) = self.write_files(&hash, format, data)?;
+        let conn = pool.get()?;
+        conn.execute(
+            "INSERT INTO media (hash, format, bytes, width, height) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(hash) DO NOTHING",
+            params![hash, format.ext(), data.len() as i64, width, height],
+        )?;
+        drop(conn);
+        self.get(pool, &hash)
+    }
+
+    pub fn get(&self, pool: &DbPool, hash: &str) -> Result<Media> {
+        if !is_hash(hash) {
+            return Err(Error::NotFound(format!("media {hash:?}")));
+        }
+        let conn = pool.get()?;
+        conn.query_row(
+            "SELECT hash, format, bytes, width, height, refs, created_at FROM media WHERE hash = ?1",
+            [hash],
+            |row| {
+                let format: String = row.get(1)?;
+                let (bytes, refs): (i64, i64) = (row.get(2)?, row.get(5)?);
+                Ok(Media {
+                    hash: row.get(0)?,
+                    format: MediaFormat::parse(&format),
+                    bytes: bytes.max(0) as u64,
+                    width: row.get(3)?,
+                    height: row.get(4)?,
+                    refs: refs.max(0) as u32,
+                    created_at: DateTime::from_timestamp(row.get(6)?, 0).unwrap_or_default(),
+                })
+            },
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Error::NotFound(format!("media {hash}")),
+            other => other.into(),
+        })
+    }
+
+    /// Original file.
+    pub fn path(&self, media: &Media) -> PathBuf {
+        self.dir(&media.hash).join(format!("{}.{}", media.hash, media.format.ext()))
+    }
+
+    pub fn thumb_path(&self, media: &Media) -> PathBuf {
+        self.dir(&media.hash).join(format!("{}.thumb.png", media.hash))
+    }
+
+    /// Delete media no card references (older than [`GC_GRACE_SECS`]) and
+    /// stray files without a row, e.g. left by a crash between write and insert.
+    pub fn gc(&self, pool: &DbPool, now: DateTime<Utc>) -> Result<GcStats> {
+        let cutoff = now.timestamp() - GC_GRACE_SECS;
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "DELETE FROM media WHERE refs <= 0 AND created_at <= ?1 RETURNING hash, format, bytes",
+        )?;
+        let removed = stmt
+            .query_map([cutoff], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        drop(stmt);
+
+        let mut stats = GcStats::default();
+        for (hash, format, bytes) in removed {
+            let dir = self.dir(&hash);
+            let _ = std::fs::remove_file(dir.join(format!("{hash}.{}", MediaFormat::parse(&format).ext())));
+            let _ = std::fs::remove_file(dir.join(format!("{hash}.thumb.png")));
+            stats.files += 1;
+            stats.bytes += bytes.max(0) as u64;
+        }
+
+        // Stray files: named like media but unknown to the table.
+        let mut known = conn.prepare("SELECT 1 FROM media WHERE hash = ?1")?;
+        for shard in std::fs::read_dir(&self.root)?.flatten() {
+            for file in std::fs::read_dir(shard.path()).into_iter().flatten().flatten() {
+                let name = file.file_name();
+                let Some(hash) = name.to_str().and_then(|n| n.split('.').next()).filter(|h| is_hash(h)) else {
+                    continue;
+                };
+                let meta = file.metadata()?;
+                let modified = meta.modified().ok().map(DateTime::<Utc>::from).unwrap_or(now);
+                if modified.timestamp() > cutoff || known.exists([hash])? {
+                    continue;
+                }
+                if std::fs::remove_file(file.path()).is_ok() {
+                    stats.bytes += meta.len();
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    fn dir(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2])
+    }
+
+    /// Write the original and its thumbnail; returns the image dimensions.
+    fn write_files(&self, hash: &str, format: MediaFormat, data: &[u8]) -> Result<(u32, u32)> {
+        let img = image::load_from_memory_with_format(data, format.image_format())
+            .map_err(|e| Error::BadMedia(e.to_string()))?;
+        let dir = self.dir(hash);
+        std::fs::create_dir_all(&dir)?;
+
+        let mut thumb = std::io::Cursor::new(Vec::new());
+        img.thumbnail(THUMB_MAX, THUMB_MAX)
+            .write_to(&mut thumb, ImageFormat::Png)
+            .map_err(|e| Error::BadMedia(e.to_string()))?;
+        write_atomic(&dir.join(format!("{hash}.thumb.png")), thumb.get_ref())?;
+        write_atomic(&dir.join(format!("{hash}.{}", format.ext())), data)?;
+        Ok((img.width(), img.height()))
+    }
+}
+
+/// Write via a temporary sibling and rename, so readers never see half a file.
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(shade: u8) -> Vec<u8> {
+        let img = image::RgbaImage::from_pixel(640, 200, image::Rgba([shade, 0, 0, 255]));
+        let mut out = std::io::Cursor::new(Vec::new());
+        img.write_to(&mut out, ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn dedupes_counts_references_and_collects_orphans() {
+        let (dir, pool) = crate::testing::temp_pool();
+        let store = MediaStore::open(dir.path().join("media")).unwrap();
+
+        let a = store.put(&pool, &png(10)).unwrap();
+        assert_eq!(store.put(&pool, &png(10)).unwrap().hash, a.hash, "same bytes, same file");
+        let b = store.put(&pool, &png(200)).unwrap();
+        assert_ne!(a.hash, b.hash);
+        assert_eq!((a.width, a.height), (640, 200));
+        let thumb = image::open(store.thumb_path(&a)).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (THUMB_MAX, 100));
+        assert!(store.put(&pool, b"not an image").is_err());
+
+        let card = crate::CardJson {
+            id: 0,
+            front: "f".into(),
+            back: "b".into(),
+            tags: vec![],
+            source: None,
+            media: Some(a.hash.clone()),
+            deck: None,
+        };
+        crate::insert_card(&pool, &card).unwrap();
+        crate::insert_card(&pool, &card).unwrap();
+        assert_eq!(store.get(&pool, &a.hash).unwrap().refs, 2);
+
+        // Within the grace period nothing goes; after it only the orphan does.
+        assert_eq!(store.gc(&pool, Utc::now()).unwrap().files, 0);
+        let later = Utc::now() + chrono::Duration::seconds(GC_GRACE_SECS + 1);
+        assert_eq!(store.gc(&pool, later).unwrap().files, 1);
+        assert!(store.path(&a).exists());
+        assert!(!store.path(&b).exists() && !store.thumb_path(&b).exists());
+        assert!(matches!(store.get(&pool, &b.hash), Err(Error::NotFound(_))));
+        assert!(matches!(store.get(&pool, "../../etc/passwd"), Err(Error::NotFound(_))));
+    }
+}
@@ -0,0 +1,81 @@
+//! Schema changes on top of the base `CREATE TABLE`s, applied once each in
+//! order and tracked in `PRAGMA user_version`. Only ever append to the list.
+
+use crate::Result;
+use rusqlite::{Connection, TransactionBehavior};
+
+const MIGRATIONS: &[&str] = &[
+    // 1: where a capture came from
+    "ALTER TABLE cards ADD COLUMN source_app TEXT;
+     ALTER TABLE cards ADD COLUMN source_title TEXT;
+     ALTER TABLE cards ADD COLUMN source_url TEXT;
+     ALTER TABLE cards ADD COLUMN captured_at INTEGER;
+     ALTER TABLE jobs ADD COLUMN source TEXT;",
+    // 2: content-addressed screenshots referenced from cards
+    crate::media::SCHEMA,
+    // 3: document imports and which of their sections are queued
+    crate::imports::SCHEMA,
+    // 4: deck a card belongs to, carried on its job until generated
+    "ALTER TABLE cards ADD COLUMN deck TEXT;
+     ALTER TABLE jobs ADD COLUMN deck TEXT;
+     CREATE INDEX cards_deck ON cards(deck);",
+    // 5: perceptual hashes of screenshots, and the earlier capture a held duplicate resembles
+    "ALTER TABLE jobs ADD COLUMN phash INTEGER;
+     ALTER TABLE jobs ADD COLUMN duplicate_of INTEGER REFERENCES jobs(id);
+     CREATE INDEX jobs_phash ON jobs(created_at) WHERE phash IS NOT NULL;",
+    // 6: card embeddings for semantic duplicate detection
+    crate::embeddings::SCHEMA,
+    // 7: how each review was graded and how long it took, for statistics
+    "ALTER TABLE reviews ADD COLUMN grade TEXT;
+     ALTER TABLE reviews ADD COLUMN duration_ms INTEGER;
+     CREATE INDEX reviews_card ON reviews(card_id, reviewed_at);",
+    // 8: learning state machine; cards reviewed before it existed are in review
+    "ALTER TABLE cards ADD COLUMN phase TEXT NOT NULL DEFAULT 'new';
+     ALTER TABLE cards ADD COLUMN step INTEGER NOT NULL DEFAULT 0;
+     UPDATE cards SET phase = 'review' WHERE id IN (SELECT card_id FROM reviews);
+     CREATE INDEX cards_phase ON cards(phase);",
+];
+
+/// Bring the schema up to date. Each step holds a write lock while it checks
+/// the version, so two processes opening the same file cannot both apply it.
+pub(crate) fn run(conn: &mut Connection) -> Result<()> {
+    loop {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let version: usize = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let Some(sql) = MIGRATIONS.get(version) else {
+            return Ok(());
+        };
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", version + 1)?;
+        tx.commit()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrades_an_old_database_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.db");
+        {
+            // A database written before migrations existed.
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch("CREATE TABLE cards (id INTEGER PRIMARY KEY, front_text TEXT NOT NULL, back_text TEXT NOT NULL, tags TEXT);")
+                .unwrap();
+            conn.execute("INSERT INTO cards (front_text, back_text) VALUES ('f', 'b')", []).unwrap();
+        }
+
+        let pool = crate::new_pool(path.to_str().unwrap()).unwrap();
+        let mut conn = pool.get().unwrap();
+        let version: usize = conn.query_row("PRAGMA user_version", [], |r| r.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        let url: Option<String> = conn.query_row("SELECT source_url FROM cards", [], |r| r.get(0)).unwrap();
+        assert_eq!(url, None);
+
+        run(&mut conn).unwrap();
+        drop(conn);
+        crate::new_pool(path.to_str().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,329 @@
+//! Review statistics for the dashboard: daily activity, retention by how long
+//! cards were away, streaks, time spent, card maturity, the upcoming load and
+//! accuracy per deck and tag. Days are study days of the given [`StudyClock`].
+
+use crate::{DbPool, Result};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use utils::clock::StudyClock;
+
+/// Cards scheduled this many days apart or more count as mature.
+pub const MATURE_DAYS: u32 = 21;
+
+/// Days covered when the caller does not say.
+pub const DEFAULT_DAYS: u32 = 30;
+
+/// Days ahead covered by [`Stats::forecast`].
+pub const FORECAST_DAYS: u32 = 30;
+
+/// Accuracy entry for cards without a deck.
+const NO_DECK: &str = "(no deck)";
+
+/// Retention buckets by whole days since the previous review: label, from, to.
+const BUCKETS: &[(&str, u32, Option<u32>)] = &[
+    ("same day", 0, Some(1)),
+    ("1–6 days", 1, Some(7)),
+    ("1–3 weeks", 7, Some(21)),
+    ("3 weeks–3 months", 21, Some(90)),
+    ("3 months+", 90, None),
+];
+
+/// Everything the dashboard shows. `reviews`, `retention`, `time` and the
+/// accuracy lists cover the last `days` days up to today; streaks use the
+/// whole history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub days: u32,
+    /// One entry per day, oldest first, including days without reviews.
+    pub reviews: Vec<DayReviews>,
+    pub retention: Vec<Retention>,
+    pub streak: Streak,
+    pub time: TimeSpent,
+    pub maturity: Maturity,
+    /// Reviews due on each of the next [`FORECAST_DAYS`] days; overdue cards
+    /// count towards today.
+    pub forecast: Vec<DueDay>,
+    /// Most reviewed first.
+    pub decks: Vec<Accuracy>,
+    pub tags: Vec<Accuracy>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayReviews {
+    pub date: NaiveDate,
+    pub reviews: u32,
+    pub passed: u32,
+    pub secs: u64,
+}
+
+/// Recall of cards that had been away `min_days` up to `max_days` (exclusive)
+/// since their previous review. First reviews are not counted: true retention
+/// only measures cards the reviewer had seen before.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Retention {
+    pub label: String,
+    pub min_days: u32,
+    pub max_days: Option<u32>,
+    pub reviews: u32,
+    pub passed: u32,
+}
+
+impl Retention {
+    /// Share of reviews passed; `None` without reviews.
+    pub fn rate(&self) -> Option<f32> {
+        (self.reviews > 0).then(|| self.passed as f32 / self.reviews as f32)
+    }
+}
+
+/// Consecutive days with at least one review. A streak stays current until a
+/// whole day passes without one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Streak {
+    pub current: u32,
+    pub longest: u32,
+    pub reviewed_today: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimeSpent {
+    pub total_secs: u64,
+    pub today_secs: u64,
+    /// Average over the reviews the client timed.
+    pub secs_per_review: Option<f32>,
+}
+
+/// Cards by how well established they are: never reviewed, scheduled less
+/// than [`MATURE_DAYS`] apart, or further.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Maturity {
+    pub new: u32,
+    pub young: u32,
+    pub mature: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DueDay {
+    pub date: NaiveDate,
+    pub due: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Accuracy {
+    pub name: String,
+    pub reviews: u32,
+    pub passed: u32,
+}
+
+impl Accuracy {
+    pub fn rate(&self) -> f32 {
+        self.passed as f32 / self.reviews.max(1) as f32
+    }
+}
+
+/// Statistics for the `days` study days up to `now`.
+pub fn stats(pool: &DbPool, now: DateTime<Utc>, clock: &StudyClock, days: u32) -> Result<Stats> {
+    let days = days.max(1);
+    let date = |ts: i64| clock.day(DateTime::from_timestamp(ts, 0).unwrap_or_default());
+    let today = clock.day(now);
+    let first = today - Days::new((days - 1).into());
+
+    let mut per_day: BTreeMap<NaiveDate, DayReviews> = (0..days)
+        .map(|i| first + Days::new(i.into()))
+        .map(|date| (date, DayReviews { date, reviews: 0, passed: 0, secs: 0 }))
+        .collect();
+    let mut retention: Vec<Retention> = BUCKETS
+        .iter()
+        .map(|&(label, min_days, max_days)| Retention { label: label.into(), min_days, max_days, reviews: 0, passed: 0 })
+        .collect();
+    let mut studied = BTreeSet::new();
+    let (mut timed, mut timed_ms) = (0u32, 0u64);
+    let mut decks: HashMap<String, Accuracy> = HashMap::new();
+    let mut tags: HashMap<String, Accuracy> = HashMap::new();
+
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(
+        "SELECT r.reviewed_at, r.passed, r.duration_ms, c.deck, c.tags,
+                r.reviewed_at - LAG(r.reviewed_at) OVER (PARTITION BY r.card_id ORDER BY r.reviewed_at, r.id)
+         FROM reviews r LEFT JOIN cards c ON c.id = r.card_id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, bool>(1)?,
+            row.get::<_, Option<u32>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, Option<i64>>(5)?,
+        ))
+    })?;
+    for row in rows {
+        let (at, passed, duration_ms, deck, card_tags, away) = row?;
+        let day = date(at);
+        studied.insert(day);
+        let Some(entry) = per_day.get_mut(&day) else { continue };
+        entry.reviews += 1;
+        entry.passed += passed as u32;
+        if let Some(ms) = duration_ms {
+            entry.secs += (u64::from(ms) + 500) / 1000;
+            timed += 1;
+            timed_ms += u64::from(ms);
+        }
+        if let Some(away) = away {
+            let away = (away.max(0) / 86_400) as u32;
+            let bucket = retention.iter_mut().find(|b| away >= b.min_days && b.max_days.is_none_or(|max| away < max));
+            if let Some(bucket) = bucket {
+                bucket.reviews += 1;
+                bucket.passed += passed as u32;
+            }
+        }
+        let count = |map: &mut HashMap<String, Accuracy>, name: &str| {
+            let acc = map
+                .entry(name.to_owned())
+                .or_insert_with(|| Accuracy { name: name.to_owned(), reviews: 0, passed: 0 });
+            acc.reviews += 1;
+            acc.passed += passed as u32;
+        };
+        count(&mut decks, deck.as_deref().unwrap_or(NO_DECK));
+        for tag in card_tags.iter().flat_map(|t| t.split(',')).map(str::trim).filter(|t| !t.is_empty()) {
+            count(&mut tags, tag);
+        }
+    }
+
+    let mut maturity = Maturity::default();
+    let mut forecast: Vec<DueDay> =
+        (0..FORECAST_DAYS).map(|i| DueDay { date: today + Days::new(i.into()), due: 0 }).collect();
+    let mut stmt = conn.prepare(
+        "SELECT interval, next_due, phase = 'new' FROM cards",
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, bool>(2)?)))?;
+    for row in rows {
+        let (interval, next_due, new) = row?;
+        if new {
+            // New cards are introduced at the reviewer's pace, not forecast.
+            maturity.new += 1;
+            continue;
+        }
+        if interval >= MATURE_DAYS.into() {
+            maturity.mature += 1;
+        } else {
+            maturity.young += 1;
+        }
+        let ahead = (date(next_due) - today).num_days().max(0) as usize;
+        if let Some(day) = forecast.get_mut(ahead) {
+            day.due += 1;
+        }
+    }
+
+    let total_secs = per_day.values().map(|d| d.secs).sum();
+    let today_secs = per_day.get(&today).map_or(0, |d| d.secs);
+    Ok(Stats {
+        days,
+        reviews: per_day.into_values().collect(),
+        retention,
+        streak: streak(&studied, today),
+        time: TimeSpent {
+            total_secs,
+            today_secs,
+            secs_per_review: (timed > 0).then(|| timed_ms as f32 / 1000.0 / timed as f32),
+        },
+        maturity,
+        forecast,
+        decks: ranked(decks),
+        tags: ranked(tags),
+    })
+}
+
+fn streak(studied: &BTreeSet<NaiveDate>, today: NaiveDate) -> Streak {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut prev: Option<NaiveDate> = None;
+    for &day in studied {
+        run = if prev.and_then(|p| p.succ_opt()) == Some(day) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        prev = Some(day);
+    }
+    let reviewed_today = studied.contains(&today);
+    // Today still counts as part of yesterday's streak until it is over.
+    let mut day = if reviewed_today { Some(today) } else { today.pred_opt() };
+    let mut current = 0;
+    while let Some(d) = day.filter(|d| studied.contains(d)) {
+        current += 1;
+        day = d.pred_opt();
+    }
+    Streak { current, longest, reviewed_today }
+}
+
+fn ranked(map: HashMap<String, Accuracy>) -> Vec<Accuracy> {
+    let mut list: Vec<Accuracy> = map.into_values().collect();
+    list.sort_by(|a, b| b.reviews.cmp(&a.reviews).then_with(|| a.name.cmp(&b.name)));
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CardJson, CardSchedule, Phase, ReviewLog};
+    use chrono::Duration;
+
+    #[test]
+    fn summarises_review_history() {
+        let (_dir, pool) = crate::testing::temp_pool();
+        let card = |deck: Option<&str>, tags: &[&str]| CardJson {
+            id: 0,
+            front: "f".into(),
+            back: "b".into(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            source: None,
+            media: None,
+            deck: deck.map(Into::into),
+        };
+        let rust = crate::insert_card(&pool, &card(Some("Rust"), &["memory"])).unwrap();
+        let french = crate::insert_card(&pool, &card(None, &["vocab", "memory"])).unwrap();
+        crate::insert_card(&pool, &card(None, &[])).unwrap();
+
+        // 09:00 in UTC+2, so every review below lands on the local day it says.
+        let clock = StudyClock::new("Etc/GMT-2".parse().unwrap(), 4);
+        let now = DateTime::parse_from_rfc3339("2026-03-10T09:00:00+02:00").unwrap().to_utc();
+        let review = |card_id, days_ago: i64, passed, duration_ms| {
+            let reviewed_at = now - Duration::days(days_ago);
+            crate::log_review(&pool, &ReviewLog { card_id, reviewed_at, passed, grade: None, duration_ms }).unwrap();
+        };
+        // Rust: seen 10, 9 and 2 days ago and today; French 3 and 2 days ago.
+        review(rust, 10, true, None);
+        review(rust, 9, true, Some(4_000));
+        review(rust, 2, false, Some(10_000));
+        review(rust, 0, true, Some(6_000));
+        review(french, 3, true, None);
+        review(french, 2, true, Some(2_000));
+        let due = |days: i64, interval| CardSchedule {
+            efactor: 2.5,
+            interval,
+            next_due: now + Duration::days(days),
+            phase: Phase::Review,
+            step: 0,
+        };
+        crate::update_schedule(&pool, rust, &due(1, 30)).unwrap();
+        crate::update_schedule(&pool, french, &due(-4, 6)).unwrap();
+
+        let stats = stats(&pool, now, &clock, 7).unwrap();
+        assert_eq!(stats.reviews.len(), 7);
+        assert_eq!(stats.reviews[0].date, NaiveDate::from_ymd_opt(2026, 3, 4).unwrap());
+        let per_day: Vec<_> = stats.reviews.iter().map(|d| (d.reviews, d.passed)).collect();
+        assert_eq!(per_day, [(0, 0), (0, 0), (0, 0), (1, 1), (2, 1), (0, 0), (1, 1)]);
+
+        // Rust was away 7 days before failing and 2 before passing; French 1 day.
+        let rates: Vec<_> = stats.retention.iter().map(|b| (b.reviews, b.passed)).collect();
+        assert_eq!(rates, [(0, 0), (2, 2), (1, 0), (0, 0), (0, 0)]);
+
+        assert_eq!(stats.streak, Streak { current: 1, longest: 2, reviewed_today: true });
+        assert_eq!((stats.time.total_secs, stats.time.today_secs), (18, 6));
+        assert_eq!(stats.time.secs_per_review, Some(6.0), "untimed reviews are left out");
+        assert_eq!(stats.maturity, Maturity { new: 1, young: 1, mature: 1 });
+        assert_eq!((stats.forecast[0].due, stats.forecast[1].due), (1, 1), "overdue cards are due today");
+
+        let decks: Vec<_> = stats.decks.iter().map(|a| (a.name.as_str(), a.reviews, a.passed)).collect();
+        assert_eq!(decks, [("(no deck)", 2, 2), ("Rust", 2, 1)]);
+        assert_eq!(stats.tags[0], Accuracy { name: "memory".into(), reviews: 4, passed: 3 });
+    }
+}
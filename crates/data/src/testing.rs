@@ -0,0 +1,12 @@
+//! Fixtures shared by this crate's tests.
+
+use crate::DbPool;
+use tempfile::TempDir;
+
+/// A fresh database in a directory of its own, removed with the returned
+/// [`TempDir`] – keep it alive for as long as the pool is used.
+pub(crate) fn temp_pool() -> (TempDir, DbPool) {
+    let dir = tempfile::tempdir().unwrap();
+    let pool = crate::new_pool(dir.path().join("oakley.db").to_str().unwrap()).unwrap();
+    (dir, pool)
+}
@@ -0,0 +1,243 @@
+//! Split extracted text into sections sized for one generation call each.
+//! Sections start at headings when the previous one is big enough, and long
+//! sections are cut at paragraph (then sentence) boundaries.
+
+/// Size bounds for a section, in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// A heading only starts a new section once the current one has this much text.
+    pub min_chars: usize,
+    pub max_chars: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self { min_chars: 400, max_chars: 3000 }
+    }
+}
+
+/// One section of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Position in the document; stable for the same input and limits.
+    pub index: usize,
+    /// Nearest heading above the text.
+    pub heading: Option<String>,
+    /// Fragment id of that heading, for sources that have one (web pages).
+    pub anchor: Option<String>,
+    /// First and last page (1-based) the text came from.
+    pub pages: (u32, u32),
+    pub text: String,
+}
+
+/// A unit of document structure: a heading or a paragraph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Heading { text: String, anchor: Option<String> },
+    Para(String),
+}
+
+/// Cut `pages` (page number, text) into sections, guessing headings from
+/// the text layout.
+pub fn sections(pages: &[(u32, &str)], limits: Limits) -> Vec<Chunk> {
+    build(pages.iter().flat_map(|&(page, text)| items(text).into_iter().map(move |b| (page, b))), limits)
+}
+
+/// Cut already structured `blocks` (e.g. from HTML) into sections. They
+/// count as a single page.
+pub fn from_blocks(blocks: &[Block], limits: Limits) -> Vec<Chunk> {
+    build(blocks.iter().map(|b| (1, b.clone())), limits)
+}
+
+fn build(blocks: impl Iterator<Item = (u32, Block)>, limits: Limits) -> Vec<Chunk> {
+    let mut out = Vec::new();
+    let mut cur = Draft::default();
+    for (page, block) in blocks {
+        match block {
+            Block::Heading { text, anchor } => {
+                if cur.text.len() >= limits.min_chars {
+                    cur.flush(&mut out);
+                }
+                if cur.text.is_empty() {
+                    cur.heading = Some(text);
+                    cur.anchor = anchor;
+                } else {
+                    // Too little text under the previous heading to stand alone.
+                    cur.push(page, &text);
+                }
+            }
+            Block::Para(p) => {
+                for piece in split_long(&p, limits.max_chars) {
+                    if !cur.text.is_empty() && cur.text.len() + piece.len() + 2 > limits.max_chars {
+                        let (heading, anchor) = (cur.heading.clone(), cur.anchor.clone());
+                        cur.flush(&mut out);
+                        (cur.heading, cur.anchor) = (heading, anchor);
+                    }
+                    cur.push(page, piece);
+                }
+            }
+        }
+    }
+    cur.flush(&mut out);
+    out
+}
+
+#[derive(Default)]
+struct Draft {
+    heading: Option<String>,
+    anchor: Option<String>,
+    pages: Option<(u32, u32)>,
+    text: String,
+}
+
+impl Draft {
+    fn push(&mut self, page: u32, text: &str) {
+        if !self.text.is_empty() {
+            self.text.push_str("\n\n");
+        }
+        self.text.push_str(text);
+        self.pages = Some(self.pages.map_or((page, page), |(first, _)| (first, page)));
+    }
+
+    fn flush(&mut self, out: &mut Vec<Chunk>) {
+        let draft = std::mem::take(self);
+        if let (Some(pages), false) = (draft.pages, draft.text.trim().is_empty()) {
+            out.push(Chunk { index: out.len(), heading: draft.heading, anchor: draft.anchor, pages, text: draft.text });
+        }
+    }
+}
+
+/// Headings and paragraphs of one page. Lines within a paragraph are
+/// joined, undoing end-of-line hyphenation.
+fn items(text: &str) -> Vec<Block> {
+    let mut items = Vec::new();
+    let mut para = String::new();
+    let end_para = |para: &mut String, items: &mut Vec<Block>| {
+        if !para.is_empty() {
+            items.push(Block::Para(std::mem::take(para)));
+        }
+    };
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            end_para(&mut para, &mut items);
+        } else if let Some(h) = heading(line) {
+            end_para(&mut para, &mut items);
+            items.push(Block::Heading { text: h, anchor: None });
+        } else if para.ends_with('-') && line.starts_with(char::is_lowercase) {
+            para.pop();
+            para.push_str(line);
+        } else {
+            if !para.is_empty() {
+                para.push(' ');
+            }
+            para.push_str(line);
+        }
+    }
+    end_para(&mut para, &mut items);
+    items
+}
+
+/// The heading text if `line` looks like one: Markdown `#`, numbered
+/// ("2.1 Ownership"), "Chapter …"/"Section …", or a short all-caps line.
+pub fn heading(line: &str) -> Option<String> {
+    let line = line.trim();
+    if line.is_empty() || line.chars().count() > 80 {
+        return None;
+    }
+    let hashes = line.bytes().take_while(|&b| b == b'#').count();
+    if hashes > 0 {
+        let h = line[hashes..].strip_prefix(' ').map(str::trim).filter(|h| hashes <= 6 && !h.is_empty());
+        return h.map(str::to_string);
+    }
+    if line.ends_with(['.', ',', ';', ':']) {
+        return None;
+    }
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let numbered = words.len() >= 2
+        && words[0].trim_end_matches('.').split('.').all(|n| !n.is_empty() && n.len() <= 3 && n.bytes().all(|b| b.is_ascii_digit()))
+        && words[1].starts_with(char::is_uppercase);
+    let titled = words.len() >= 2
+        && matches!(words[0], "Chapter" | "CHAPTER" | "Section" | "SECTION" | "Part" | "PART" | "Appendix" | "APPENDIX");
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+    let shouting = letters >= 3 && words.len() <= 8 && !line.chars().any(char::is_lowercase);
+    (numbered || titled || shouting).then(|| line.to_string())
+}
+
+/// Cut an over-long paragraph at sentence ends, or hard at `max` when a
+/// single sentence is longer.
+fn split_long(para: &str, max: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = para;
+    while rest.len() > max {
+        let window = &rest[..floor_char_boundary(rest, max)];
+        let cut = window
+            .rmatch_indices(['.', '?', '!'])
+            .map(|(i, _)| i + 1)
+            .find(|&i| rest[i..].starts_with(' '))
+            .or_else(|| window.rfind(' '))
+            .filter(|&i| i > 0)
+            .unwrap_or(window.len());
+        pieces.push(rest[..cut].trim());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+fn floor_char_boundary(s: &str, mut i: usize) -> usize {
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_headings() {
+        assert_eq!(heading("## Borrowing").as_deref(), Some("Borrowing"));
+        assert_eq!(heading("2.1 Ownership rules").as_deref(), Some("2.1 Ownership rules"));
+        assert_eq!(heading("Chapter 4 Lifetimes").as_deref(), Some("Chapter 4 Lifetimes"));
+        assert_eq!(heading("INTRODUCTION").as_deref(), Some("INTRODUCTION"));
+        assert_eq!(heading("In 2024 Rust shipped a new edition."), None);
+        assert_eq!(heading("3 apples and 2 pears"), None);
+        assert_eq!(heading("Figure 2: results"), None);
+        assert_eq!(heading("#hashtag"), None);
+    }
+
+    #[test]
+    fn splits_by_heading_and_size_with_page_ranges() {
+        let p1 = "INTRODUCTION\nRust is a systems lan-\nguage.\n\nIt is fast.\n1 Ownership\nEach value has an owner.";
+        let p2 = "When the owner goes out of scope the value is dropped.\n\n2 Borrowing\nReferences borrow.";
+        let chunks = sections(&[(1, p1), (2, p2)], Limits { min_chars: 20, max_chars: 80 });
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].heading.as_deref(), Some("INTRODUCTION"));
+        assert_eq!(chunks[0].text, "Rust is a systems language.\n\nIt is fast.");
+        assert_eq!((chunks[1].heading.as_deref(), chunks[1].pages), (Some("1 Ownership"), (1, 2)));
+        assert_eq!(chunks[2].index, 2);
+        assert_eq!((chunks[2].text.as_str(), chunks[2].pages), ("References borrow.", (2, 2)));
+
+        // A short intro is merged into the next section instead of standing alone.
+        let merged = sections(&[(1, "# A\nhi\n# B\nthere")], Limits { min_chars: 20, max_chars: 80 });
+        assert_eq!(merged.len(), 1);
+        assert_eq!((merged[0].heading.as_deref(), merged[0].text.as_str()), (Some("A"), "hi\n\nB\n\nthere"));
+
+        let long = "One sentence here. Another one follows. ".repeat(5);
+        let cut = sections(&[(1, &long)], Limits { min_chars: 0, max_chars: 60 });
+        assert!(cut.len() > 1 && cut.iter().all(|c| c.text.len() <= 60 && c.text.ends_with('.')));
+    }
+
+    #[test]
+    fn keeps_anchors_of_marked_up_headings() {
+        let h = |t: &str, a: &str| Block::Heading { text: t.into(), anchor: Some(a.into()) };
+        let blocks = [h("Intro", "intro"), Block::Para("x".repeat(30)), h("Usage", "usage"), Block::Para("y".repeat(50))];
+        let chunks = from_blocks(&blocks, Limits { min_chars: 20, max_chars: 40 });
+        let anchors: Vec<_> = chunks.iter().map(|c| c.anchor.as_deref()).collect();
+        assert_eq!(anchors, [Some("intro"), Some("usage"), Some("usage")]);
+    }
+}
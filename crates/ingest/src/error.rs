@@ -0,0 +1,33 @@
+//! Errors returned by the `ingest` crate.
+
+use utils::error::Coded;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("could not read document: {0}")]
+    Io(#[from] std::io::Error),
+    /// Damaged, encrypted or otherwise unreadable PDF.
+    #[error("PDF could not be parsed: {0}")]
+    Pdf(String),
+    /// `pdftoppm` missing or failing on a scanned page.
+    #[error("PDF page could not be rendered: {0}")]
+    Render(String),
+    #[error("rendered page could not be decoded: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("no text found in {0}")]
+    Empty(String),
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "ingest.io",
+            Error::Pdf(_) => "ingest.bad_pdf",
+            Error::Render(_) => "ingest.render_failed",
+            Error::Image(_) => "ingest.bad_image",
+            Error::Empty(_) => "ingest.empty",
+        }
+    }
+}
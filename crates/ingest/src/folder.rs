@@ -0,0 +1,102 @@
+//! Watched-folder helpers: which files can be imported, where they are, and
+//! which deck their cards belong to.
+
+use std::path::{Path, PathBuf};
+
+/// How a file is imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Pdf,
+    /// Screenshot or photo; generated from like a capture.
+    Image,
+    /// Markdown or plain text, split into sections like a PDF.
+    Text,
+    /// Saved web page; the article is extracted and split at its headings.
+    Html,
+}
+
+/// Import kind by extension; `None` for files we leave alone.
+pub fn kind(path: &Path) -> Option<Kind> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "pdf" => Some(Kind::Pdf),
+        "png" | "jpg" | "jpeg" | "webp" => Some(Kind::Image),
+        "md" | "markdown" | "txt" => Some(Kind::Text),
+        "html" | "htm" => Some(Kind::Html),
+        _ => None,
+    }
+}
+
+/// Deck for a file under the watched folder `root`: the folder names from
+/// `root` down to the file, joined with `::`. `Lectures/Biology/week1.pdf`
+/// under `Lectures` lands in `"Lectures::Biology"`.
+pub fn deck(root: &Path, path: &Path) -> String {
+    let dirs = path.parent().and_then(|p| p.strip_prefix(root).ok()).unwrap_or(Path::new(""));
+    root.file_name()
+        .into_iter()
+        .chain(dirs.iter())
+        .map(|c| c.to_string_lossy().trim().replace(',', " "))
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Importable files below `root`, recursively and in a stable order. Hidden
+/// files and folders, editor lock files (`~$…`) and the `…_files` folders
+/// browsers save page assets into are skipped.
+pub fn scan(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') || name.starts_with("~$") {
+                continue;
+            }
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if !name.ends_with("_files") {
+                    dirs.push(entry.path());
+                }
+            } else if file_type.is_file() && kind(&entry.path()).is_some() {
+                out.push(entry.path());
+            }
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_importable_files_and_their_decks() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Lectures");
+        let files = [
+            "intro.md",
+            "Biology/Cells/slide.PNG",
+            "Biology/notes.txt",
+            "Biology/.hidden.md",
+            ".git/x.md",
+            "a.docx",
+            "Page_files/logo.png",
+        ];
+        for f in files {
+            let path = root.join(f);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "x").unwrap();
+        }
+
+        let files = scan(&root).unwrap();
+        let rel: Vec<_> = files.iter().map(|p| p.strip_prefix(&root).unwrap().to_path_buf()).collect();
+        assert_eq!(rel, ["Biology/Cells/slide.PNG", "Biology/notes.txt", "intro.md"].map(PathBuf::from));
+        assert_eq!(kind(&files[0]), Some(Kind::Image));
+        assert_eq!(deck(&root, &files[0]), "Lectures::Biology::Cells");
+        assert_eq!(deck(&root, &files[2]), "Lectures");
+    }
+}
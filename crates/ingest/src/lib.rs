@@ -0,0 +1,249 @@
+//! Document ingestion: turn files into text sections ready for card
+//! generation. Persisting progress and queueing is up to the caller.
+
+pub mod chunk;
+mod error;
+pub mod folder;
+pub mod pdf;
+pub mod web;
+
+pub use chunk::{Block, Chunk, Limits};
+pub use error::{Error, Result};
+pub use pdf::Page;
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use utils::config::OcrConfig;
+use utils::source::Source;
+
+/// Where a document came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    File(PathBuf),
+    /// Web page, by the address it calls canonical when it names one.
+    Web(String),
+}
+
+/// Extracted text of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    /// Page text with headings guessed from the layout. `numbered` when the
+    /// page numbers mean something (PDFs); plain text is one long "page".
+    Pages { pages: Vec<Page>, numbered: bool },
+    /// Headings and paragraphs as marked up (web pages).
+    Blocks(Vec<Block>),
+}
+
+/// An extracted document.
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub origin: Origin,
+    pub title: Option<String>,
+    /// SHA-256 of the file contents (of the article text for web pages, whose
+    /// markup changes between fetches); identifies the document across renames.
+    pub hash: String,
+    pub content: Content,
+}
+
+/// Hex SHA-256 of `bytes`.
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+impl Document {
+    /// Read and extract a PDF; scanned pages are OCR'd when possible.
+    pub fn open_pdf(path: &Path, ocr: &OcrConfig) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let pages = pdf::pages(path, &bytes, ocr)?;
+        Self::from_pages(path, &bytes, pages, true)
+    }
+
+    /// Read a Markdown or plain-text file.
+    pub fn open_text(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let pages = vec![Page { number: 1, text: String::from_utf8_lossy(&bytes).into_owned(), ocr: false }];
+        Self::from_pages(path, &bytes, pages, false)
+    }
+
+    fn from_pages(path: &Path, bytes: &[u8], pages: Vec<Page>, numbered: bool) -> Result<Self> {
+        if pages.iter().all(|p| p.text.trim().is_empty()) {
+            return Err(Error::Empty(path.display().to_string()));
+        }
+        Ok(Self {
+            origin: Origin::File(path.to_path_buf()),
+            title: None,
+            hash: content_hash(bytes),
+            content: Content::Pages { pages, numbered },
+        })
+    }
+
+    /// Read a saved web page. Cards link to the page's canonical address
+    /// when it names one, otherwise to the file.
+    pub fn open_html(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_html(&String::from_utf8_lossy(&bytes), Origin::File(path.to_path_buf()))
+    }
+
+    /// Extract the article from a page fetched from (or saved at) `origin`.
+    pub fn from_html(html: &str, origin: Origin) -> Result<Self> {
+        let article = web::article(html);
+        let text = article.text();
+        if text.trim().is_empty() {
+            return Err(Error::Empty(Self::describe(&origin)));
+        }
+        Ok(Self {
+            origin: article.url.map_or(origin, Origin::Web),
+            title: article.title,
+            hash: content_hash(text.as_bytes()),
+            content: Content::Blocks(article.blocks),
+        })
+    }
+
+    /// File path or web address, as recorded for the import.
+    pub fn location(&self) -> String {
+        Self::describe(&self.origin)
+    }
+
+    fn describe(origin: &Origin) -> String {
+        match origin {
+            Origin::File(path) => path.display().to_string(),
+            Origin::Web(url) => url.clone(),
+        }
+    }
+
+    /// Title, else file name or address.
+    pub fn name(&self) -> String {
+        if let Some(title) = &self.title {
+            return title.clone();
+        }
+        match &self.origin {
+            Origin::File(path) => {
+                path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned())
+            }
+            Origin::Web(url) => url.clone(),
+        }
+    }
+
+    /// Sections to generate cards from, in document order.
+    pub fn chunks(&self, limits: Limits) -> Vec<Chunk> {
+        match &self.content {
+            Content::Pages { pages, .. } => {
+                let pages: Vec<(u32, &str)> = pages.iter().map(|p| (p.number, p.text.as_str())).collect();
+                chunk::sections(&pages, limits)
+            }
+            Content::Blocks(blocks) => chunk::from_blocks(blocks, limits),
+        }
+    }
+
+    /// Card source for a section: the document opened at the section (its
+    /// first page, or its heading on a web page) and a title like
+    /// `"notes.pdf, p. 3–4 — 2.1 Ownership"`.
+    pub fn source(&self, chunk: &Chunk) -> Source {
+        let name = self.name();
+        let mut title = name.clone();
+        let mut url = match &self.origin {
+            Origin::File(path) => file_url(path),
+            Origin::Web(url) => url.split('#').next().unwrap_or_default().to_string(),
+        };
+        match &self.content {
+            Content::Pages { numbered: true, .. } => {
+                match chunk.pages {
+                    (a, b) if a == b => title.push_str(&format!(", p. {a}")),
+                    (a, b) => title.push_str(&format!(", pp. {a}–{b}")),
+                }
+                url.push_str(&format!("#page={}", chunk.pages.0));
+            }
+            Content::Pages { .. } => {}
+            Content::Blocks(_) => match (&chunk.anchor, &chunk.heading) {
+                (Some(id), _) => {
+                    url.push('#');
+                    url.push_str(&escape(id, |b| matches!(b, b'-' | b'_' | b'.' | b'~')));
+                }
+                // Browsers scroll to a text fragment when the heading has no id.
+                (None, Some(h)) if *h != name => {
+                    url.push_str("#:~:text=");
+                    url.push_str(&escape(h, |b| matches!(b, b'_' | b'.' | b'~')));
+                }
+                (None, _) => {}
+            },
+        }
+        if let Some(h) = chunk.heading.as_ref().filter(|h| **h != name) {
+            title.push_str(" — ");
+            title.push_str(h);
+        }
+        Source { window_title: Some(title), url: Some(url), ..Source::now() }
+    }
+}
+
+/// `file://` URL for an absolute path, escaping what would break the URL.
+pub fn file_url(path: &Path) -> String {
+    format!("file://{}", escape(&path.to_string_lossy(), |b| matches!(b, b'/' | b'-' | b'_' | b'.' | b'~')))
+}
+
+/// Percent-encode everything but ASCII letters, digits and bytes `keep` allows.
+fn escape(s: &str, keep: impl Fn(u8) -> bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || keep(b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_point_at_the_first_page() {
+        let doc = Document {
+            origin: Origin::File(PathBuf::from("/home/me/Lecture 3.pdf")),
+            title: None,
+            hash: content_hash(b"pdf"),
+            content: Content::Pages { pages: vec![Page { number: 4, text: "text".into(), ocr: false }], numbered: true },
+        };
+        let chunk =
+            Chunk { index: 0, heading: Some("Borrowing".into()), anchor: None, pages: (4, 5), text: "text".into() };
+        let src = doc.source(&chunk);
+        assert_eq!(src.window_title.as_deref(), Some("Lecture 3.pdf, pp. 4–5 — Borrowing"));
+        assert_eq!(src.url.as_deref(), Some("file:///home/me/Lecture%203.pdf#page=4"));
+        assert_eq!(doc.hash.len(), 64);
+
+        let notes = Document {
+            origin: Origin::File(PathBuf::from("/notes.md")),
+            content: Content::Pages { pages: vec![], numbered: false },
+            ..doc
+        };
+        assert_eq!(notes.source(&chunk).window_title.as_deref(), Some("notes.md — Borrowing"));
+        assert_eq!(notes.source(&chunk).url.as_deref(), Some("file:///notes.md"));
+    }
+
+    #[test]
+    fn web_sources_link_to_the_section() {
+        let html = include_str!("../tests/fixtures/docs.html");
+        let doc = Document::from_html(html, Origin::Web("https://guide.example.org/lifetimes?v=2#top".into())).unwrap();
+        let chunks = doc.chunks(Limits { min_chars: 100, max_chars: 3000 });
+        let urls: Vec<_> = chunks.iter().map(|c| doc.source(c).url.unwrap()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://guide.example.org/lifetimes?v=2#:~:text=Lifetimes",
+                "https://guide.example.org/lifetimes?v=2#elision",
+                "https://guide.example.org/lifetimes?v=2#annotations",
+                "https://guide.example.org/lifetimes?v=2#:~:text=Static%20lifetimes",
+            ]
+        );
+        let title = doc.source(&chunks[1]).window_title.unwrap();
+        assert_eq!(title, "Lifetimes — The Guide — Elision");
+
+        // A saved copy of a page that names its canonical address links there.
+        let blog = Document::from_html(include_str!("../tests/fixtures/blog.html"), Origin::File("/tmp/saved.html".into()))
+            .unwrap();
+        assert_eq!(blog.location(), "https://blog.example.com/posts/ownership");
+        let again = Document::from_html(&html.replace("<style>", "<style>/* new build */"), Origin::Web("x".into())).unwrap();
+        assert_eq!(again.hash, doc.hash, "markup-only changes keep the hash");
+    }
+}
@@ -0,0 +1,87 @@
+//! Per-page PDF text. Pages without a usable text layer (scans) are rendered
+//! with poppler's `pdftoppm` and run through OCR when an engine is built in.
+
+use crate::{Error, Result};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::{info, warn};
+use utils::config::OcrConfig;
+
+/// Pages with fewer visible characters than this are treated as scanned.
+const MIN_TEXT_CHARS: usize = 40;
+/// Render resolution for OCR; Tesseract does best around 200–300 dpi.
+const RENDER_DPI: u32 = 200;
+
+/// Text of one page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// 1-based.
+    pub number: u32,
+    pub text: String,
+    /// The text came from OCR rather than the PDF's text layer.
+    pub ocr: bool,
+}
+
+/// Extract every page of the PDF at `path` (contents in `bytes`).
+pub fn pages(path: &Path, bytes: &[u8], cfg: &OcrConfig) -> Result<Vec<Page>> {
+    // pdf-extract panics on some malformed files instead of returning an error.
+    let texts = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(bytes))
+        .map_err(|_| Error::Pdf("parser crashed on this file".into()))?
+        .map_err(|e| Error::Pdf(e.to_string()))?;
+
+    let mut engine = None;
+    let mut pages = Vec::with_capacity(texts.len());
+    for (i, text) in texts.into_iter().enumerate() {
+        let number = i as u32 + 1;
+        if visible_chars(&text) >= MIN_TEXT_CHARS {
+            pages.push(Page { number, text, ocr: false });
+            continue;
+        }
+        let engine = match engine.get_or_insert_with(|| ocr::engine(&cfg.language)) {
+            Ok(engine) => engine,
+            Err(e) => {
+                warn!(page = number, error = %e, "page has no text layer and OCR is unavailable");
+                pages.push(Page { number, text, ocr: false });
+                continue;
+            }
+        };
+        let ocr_text = render(path, number).map(|img| ocr::recognize(engine.as_ref(), &img));
+        match ocr_text {
+            Ok(Ok(out)) => {
+                info!(page = number, "🔎 Scanned page read with OCR");
+                pages.push(Page { number, text: out.text(cfg.min_confidence), ocr: true });
+            }
+            Ok(Err(e)) => {
+                warn!(page = number, error = %e, "OCR failed on scanned page");
+                pages.push(Page { number, text, ocr: false });
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(pages)
+}
+
+fn visible_chars(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// Render one page to an image with `pdftoppm`.
+fn render(path: &Path, page: u32) -> Result<image::RgbaImage> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().join("page");
+    let n = page.to_string();
+    let out = Command::new("pdftoppm")
+        .args(["-f", &n, "-l", &n, "-r", &RENDER_DPI.to_string(), "-png", "-singlefile"])
+        .arg(path)
+        .arg(&root)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Error::Render("pdftoppm not found; install poppler-utils".into()),
+            _ => Error::Render(e.to_string()),
+        })?;
+    if !out.status.success() {
+        return Err(Error::Render(String::from_utf8_lossy(&out.stderr).trim().to_string()));
+    }
+    Ok(image::open(root.with_extension("png"))?.to_rgba8())
+}
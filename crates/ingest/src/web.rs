@@ -0,0 +1,372 @@
+//! Readable article content from a web page. The main content element is
+//! found by scoring paragraphs (after Mozilla's Readability); navigation,
+//! sidebars, comment threads and link lists are dropped, and headings keep
+//! their fragment ids so each section can be linked to.
+
+use crate::chunk::Block;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::HashMap;
+
+/// Never content, whatever their classes say.
+const SKIP_TAGS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "svg", "canvas", "iframe", "nav", "aside", "footer", "form",
+    "button", "select", "input", "textarea", "dialog",
+];
+
+/// Class or id words of page furniture.
+const BOILERPLATE: &[&str] = &[
+    "ad", "ads", "advert", "advertisement", "banner", "breadcrumb", "breadcrumbs", "comment", "comments", "cookie",
+    "cookies", "footer", "menu", "modal", "nav", "navbar", "newsletter", "popup", "promo", "related", "share",
+    "sharing", "sidebar", "social", "sponsored", "subscribe", "toc",
+];
+
+/// Class or id words of the main text.
+const LIKELY: &[&str] = &["article", "body", "content", "entry", "main", "post", "prose", "story", "text"];
+
+/// ARIA roles of page furniture.
+const BOILERPLATE_ROLES: &[&str] = &["navigation", "complementary", "contentinfo", "banner", "dialog", "search"];
+
+/// Elements that end the running paragraph.
+const BLOCK_TAGS: &[&str] = &[
+    "address", "article", "blockquote", "center", "dd", "details", "div", "dl", "dt", "figcaption", "figure",
+    "header", "hr", "li", "main", "p", "section", "summary",
+];
+
+/// Paragraphs shorter than this do not vote for their container.
+const MIN_SCORED_CHARS: usize = 25;
+
+/// The readable part of a page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    pub title: Option<String>,
+    /// Canonical address the page names for itself (`<link rel=canonical>`
+    /// or `og:url`); lets a saved copy point back at the original.
+    pub url: Option<String>,
+    pub blocks: Vec<Block>,
+}
+
+impl Article {
+    /// Plain text of the whole article, headings included.
+    pub fn text(&self) -> String {
+        let parts: Vec<&str> = self
+            .blocks
+            .iter()
+            .map(|b| match b {
+                Block::Heading { text, .. } | Block::Para(text) => text.as_str(),
+            })
+            .collect();
+        parts.join("\n\n")
+    }
+}
+
+/// Extract the article from an HTML document.
+pub fn article(html: &str) -> Article {
+    let doc = Html::parse_document(html);
+    let root = content_root(&doc).unwrap_or_else(|| doc.root_element());
+
+    let mut walker = Walker::default();
+    walker.children(root);
+    walker.flush();
+
+    let meta = |sel: &str, attr: &str| {
+        doc.select(&selector(sel))
+            .next()
+            .and_then(|m| m.value().attr(attr))
+            .map(|v| collapse(std::iter::once(v)))
+            .filter(|v| !v.is_empty())
+    };
+    let title = meta(r#"meta[property="og:title"]"#, "content")
+        .or_else(|| doc.select(&selector("title")).next().map(|t| collapse(t.text())).filter(|t| !t.is_empty()))
+        .or_else(|| doc.select(&selector("h1")).next().map(|h| collapse(h.text())).filter(|t| !t.is_empty()));
+    let url = meta(r#"link[rel="canonical"]"#, "href")
+        .or_else(|| meta(r#"meta[property="og:url"]"#, "content"))
+        .filter(|u| u.starts_with("https://") || u.starts_with("http://"));
+
+    Article { title, url, blocks: walker.blocks }
+}
+
+fn selector(s: &str) -> Selector {
+    Selector::parse(s).expect("valid selector")
+}
+
+/// Whitespace-collapsed concatenation of text nodes.
+fn collapse<'a>(parts: impl Iterator<Item = &'a str>) -> String {
+    parts.collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn visible_len(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// Lower-cased words of the class and id attributes.
+fn words(el: ElementRef<'_>) -> impl Iterator<Item = String> + '_ {
+    let v = el.value();
+    v.attr("class")
+        .into_iter()
+        .chain(v.attr("id"))
+        .flat_map(|a| a.split(|c: char| !c.is_ascii_alphanumeric()))
+        .filter(|w| !w.is_empty())
+        .map(str::to_ascii_lowercase)
+}
+
+fn is_boilerplate(el: ElementRef) -> bool {
+    let v = el.value();
+    let name = v.name();
+    if SKIP_TAGS.contains(&name) || v.attr("hidden").is_some() || v.attr("aria-hidden") == Some("true") {
+        return true;
+    }
+    if v.attr("style").is_some_and(|s| s.replace(' ', "").to_ascii_lowercase().contains("display:none")) {
+        return true;
+    }
+    // Containers the content usually lives in carry site-wide classes
+    // (`<body class="has-sidebar">`); judge them by their children instead.
+    if matches!(name, "html" | "body" | "main" | "article") {
+        return false;
+    }
+    v.attr("role").is_some_and(|r| BOILERPLATE_ROLES.contains(&r)) || words(el).any(|w| BOILERPLATE.contains(&w.as_str()))
+}
+
+/// Share of an element's text that sits inside links.
+fn link_density(el: ElementRef) -> f32 {
+    let total = visible_len(&el.text().collect::<String>());
+    if total == 0 {
+        return 0.0;
+    }
+    let links: usize = el.select(&selector("a")).map(|a| visible_len(&a.text().collect::<String>())).sum();
+    links as f32 / total as f32
+}
+
+/// Starting score of a candidate container, from its tag and class names.
+fn initial_score(el: ElementRef) -> f32 {
+    let tag = match el.value().name() {
+        "article" | "main" => 10.0,
+        "div" | "section" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "dd" | "dl" | "dt" | "form" | "li" | "ol" | "ul" => -3.0,
+        "th" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => -5.0,
+        _ => 0.0,
+    };
+    let class: f32 = words(el)
+        .map(|w| {
+            if LIKELY.contains(&w.as_str()) {
+                25.0
+            } else if BOILERPLATE.contains(&w.as_str()) {
+                -25.0
+            } else {
+                0.0
+            }
+        })
+        .sum();
+    tag + class
+}
+
+/// The element holding the article: every substantial paragraph scores its
+/// parent fully and its grandparent by half, and the best container wins.
+/// When the winner's siblings scored too (an article split into
+/// `<section>`s), their common parent is taken instead.
+fn content_root(doc: &Html) -> Option<ElementRef<'_>> {
+    let body = doc.select(&selector("body")).next()?;
+    let mut scores = HashMap::new();
+    for p in body.select(&selector("p, pre, td")) {
+        if p.ancestors().filter_map(ElementRef::wrap).chain([p]).any(is_boilerplate) {
+            continue;
+        }
+        let text = collapse(p.text());
+        let len = text.chars().count();
+        if len < MIN_SCORED_CHARS {
+            continue;
+        }
+        let score = 1.0 + text.matches(',').count() as f32 + (len / 100).min(3) as f32;
+        for (el, share) in p.ancestors().filter_map(ElementRef::wrap).take(2).zip([1.0, 0.5]) {
+            scores.entry(el.id()).or_insert_with(|| (el, initial_score(el))).1 += score * share;
+        }
+    }
+    let scored: HashMap<_, f32> =
+        scores.into_values().map(|(el, score)| (el.id(), score * (1.0 - link_density(el)))).collect();
+    let (&top_id, &top_score) = scored.iter().max_by(|a, b| a.1.total_cmp(b.1))?;
+    let mut top = ElementRef::wrap(doc.tree.get(top_id)?)?;
+
+    while let Some(parent) = top.parent().and_then(ElementRef::wrap).filter(|p| p.value().name() != "html") {
+        let strong = parent
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|c| scored.get(&c.id()).is_some_and(|&s| s >= top_score * 0.2))
+            .count();
+        if strong < 2 {
+            break;
+        }
+        top = parent;
+    }
+    Some(top)
+}
+
+/// Fragment id for a heading: its own `id`, an `<a id|name>` inside it, or
+/// the id of the `<section>` it opens.
+fn anchor(h: ElementRef) -> Option<String> {
+    fn id_of(el: ElementRef) -> Option<String> {
+        let v = el.value();
+        v.attr("id")
+            .or_else(|| if v.name() == "a" { v.attr("name") } else { None })
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+    }
+    h.descendants().filter_map(ElementRef::wrap).find_map(id_of).or_else(|| {
+        let parent = h.parent().and_then(ElementRef::wrap)?;
+        let first = parent.children().filter_map(ElementRef::wrap).next()?;
+        if first.id() == h.id() {
+            id_of(parent)
+        } else {
+            None
+        }
+    })
+}
+
+/// Turns the content element into headings and paragraphs. Inline text is
+/// collected until the next block element.
+#[derive(Default)]
+struct Walker {
+    blocks: Vec<Block>,
+    inline: String,
+    /// Visible characters of `inline` that are link text.
+    link_chars: usize,
+}
+
+impl Walker {
+    fn children(&mut self, el: ElementRef) {
+        for node in el.children() {
+            match node.value() {
+                Node::Text(t) => self.inline.push_str(t),
+                Node::Element(_) => self.element(ElementRef::wrap(node).expect("element node")),
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, el: ElementRef) {
+        if is_boilerplate(el) {
+            return;
+        }
+        match el.value().name() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                let text = collapse(el.text());
+                if !text.is_empty() {
+                    self.blocks.push(Block::Heading { text, anchor: anchor(el) });
+                }
+            }
+            "pre" => {
+                self.flush();
+                let text: String = el.text().collect();
+                let text = text.trim_matches('\n').trim_end();
+                if !text.trim().is_empty() {
+                    self.blocks.push(Block::Para(text.to_string()));
+                }
+            }
+            "ul" | "ol" => {
+                self.flush();
+                // Menus, tag clouds and "read next" lists are mostly links.
+                if link_density(el) > 0.5 {
+                    return;
+                }
+                let items: Vec<String> = el
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|li| li.value().name() == "li" && !is_boilerplate(*li))
+                    .map(|li| collapse(li.text()))
+                    .filter(|t| !t.is_empty())
+                    .map(|t| format!("- {t}"))
+                    .collect();
+                if !items.is_empty() {
+                    self.blocks.push(Block::Para(items.join("\n")));
+                }
+            }
+            "table" => {
+                self.flush();
+                let rows: Vec<String> = el
+                    .select(&selector("tr"))
+                    .map(|tr| {
+                        let cells: Vec<String> = tr.select(&selector("td, th")).map(|c| collapse(c.text())).collect();
+                        cells.join("\t")
+                    })
+                    .filter(|r| !r.trim().is_empty())
+                    .collect();
+                if !rows.is_empty() {
+                    self.blocks.push(Block::Para(rows.join("\n")));
+                }
+            }
+            "br" => self.inline.push(' '),
+            "img" => {}
+            "a" => {
+                let start = self.inline.len();
+                self.children(el);
+                self.link_chars += visible_len(&self.inline[start..]);
+            }
+            name if BLOCK_TAGS.contains(&name) => {
+                self.flush();
+                self.children(el);
+                self.flush();
+            }
+            _ => self.children(el),
+        }
+    }
+
+    fn flush(&mut self) {
+        let raw = std::mem::take(&mut self.inline);
+        let links = std::mem::take(&mut self.link_chars);
+        let text = collapse(std::iter::once(raw.as_str()));
+        // A run that is mostly links is navigation, not prose.
+        if text.is_empty() || links * 2 > visible_len(&text) {
+            return;
+        }
+        self.blocks.push(Block::Para(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heading(text: &str, anchor: Option<&str>) -> Block {
+        Block::Heading { text: text.into(), anchor: anchor.map(str::to_string) }
+    }
+
+    #[test]
+    fn keeps_the_article_and_drops_page_furniture() {
+        let a = article(include_str!("../tests/fixtures/blog.html"));
+        assert_eq!(a.title.as_deref(), Some("Understanding Ownership"));
+        assert_eq!(a.url.as_deref(), Some("https://blog.example.com/posts/ownership"));
+
+        let text = a.text();
+        for furniture in ["Subscribe", "Home", "Popular posts", "Great post", "All rights reserved", "cookies", "Read next"] {
+            assert!(!text.contains(furniture), "{furniture:?} leaked into:\n{text}");
+        }
+        assert_eq!(a.blocks[0], heading("Understanding Ownership", None));
+        assert!(a.blocks.contains(&heading("The stack and the heap", Some("stack-and-heap"))));
+        assert!(a.blocks.contains(&heading("Ownership rules", Some("rules"))));
+        assert!(a.blocks.contains(&Block::Para(
+            "- Each value in Rust has an owner.\n- There can only be one owner at a time.".into()
+        )));
+        assert!(text.contains("Memory is managed through a system of ownership"));
+    }
+
+    #[test]
+    fn finds_anchors_of_sectioned_documentation() {
+        let a = article(include_str!("../tests/fixtures/docs.html"));
+        assert_eq!(a.title.as_deref(), Some("Lifetimes — The Guide"));
+        assert_eq!(a.url, None);
+
+        let headings: Vec<_> = a.blocks.iter().filter(|b| matches!(b, Block::Heading { .. })).collect();
+        assert_eq!(
+            headings,
+            [
+                &heading("Lifetimes", None),
+                &heading("Elision", Some("elision")),
+                &heading("Annotations", Some("annotations")),
+                &heading("Static lifetimes", None),
+            ]
+        );
+        assert!(a.blocks.contains(&Block::Para("fn longest<'a>(x: &'a str, y: &'a str) -> &'a str {\n    x\n}".into())));
+        assert!(a.blocks.contains(&Block::Para("Rule\tApplies to\nEach input gets a lifetime\tparameters".into())));
+    }
+}
@@ -0,0 +1,274 @@
+//! Local IPC between the Oakley daemon and its thin clients (CLI, Tauri shell).
+//!
+//! Messages are JSON documents framed by a 4-byte big-endian length prefix and
+//! exchanged over a Unix-domain socket. A connection is request/response until
+//! the client sends [`Request::Subscribe`], after which the daemon only pushes
+//! [`Event`]s down that connection.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use data::{CardJson, DuplicatePair, Import, Job, JobStatus, Similar, Stats};
+use scheduler::{DueSummary, Grade, Verdict};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::UnixStream;
+use utils::error::ErrorPayload;
+use utils::source::Source;
+
+/// Error code for a client that cannot connect to the daemon socket.
+pub const UNREACHABLE: &str = "daemon.unreachable";
+
+/// Upper bound for a single frame; protects both ends from garbage lengths.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Client → daemon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Ping,
+    ListCards,
+    /// Today's review queue: due cards within the `[queue]` limits, in order.
+    DueCards,
+    /// Pairs of cards that ask the same thing; `threshold` overrides
+    /// `dedupe.threshold`.
+    DedupeCards {
+        #[serde(default)]
+        threshold: Option<f32>,
+    },
+    /// Fold card `duplicate` into `keep` (see [`data::merge_cards`]).
+    MergeCards { keep: i64, duplicate: i64 },
+    /// Record a review; `grade` refines `passed` when the reviewer chose one,
+    /// `duration_ms` is how long the card was on screen.
+    Grade {
+        card_id: i64,
+        passed: bool,
+        #[serde(default)]
+        grade: Option<Grade>,
+        #[serde(default)]
+        duration_ms: Option<u32>,
+    },
+    /// Review statistics over the last `days` days (default 30).
+    Stats {
+        #[serde(default)]
+        days: Option<u32>,
+    },
+    /// Compare a typed answer with the back of the card and suggest a grade.
+    CheckAnswer { card_id: i64, answer: String },
+    /// Record a spoken answer from the microphone, or read `wav` (an absolute
+    /// path), and transcribe it; grade the text with [`Request::CheckAnswer`].
+    Transcribe {
+        #[serde(default)]
+        wav: Option<PathBuf>,
+    },
+    /// Read `text` aloud through the `[tts]` engine; replies once it is spoken.
+    Speak { text: String },
+    /// `source` describes where the text was selected, when the client knows.
+    GenerateFromText {
+        text: String,
+        #[serde(default)]
+        source: Option<Source>,
+    },
+    CaptureScreen,
+    /// Capture the focused window (or let the user pick one).
+    CaptureWindow,
+    /// Generate a card from the clipboard (text, HTML or image).
+    CaptureClipboard,
+    /// Split a document (absolute path to a PDF, saved web page, Markdown
+    /// or text file) into sections and queue a card for each. Re-importing
+    /// the same file queues only the sections still missing.
+    ImportFile { path: PathBuf },
+    /// Fetch a web page, keep its article text and import it like a file.
+    ImportUrl { url: String },
+    SnoozeReminders { minutes: i64 },
+    /// A stored screenshot by hash, or its thumbnail.
+    Media {
+        hash: String,
+        #[serde(default)]
+        thumb: bool,
+    },
+    /// Generation queue, optionally filtered by status.
+    ListJobs { status: Option<JobStatus> },
+    /// Re-queue one dead job, or all of them when `id` is `None`.
+    RetryJobs { id: Option<i64> },
+    /// Settle a screenshot held as a near-duplicate: `reuse` keeps the earlier
+    /// capture's card, otherwise a new card is generated.
+    ResolveDuplicate { job_id: i64, reuse: bool },
+    /// Turn this connection into an event stream.
+    Subscribe,
+}
+
+/// Daemon → client reply to a [`Request`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    /// `hotkeys` is set when the daemon owns the global hot-keys.
+    Pong {
+        version: String,
+        #[serde(default)]
+        hotkeys: bool,
+    },
+    Cards { cards: Vec<CardJson> },
+    /// `similar` lists existing cards that look like duplicates of a new one.
+    Card {
+        card: CardJson,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        similar: Vec<Similar>,
+    },
+    Duplicates { pairs: Vec<DuplicatePair> },
+    Graded { next_due: DateTime<Utc> },
+    Checked { verdict: Verdict },
+    Stats { stats: Stats },
+    Transcript { text: String },
+    /// `path` is the WAV written when `tts.output_dir` is set.
+    Spoken { path: Option<PathBuf> },
+    /// Generation could not finish now; the job stays queued and will be retried.
+    Queued { job_id: i64, retry_at: Option<DateTime<Utc>>, error: Option<ErrorPayload> },
+    /// The screenshot looks like job `duplicate_of`'s and is held until
+    /// [`Request::ResolveDuplicate`]; `card` is that job's card if generated.
+    Duplicate { job_id: i64, duplicate_of: i64, card: Option<CardJson> },
+    Jobs { jobs: Vec<Job> },
+    Retried { count: usize },
+    /// `data` is the file, base64-encoded.
+    Media { content_type: String, data: String },
+    /// `added` sections were queued by this request.
+    Imported { import: Import, added: usize },
+    Ok,
+    Subscribed,
+    Error { error: ErrorPayload },
+}
+
+/// Daemon → subscriber push notifications.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A capture was received and card generation started.
+    Generating,
+    /// A queued capture became a card; `job_id` is what submitting it
+    /// returned, `similar` the existing cards it may duplicate.
+    CardCreated {
+        card: CardJson,
+        job_id: i64,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        similar: Vec<Similar>,
+    },
+    CardsDue { summary: DueSummary },
+    /// The user asked (e.g. via a notification action) to start reviewing.
+    ReviewRequested,
+    /// A screenshot was held as a near-duplicate of job `duplicate_of`
+    /// instead of being generated; see [`Response::Duplicate`].
+    DuplicateCapture { job_id: i64, duplicate_of: i64, card: Option<CardJson> },
+    /// A generation attempt failed; `retry_at` is `None` once dead-lettered.
+    JobFailed { job_id: i64, retry_at: Option<DateTime<Utc>>, error: ErrorPayload },
+    /// A background (hot-key) operation failed; no request to reply to.
+    Error { error: ErrorPayload },
+}
+
+/// Write one length-prefixed JSON frame.
+pub async fn write_frame<W, T>(w: &mut W, msg: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let body = serde_json::to_vec(msg)?;
+    if body.len() > MAX_FRAME_LEN {
+        bail!("frame too large ({} bytes)", body.len());
+    }
+    w.write_u32(body.len() as u32).await?;
+    w.write_all(&body).await?;
+    w.flush().await?;
+    Ok(())
+}
+
+/// Read one frame. Returns `None` when the peer closed the connection cleanly.
+pub async fn read_frame<R, T>(r: &mut R) -> Result<Option<T>>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let len = match r.read_u32().await {
+        Ok(n) => n as usize,
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if len > MAX_FRAME_LEN {
+        bail!("frame too large ({len} bytes)");
+    }
+    let mut body = vec![0u8; len];
+    r.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Connection to a running daemon.
+pub struct Client {
+    stream: UnixStream,
+}
+
+impl Client {
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let stream = UnixStream::connect(path).await.map_err(|e| {
+            let mut err = ErrorPayload::new(
+                UNREACHABLE,
+                format!("cannot reach oakley daemon at {}: {e}", path.display()),
+            );
+            err.retryable = true;
+            err
+        })?;
+        Ok(Self { stream })
+    }
+
+    /// Send a request and wait for its response. `Response::Error` becomes an
+    /// `Err` wrapping the [`ErrorPayload`], so callers can downcast for the code.
+    pub async fn call(&mut self, req: &Request) -> Result<Response> {
+        write_frame(&mut self.stream, req).await?;
+        match read_frame(&mut self.stream).await? {
+            Some(Response::Error { error }) => Err(error.into()),
+            Some(resp) => Ok(resp),
+            None => bail!("daemon closed the connection"),
+        }
+    }
+
+    /// Switch the connection into event-stream mode.
+    pub async fn subscribe(mut self) -> Result<Subscription> {
+        match self.call(&Request::Subscribe).await? {
+            Response::Subscribed => Ok(Subscription { stream: self.stream }),
+            other => bail!("unexpected reply to subscribe: {other:?}"),
+        }
+    }
+}
+
+/// Stream of daemon [`Event`]s.
+pub struct Subscription {
+    stream: UnixStream,
+}
+
+impl Subscription {
+    /// Next event, or `None` once the daemon goes away.
+    pub async fn next(&mut self) -> Result<Option<Event>> {
+        read_frame(&mut self.stream).await
+    }
+}
+
+/// One-shot helper: connect, send a single request, return the response.
+pub async fn request(path: impl AsRef<Path>, req: &Request) -> Result<Response> {
+    Client::connect(path).await?.call(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn frames_round_trip() {
+        let (mut a, mut b) = tokio::io::duplex(1024);
+        write_frame(&mut a, &Request::Grade { card_id: 7, passed: true, grade: None, duration_ms: None }).await.unwrap();
+        drop(a);
+
+        match read_frame::<_, Request>(&mut b).await.unwrap() {
+            Some(Request::Grade { card_id: 7, passed: true, .. }) => {}
+            other => panic!("unexpected frame: {other:?}"),
+        }
+        assert!(read_frame::<_, Request>(&mut b).await.unwrap().is_none());
+    }
+}
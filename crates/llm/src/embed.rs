@@ -0,0 +1,164 @@
+//! Text embeddings for finding cards that ask the same thing in different
+//! words. Providers implement [`Embedder`]; [`LocalEmbedder`] works offline,
+//! [`ApiEmbedder`] calls an OpenAI-compatible `/embeddings` endpoint.
+
+use crate::Result;
+use async_trait::async_trait;
+use utils::config::{DedupeConfig, EmbeddingProvider, LlmConfig};
+
+/// Turns texts into unit-length vectors whose dot product tracks meaning.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Names the vector space. Vectors from different models are never compared.
+    fn model(&self) -> &str;
+
+    /// One vector per text, in order.
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// The embedder `[dedupe]` selects.
+pub fn embedder(dedupe: &DedupeConfig, llm: &LlmConfig) -> Box<dyn Embedder> {
+    match dedupe.provider {
+        EmbeddingProvider::Local => Box::new(LocalEmbedder),
+        EmbeddingProvider::Api => Box::new(ApiEmbedder { api_base: llm.api_base.clone(), model: dedupe.model.clone() }),
+    }
+}
+
+/// Dimensions of [`LocalEmbedder`] vectors.
+const DIMS: usize = 512;
+
+/// Words too common to say anything about a card.
+const STOP_WORDS: &[&str] = &[
+    "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from", "how", "in", "into", "is",
+    "it", "its", "of", "on", "or", "that", "the", "this", "to", "what", "when", "where", "which", "who", "why",
+    "with", "you", "your",
+];
+
+/// Offline embedder: hashes word stems and their character trigrams into a
+/// fixed-size vector. Catches rewordings that share vocabulary ("Explain Rust
+/// ownership" / "What is ownership in Rust?"), not synonyms; use the API
+/// provider for those.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalEmbedder;
+
+impl LocalEmbedder {
+    pub fn vector(text: &str) -> Vec<f32> {
+        let mut v = vec![0f32; DIMS];
+        let lower = text.to_lowercase();
+        let words = lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.chars().count() > 1 && !STOP_WORDS.contains(w))
+            .map(stem);
+        for word in words {
+            add(&mut v, &word, 1.0);
+            // Trigrams of the padded word tie together forms the stemmer misses.
+            let padded: Vec<char> = format!(" {word} ").chars().collect();
+            for gram in padded.windows(3) {
+                add(&mut v, &gram.iter().collect::<String>(), 0.25);
+            }
+        }
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            v.iter_mut().for_each(|x| *x /= norm);
+        }
+        v
+    }
+}
+
+#[async_trait]
+impl Embedder for LocalEmbedder {
+    fn model(&self) -> &str {
+        "local-hash-v1"
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| Self::vector(t)).collect())
+    }
+}
+
+/// Strip common English suffixes so "owners", "owned" and "owning" meet.
+fn stem(word: &str) -> String {
+    for suffix in ["ing", "ed", "es", "s"] {
+        if let Some(root) = word.strip_suffix(suffix) {
+            if root.chars().count() >= 3 && !root.ends_with('s') {
+                return root.to_string();
+            }
+        }
+    }
+    word.to_string()
+}
+
+/// Signed feature hashing (FNV-1a), so collisions cancel out rather than pile up.
+fn add(v: &mut [f32], feature: &str, weight: f32) {
+    let hash = feature.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100_0000_01b3));
+    let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+    v[(hash % DIMS as u64) as usize] += sign * weight;
+}
+
+/// OpenAI-compatible embeddings endpoint (`<api_base>/embeddings`).
+#[derive(Debug, Clone)]
+pub struct ApiEmbedder {
+    pub api_base: String,
+    pub model: String,
+}
+
+#[async_trait]
+impl Embedder for ApiEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        #[cfg(feature = "full")]
+        {
+            let body = serde_json::json!({ "model": self.model, "input": texts });
+            let resp = crate::post_json(&format!("{}/embeddings", self.api_base), &body).await?;
+            let data = resp
+                .get("data")
+                .and_then(|d| d.as_array())
+                .filter(|d| d.len() == texts.len())
+                .ok_or_else(|| crate::Error::BadResponse("embeddings response has no data per input".into()))?;
+            data.iter()
+                .map(|item| {
+                    let values = item.get("embedding").and_then(|e| e.as_array()).ok_or_else(|| {
+                        crate::Error::BadResponse("embeddings response item has no vector".into())
+                    })?;
+                    let mut v: Vec<f32> = values.iter().filter_map(|x| x.as_f64()).map(|x| x as f32).collect();
+                    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+                    if norm > 0.0 {
+                        v.iter_mut().for_each(|x| *x /= norm);
+                    }
+                    Ok(v)
+                })
+                .collect()
+        }
+
+        #[cfg(not(feature = "full"))]
+        {
+            // Stub build: same vectors as the local embedder.
+            LocalEmbedder.embed(texts).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn similarity(a: &str, b: &str) -> f32 {
+        let (a, b) = (LocalEmbedder::vector(a), LocalEmbedder::vector(b));
+        a.iter().zip(&b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn rewordings_score_higher_than_other_cards() {
+        let card = "What is ownership in Rust?\nEach value has a single owner and is dropped when the owner goes out of scope.";
+        let reworded = "Explain Rust's ownership rule.\nEvery value has one owner; values are dropped once their owner leaves scope.";
+        let other = "What is the capital of France?\nParis";
+
+        assert!((similarity(card, card) - 1.0).abs() < 1e-5);
+        let close = similarity(card, reworded);
+        assert!(close >= 0.6, "reworded card scored {close}");
+        assert!(similarity(card, other) < 0.2, "unrelated card scored {}", similarity(card, other));
+    }
+}
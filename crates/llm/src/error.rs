@@ -0,0 +1,124 @@
+//! Errors returned by the `llm` crate.
+
+use std::time::Duration;
+use utils::error::Coded;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("environment variable OPENAI_API_KEY not set")]
+    MissingApiKey,
+    #[error("OpenAI rejected the API key (HTTP 401)")]
+    Unauthorized,
+    #[error("OpenAI rate limit reached")]
+    RateLimited { retry_after: Option<Duration> },
+    /// Could not reach the provider at all (DNS, connect).
+    #[error("cannot reach the model provider: {0}")]
+    Offline(String),
+    /// Reached the provider, but it did not answer in time.
+    #[error("the model provider timed out: {0}")]
+    Timeout(String),
+    /// The request could not be sent as built; retrying will not help.
+    #[error("request to the model provider failed: {0}")]
+    BadRequest(String),
+    #[error("OpenAI API error: Status={status}, Body={body}")]
+    Api { status: u16, body: String },
+    #[error("unusable model response: {0}")]
+    BadResponse(String),
+}
+
+impl Error {
+    /// Classify a non-success HTTP status.
+    pub fn from_status(status: u16, retry_after: Option<Duration>, body: String) -> Self {
+        match status {
+            401 | 403 => Error::Unauthorized,
+            429 => Error::RateLimited { retry_after },
+            _ => Error::Api { status, body },
+        }
+    }
+}
+
+#[cfg(feature = "full")]
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_connect() {
+            Error::Offline(e.to_string())
+        } else if e.is_timeout() {
+            Error::Timeout(e.to_string())
+        } else if e.is_request() || e.is_builder() || e.is_redirect() {
+            Error::BadRequest(e.to_string())
+        } else if e.is_decode() {
+            Error::BadResponse(e.to_string())
+        } else {
+            Error::Api { status: e.status().map_or(0, |s| s.as_u16()), body: e.to_string() }
+        }
+    }
+}
+
+/// `Retry-After` in seconds (HTTP-date form is not used by OpenAI).
+#[cfg(feature = "full")]
+pub(crate) fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|secs| *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::MissingApiKey => "llm.missing_api_key",
+            Error::Unauthorized => "llm.unauthorized",
+            Error::RateLimited { .. } => "llm.rate_limited",
+            Error::Offline(_) => "llm.offline",
+            Error::Timeout(_) => "llm.timeout",
+            Error::BadRequest(_) => "llm.bad_request",
+            Error::Api { .. } => "llm.api_error",
+            Error::BadResponse(_) => "llm.bad_response",
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        match self {
+            Error::RateLimited { .. } | Error::Offline(_) | Error::Timeout(_) | Error::BadResponse(_) => true,
+            Error::Api { status, .. } => *status >= 500 || *status == 0,
+            Error::MissingApiKey | Error::Unauthorized | Error::BadRequest(_) => false,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "full"))]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn only_connection_failures_count_as_offline() {
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = closed.local_addr().unwrap();
+        drop(closed);
+        let e: Error = reqwest::get(format!("http://{addr}")).await.unwrap_err().into();
+        assert_eq!(e.code(), "llm.offline");
+
+        // Accepts, then never answers.
+        let silent = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = silent.local_addr().unwrap();
+        let client = reqwest::Client::builder().timeout(Duration::from_millis(100)).build().unwrap();
+        let e: Error = client.get(format!("http://{addr}")).send().await.unwrap_err().into();
+        assert_eq!((e.code(), e.retryable()), ("llm.timeout", true));
+
+        let e: Error = reqwest::Client::new().get("not a url").send().await.unwrap_err().into();
+        assert_eq!((e.code(), e.retryable()), ("llm.bad_request", false));
+        drop(silent);
+    }
+}
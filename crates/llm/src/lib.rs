@@ -1,7 +1,14 @@
 //! Flash-card generation via LLM prompt - stub implementation.
 
-use anyhow::Result;
+pub mod embed;
+mod error;
+
+pub use embed::Embedder;
+pub use error::{Error, Result};
+
 use serde::Deserialize;
+use utils::config::LlmConfig;
+#[cfg(feature = "full")]
 use tracing::{info, error};
 
 /// Result fields coming back from the LLM
@@ -16,13 +23,13 @@ pub struct CardFields {
 #[cfg(feature = "full")]
 mod llama_cpp {
     pub struct Params;
-    
+
     impl Default for Params {
         fn default() -> Self {
             Self {}
         }
     }
-    
+
     pub fn generate(_prompt: &str, _params: Params) -> Result<String, Box<dyn std::error::Error>> {
         // Mock implementation returns a fixed response
         Ok(r#"{"front":"What is the capital of France?","back":"Paris","tags":["geography","europe"]}"#.into())
@@ -35,69 +42,32 @@ use reqwest::Client;
 use serde_json::json;
 
 /// Generate card JSON from plain text.
-pub async fn gen_card(text: &str) -> Result<CardFields> {
+pub async fn gen_card(text: &str, cfg: &LlmConfig) -> Result<CardFields> {
     #[cfg(feature = "full")]
     {
         // Include the word "json" in the input text to satisfy API requirements
         let enhanced_text = format!("Create JSON flashcard from this text: {}", text);
         let instructions = "You are an expert pedagogue. For the given text create a concise flashcard JSON with keys front, back, tags (array of strings). Only output valid, structured JSON without any additional text.";
-        
+
         info!("Enhanced input with JSON request: '{}'", enhanced_text);
-        
+
         let body = json!({
-            "model": "gpt-4.1", // cheaper default; change as desired
+            "model": cfg.text_model,
             "instructions": instructions,
             "input": enhanced_text,
-            "temperature": 0.4,
-            "max_output_tokens": 256,
+            "temperature": cfg.temperature,
+            "max_output_tokens": cfg.max_output_tokens,
             "text": { "format": { "type": "json_object" } }
         });
 
-        // Get API key
-        let api_key = match std::env::var("OPENAI_API_KEY") {
-            Ok(key) => {
-                info!("Found OpenAI API key (length: {})", key.len());
-                key
-            }
-            Err(_) => {
-                return Err(anyhow::anyhow!("Environment variable OPENAI_API_KEY not set"));
-            }
-        };
-        
         info!("Making OpenAI API request with model: {}", body["model"]);
-        let client = Client::new();
-        let response = client
-            .post("https://api.openai.com/v1/responses")
-            .bearer_auth(api_key)
-            .json(&body)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await?;
-            error!("OpenAI API error: Status={}, Body={}", status, error_body);
-            return Err(anyhow::anyhow!("OpenAI API error: Status={}, Body={}", status, error_body));
-        }
-
-        info!("OpenAI API request successful");
-        let resp: serde_json::Value = response.json().await?;
-
-        // Extract the assistant text content.
-        let content = resp
-            .get("output")
-            .and_then(|o| o.get(0))
-            .and_then(|m| m.get("content"))
-            .and_then(|c| c.get(0))
-            .and_then(|p| p.get("text"))
-            .and_then(|t| t.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Unexpected response structure from OpenAI"))?;
-
-        Ok(serde_json::from_str(content)?)
+        let content = post_responses(cfg, &body).await?;
+        parse_card(&content)
     }
 
     #[cfg(not(feature = "full"))]
     {
+        let _ = cfg;
         // Fallback stub.
         Ok(CardFields {
             front: format!("What is the gist of: {}?", text.lines().next().unwrap_or("text")),
@@ -108,12 +78,10 @@ pub async fn gen_card(text: &str) -> Result<CardFields> {
 }
 
 /// Generate card JSON from a screenshot PNG/JPEG image.
-pub async fn gen_card_from_image(image_bytes: &[u8]) -> Result<CardFields> {
+pub async fn gen_card_from_image(image_bytes: &[u8], cfg: &LlmConfig) -> Result<CardFields> {
     #[cfg(feature = "full")]
     {
         use base64::{engine::general_purpose, Engine as _};
-        use reqwest::Client;
-        use serde_json::json;
 
         // Encode bytes as data URL
         let b64 = general_purpose::STANDARD.encode(image_bytes);
@@ -122,7 +90,7 @@ pub async fn gen_card_from_image(image_bytes: &[u8]) -> Result<CardFields> {
         let instructions = "You are an expert pedagogue. For the given image create a concise flashcard JSON with keys front, back, tags (array of strings). Only output valid, structured JSON without any additional text.";
 
         let body = json!({
-            "model": "gpt-4o-mini",
+            "model": cfg.vision_model,
             "instructions": instructions,
             "input": [{
                 "role": "user",
@@ -131,52 +99,121 @@ pub async fn gen_card_from_image(image_bytes: &[u8]) -> Result<CardFields> {
                     {"type": "input_image", "image_url": data_url}
                 ]
             }],
-            "temperature": 0.4,
-            "max_output_tokens": 256,
+            "temperature": cfg.temperature,
+            "max_output_tokens": cfg.max_output_tokens,
             "text": { "format": { "type": "json_object" } }
         });
 
-        // Get API key
-        let api_key = std::env::var("OPENAI_API_KEY")
-            .map_err(|_| anyhow::anyhow!("Environment variable OPENAI_API_KEY not set"))?;
-
         info!("Sending vision request ({} bytes image)", image_bytes.len());
-
-        let client = Client::new();
-        let response = client
-            .post("https://api.openai.com/v1/responses")
-            .bearer_auth(api_key)
-            .json(&body)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let err = response.text().await?;
-            error!(?status, ?err, "OpenAI vision API error");
-            return Err(anyhow::anyhow!("OpenAI vision API error: {status} {err}"));
-        }
-
-        let resp: serde_json::Value = response.json().await?;
-
-        let content = resp
-            .get("output")
-            .and_then(|o| o.get(0))
-            .and_then(|m| m.get("content"))
-            .and_then(|c| c.get(0))
-            .and_then(|p| p.get("text"))
-            .and_then(|t| t.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Unexpected response structure from OpenAI vision"))?;
-
-        Ok(serde_json::from_str(content)?)
+        let content = post_responses(cfg, &body).await?;
+        parse_card(&content)
     }
 
     #[cfg(not(feature = "full"))]
     {
+        let _ = (image_bytes, cfg);
         Ok(CardFields {
             front: "stub front from image".into(),
             back: "stub back".into(),
             tags: vec!["stub".into()],
         })
     }
-} 
\ No newline at end of file
+}
+
+/// The model's ruling on a typed answer.
+#[derive(Debug, Deserialize)]
+pub struct Judgement {
+    pub verdict: JudgeVerdict,
+    /// One sentence on what was wrong or missing, empty when correct.
+    #[serde(default)]
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JudgeVerdict {
+    Correct,
+    Partial,
+    Wrong,
+}
+
+/// Ask the text model whether `answer` means the same as `expected`.
+pub async fn judge_answer(question: &str, expected: &str, answer: &str, cfg: &LlmConfig) -> Result<Judgement> {
+    #[cfg(feature = "full")]
+    {
+        let instructions = "You grade flashcard answers. Compare the student's answer with the expected answer \
+            on meaning, not wording. Reply with JSON: {\"verdict\": \"correct\" | \"partial\" | \"wrong\", \
+            \"explanation\": one short sentence telling the student what was wrong or missing (empty if correct)}.";
+        let body = json!({
+            "model": cfg.text_model,
+            "instructions": instructions,
+            "input": format!("Question: {question}\nExpected answer: {expected}\nStudent answer: {answer}\nReply in JSON."),
+            "temperature": 0.0,
+            "max_output_tokens": cfg.max_output_tokens,
+            "text": { "format": { "type": "json_object" } }
+        });
+        let content = post_responses(cfg, &body).await?;
+        serde_json::from_str(&content).map_err(|e| Error::BadResponse(format!("judgement JSON did not parse: {e}")))
+    }
+
+    #[cfg(not(feature = "full"))]
+    {
+        let _ = (question, cfg);
+        // Fallback stub: correct when the answer appears in the expected text.
+        let answer = answer.trim().to_lowercase();
+        let correct = !answer.is_empty() && expected.to_lowercase().contains(&answer);
+        Ok(Judgement {
+            verdict: if correct { JudgeVerdict::Correct } else { JudgeVerdict::Wrong },
+            explanation: if correct { String::new() } else { "stub judge".into() },
+        })
+    }
+}
+
+/// POST to the OpenAI responses endpoint and return the assistant text content.
+#[cfg(feature = "full")]
+async fn post_responses(cfg: &LlmConfig, body: &serde_json::Value) -> Result<String> {
+    let resp = post_json(&format!("{}/responses", cfg.api_base), body).await?;
+
+    // Extract the assistant text content.
+    resp.get("output")
+        .and_then(|o| o.get(0))
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.get(0))
+        .and_then(|p| p.get("text"))
+        .and_then(|t| t.as_str())
+        .map(str::to_owned)
+        .ok_or_else(|| Error::BadResponse("unexpected response structure from OpenAI".into()))
+}
+
+/// POST JSON to an OpenAI-compatible endpoint with the API key and decode the reply.
+#[cfg(feature = "full")]
+pub(crate) async fn post_json(url: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+    let api_key = std::env::var("OPENAI_API_KEY")
+        .ok()
+        .filter(|k| !k.trim().is_empty())
+        .ok_or(Error::MissingApiKey)?;
+
+    let client = Client::new();
+    let response = client
+        .post(url)
+        .bearer_auth(api_key)
+        .json(body)
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let retry_after = error::parse_retry_after(response.headers());
+        let error_body = response.text().await.unwrap_or_default();
+        error!("OpenAI API error: Status={}, Body={}", status, error_body);
+        return Err(Error::from_status(status.as_u16(), retry_after, error_body));
+    }
+
+    info!("OpenAI API request successful");
+    Ok(response.json().await?)
+}
+
+#[cfg(feature = "full")]
+fn parse_card(content: &str) -> Result<CardFields> {
+    serde_json::from_str(content).map_err(|e| Error::BadResponse(format!("card JSON did not parse: {e}")))
+}
@@ -1,61 +1,390 @@
-//! Binary entry-point that wires the pipeline together.
+//! `oakley` command-line entry-point. `oakley daemon` runs the pipeline; every
+//! other subcommand is a thin client talking to it over the IPC socket.
 
-use anyhow::Result;
-use capture::CaptureEvent;
-use llm::{gen_card_from_image};
-use scheduler::{Scheduler, ReviewOutcome};
-use tokio::{select, sync::mpsc};
-use tracing::{info, warn};
-use notify_rust::Notification;
+mod review;
+mod stats;
+
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use ipc::{Request, Response};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use utils::config::ConfigLoader;
+
+#[derive(Parser)]
+#[command(name = "oakley", version, about = "Oakley SRS – offline, AI-powered spaced repetition")]
+struct Cli {
+    /// Config file (default: ~/.oakley/config.toml or $OAKLEY_CONFIG).
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Override a config key, e.g. `--set llm.temperature=0.2`. Repeatable.
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    overrides: Vec<String>,
+
+    /// IPC socket of the daemon (config key `socket_path`).
+    #[arg(long, global = true)]
+    socket: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run capture, generation, scheduling and the APIs in the foreground.
+    Daemon {
+        /// SQLite database file (config key `db_path`).
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Port for the HTTP JSON API (config key `http.port`).
+        #[arg(long)]
+        http_port: Option<u16>,
+        /// Disable the HTTP JSON API.
+        #[arg(long)]
+        no_http: bool,
+        /// Do not install the global capture hot-key.
+        #[arg(long)]
+        no_hotkeys: bool,
+    },
+    /// Inspect cards.
+    Cards {
+        #[command(subcommand)]
+        command: CardsCommand,
+    },
+    /// Review due cards in the terminal.
+    Review {
+        /// Type each answer and have it checked before grading.
+        #[arg(long = "type", conflicts_with_all = ["voice", "audio"])]
+        typed: bool,
+        /// Answer aloud; needs `speech.microphone = true` and a whisper model.
+        #[arg(long, conflicts_with = "audio")]
+        voice: bool,
+        /// Hands-free: read cards aloud, answer aloud, grade as suggested.
+        /// Say "stop" to end.
+        #[arg(long)]
+        audio: bool,
+    },
+    /// Review statistics: activity, retention, streak, upcoming load.
+    Stats {
+        /// Days of history to summarise.
+        #[arg(long, default_value_t = data::stats::DEFAULT_DAYS)]
+        days: u32,
+        /// Print the statistics as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Transcribe a WAV file with the configured speech model.
+    Transcribe { file: PathBuf },
+    /// Read text aloud with the configured `[tts]` engine.
+    Speak { text: String },
+    /// Generate a card from text.
+    Generate { text: String },
+    /// Take a screenshot and generate a card from it.
+    Capture {
+        /// Capture the focused window instead of a selected region.
+        #[arg(long, conflicts_with = "clipboard")]
+        window: bool,
+        /// Use the clipboard (text, HTML or image) instead of the screen.
+        #[arg(long)]
+        clipboard: bool,
+    },
+    /// Turn a document into cards, one per section.
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+    /// Silence due-card reminders.
+    Snooze {
+        #[arg(default_value_t = 60)]
+        minutes: i64,
+    },
+    /// Print daemon events as JSON lines until interrupted.
+    Events,
+    /// Inspect and retry the card-generation queue.
+    Jobs {
+        #[command(subcommand)]
+        command: Option<JobsCommand>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportCommand {
+    /// Import a PDF; scanned pages are OCR'd. Run again to resume.
+    Pdf { file: PathBuf },
+    /// Import the article of a web page, by URL or saved HTML file, one card
+    /// per section.
+    Web { page: String },
+}
+
+#[derive(Subcommand)]
+enum JobsCommand {
+    /// Dump JSON of queued jobs (default: dead-lettered only).
+    List {
+        /// pending, running, done, dead or held; `all` for every job.
+        #[arg(long, default_value = "dead")]
+        status: String,
+    },
+    /// Re-queue a dead job, or every dead job when no id is given.
+    Retry { id: Option<i64> },
+    /// Settle a screenshot held as a duplicate by keeping the earlier card.
+    Reuse { id: i64 },
+    /// Generate a card for a screenshot held as a duplicate after all.
+    Generate { id: i64 },
+}
+
+#[derive(Subcommand)]
+enum CardsCommand {
+    /// Dump JSON of all cards.
+    List,
+    /// Dump JSON of today's review queue.
+    Due,
+    /// List pairs of cards that ask the same thing in different words.
+    Dedupe {
+        /// Cosine similarity (0–1) to report from; default `dedupe.threshold`.
+        #[arg(long)]
+        threshold: Option<f32>,
+        /// Print the pairs as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Fold a duplicate card into another, keeping the first card's text,
+    /// schedule and review history plus the duplicate's reviews and tags.
+    Merge { keep: i64, duplicate: i64 },
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
     utils::log::init();
+    let cli = Cli::parse();
+
+    let mut loader = ConfigLoader::new(cli.config);
+    for arg in &cli.overrides {
+        loader = loader.with_override_arg(arg)?;
+    }
+    if let Some(socket) = &cli.socket {
+        loader = loader.with_override("socket_path", socket.to_string_lossy());
+    }
 
-    // ── bootstrap shared state ──
-    let db = data::new_pool("oakley.db")?;
-    let (cap_tx, mut cap_rx) = mpsc::channel::<CaptureEvent>(16);
-    let (rev_tx, mut rev_rx) = mpsc::channel::<ReviewOutcome>(32);
-    let scheduler = Scheduler::new(db.clone(), rev_tx.clone());
-
-    // ── task: global hot-key + screenshot capture ──
-    tokio::spawn(capture::listen_and_capture(cap_tx.clone()));
-
-    // ── task: scheduler tick every minute ──
-    tokio::spawn(scheduler.run());
-
-    // ── main bus ──
-    loop {
-        select! {
-            Some(evt) = cap_rx.recv() => {
-                info!("📸 Capture event received: region={:?}", evt.region);
-                // Encode image to PNG bytes for OpenAI
-                let mut png_bytes = Vec::new();
-                image::codecs::png::PngEncoder::new(&mut png_bytes)
-                    .encode(
-                        &evt.image,
-                        evt.image.width(),
-                        evt.image.height(),
-                        image::ColorType::Rgba8,
-                    )?;
-
-                let card = gen_card_from_image(&png_bytes).await?;
-                info!("🧠 Generated card from image");
-                let card_json = data::CardJson { id: 0, front: card.front, back: card.back, tags: card.tags };
-                let new_id = data::insert_card(&db, &card_json, evt.path.as_deref())?;
-                info!("inserted card id={new_id}");
-
-                // Fire a system notification so the user knows card was created
-                let _ = Notification::new()
-                    .summary("Oakley – Card Saved")
-                    .body(&format!("Card #{new_id} created from screenshot."))
-                    .icon("dialog-information")
-                    .show();
-            }
-            Some(outcome) = rev_rx.recv() => {
-                // TODO: persist outcome
-                warn!(?outcome, "received review outcome – persistence TBD");
+    let command = cli.command.unwrap_or(Command::Daemon {
+        db: None,
+        http_port: None,
+        no_http: false,
+        no_hotkeys: false,
+    });
+
+    if let Command::Daemon { db, http_port, no_http, no_hotkeys } = command {
+        if let Some(db) = db {
+            loader = loader.with_override("db_path", db.to_string_lossy());
+        }
+        if let Some(port) = http_port {
+            loader = loader.with_override("http.port", port.to_string());
+        }
+        if no_http {
+            loader = loader.with_override("http.enabled", "false");
+        }
+        if no_hotkeys {
+            loader = loader.with_override("hotkeys.enabled", "false");
+        }
+        return daemon::run(loader).await;
+    }
+
+    let socket = loader.load()?.socket_path;
+    match command {
+        Command::Daemon { .. } => unreachable!("handled above"),
+        Command::Cards { command } => {
+            let req = match command {
+                CardsCommand::List => Request::ListCards,
+                CardsCommand::Due => Request::DueCards,
+                CardsCommand::Dedupe { threshold, json } => {
+                    return match ipc::request(&socket, &Request::DedupeCards { threshold }).await? {
+                        Response::Duplicates { pairs } if json => print_json(&pairs),
+                        Response::Duplicates { pairs } => {
+                            print_duplicates(&pairs);
+                            Ok(())
+                        }
+                        other => unexpected(other),
+                    };
+                }
+                CardsCommand::Merge { keep, duplicate } => Request::MergeCards { keep, duplicate },
+            };
+            match ipc::request(&socket, &req).await? {
+                Response::Cards { cards } => print_json(&cards),
+                Response::Card { card, .. } => print_json(&card),
+                other => unexpected(other),
+            }
+        }
+        Command::Review { typed, voice, audio } => {
+            let mode = match (typed, voice, audio) {
+                (_, _, true) => review::Answer::Audio,
+                (_, true, _) => review::Answer::Spoken,
+                (true, _, _) => review::Answer::Typed,
+                _ => review::Answer::Recall,
+            };
+            review::run_session(&socket, mode).await
+        }
+        Command::Stats { days, json } => match ipc::request(&socket, &Request::Stats { days: Some(days) }).await? {
+            Response::Stats { stats } if json => print_json(&stats),
+            Response::Stats { stats } => {
+                stats::print(&stats);
+                Ok(())
+            }
+            other => unexpected(other),
+        },
+        Command::Transcribe { file } => {
+            let wav = Some(std::fs::canonicalize(file)?);
+            match ipc::request(&socket, &Request::Transcribe { wav }).await? {
+                Response::Transcript { text } => {
+                    println!("{text}");
+                    Ok(())
+                }
+                other => bail!("unexpected daemon response: {other:?}"),
             }
         }
+        Command::Speak { text } => match ipc::request(&socket, &Request::Speak { text }).await? {
+            Response::Spoken { path } => {
+                if let Some(path) = path {
+                    println!("{}", path.display());
+                }
+                Ok(())
+            }
+            other => bail!("unexpected daemon response: {other:?}"),
+        },
+        Command::Generate { text } => {
+            print_generated(&socket, ipc::request(&socket, &Request::GenerateFromText { text, source: None }).await?).await
+        }
+        Command::Capture { window, clipboard } => {
+            let req = match (window, clipboard) {
+                (_, true) => Request::CaptureClipboard,
+                (true, _) => Request::CaptureWindow,
+                _ => Request::CaptureScreen,
+            };
+            print_generated(&socket, ipc::request(&socket, &req).await?).await
+        }
+        Command::Import { command } => {
+            let req = match command {
+                ImportCommand::Web { page } if page.starts_with("https://") || page.starts_with("http://") => {
+                    Request::ImportUrl { url: page }
+                }
+                // The daemon resolves paths from its own working directory.
+                ImportCommand::Web { page: file } => Request::ImportFile { path: std::fs::canonicalize(file)? },
+                ImportCommand::Pdf { file } => Request::ImportFile { path: std::fs::canonicalize(file)? },
+            };
+            match ipc::request(&socket, &req).await? {
+                Response::Imported { import, added } => {
+                    let skipped = import.queued - added;
+                    println!("Queued {added} of {} section(s) from {}.", import.chunks, import.path);
+                    if skipped > 0 {
+                        println!("{skipped} section(s) were queued by an earlier run.");
+                    }
+                    Ok(())
+                }
+                other => unexpected(other),
+            }
+        }
+        Command::Snooze { minutes } => {
+            ipc::request(&socket, &Request::SnoozeReminders { minutes }).await?;
+            println!("Reminders snoozed for {minutes} minutes.");
+            Ok(())
+        }
+        Command::Events => {
+            let mut sub = ipc::Client::connect(&socket).await?.subscribe().await?;
+            while let Some(evt) = sub.next().await? {
+                println!("{}", serde_json::to_string(&evt)?);
+            }
+            Ok(())
+        }
+        Command::Jobs { command } => match command.unwrap_or(JobsCommand::List { status: "dead".into() }) {
+            JobsCommand::List { status } => {
+                let status = match status.as_str() {
+                    "all" => None,
+                    s => Some(serde_json::from_value(serde_json::Value::String(s.into()))?),
+                };
+                match ipc::request(&socket, &Request::ListJobs { status }).await? {
+                    Response::Jobs { jobs } => print_json(&jobs),
+                    other => unexpected(other),
+                }
+            }
+            JobsCommand::Retry { id } => match ipc::request(&socket, &Request::RetryJobs { id }).await? {
+                Response::Retried { count } => {
+                    println!("Re-queued {count} job(s).");
+                    Ok(())
+                }
+                other => unexpected(other),
+            },
+            JobsCommand::Reuse { id } => {
+                let req = Request::ResolveDuplicate { job_id: id, reuse: true };
+                print_generated(&socket, ipc::request(&socket, &req).await?).await
+            }
+            JobsCommand::Generate { id } => {
+                let req = Request::ResolveDuplicate { job_id: id, reuse: false };
+                print_generated(&socket, ipc::request(&socket, &req).await?).await
+            }
+        },
     }
-} 
\ No newline at end of file
+}
+
+/// Print a generated card, or explain why it is still queued. A screenshot
+/// held as a duplicate is settled by asking when stdin is a terminal.
+async fn print_generated(socket: &Path, resp: Response) -> Result<()> {
+    let resp = match resp {
+        Response::Duplicate { job_id, duplicate_of, card } => {
+            let Some(card) = card else {
+                eprintln!("Held as job #{job_id}; it looks like job #{duplicate_of}, which is still generating.");
+                eprintln!("Run `oakley jobs reuse {job_id}` once it has a card or `oakley jobs generate {job_id}` for a new one.");
+                return Ok(());
+            };
+            let like = format!("card #{}: {}", card.id, card.front);
+            if !std::io::stdin().is_terminal() {
+                eprintln!("Held as job #{job_id}; it looks like {like}.");
+                eprintln!("Run `oakley jobs reuse {job_id}` to keep that card or `oakley jobs generate {job_id}` for a new one.");
+                return Ok(());
+            }
+            let answer = review::read_line(format!("This looks like {like}\nReuse that card? [Y/n] ")).await?;
+            let reuse = !matches!(answer.as_deref().map(str::trim), Some("n") | Some("N"));
+            ipc::request(socket, &Request::ResolveDuplicate { job_id, reuse }).await?
+        }
+        other => other,
+    };
+    match resp {
+        Response::Card { card, similar } => {
+            for s in &similar {
+                eprintln!("Looks like card #{} ({:.0}% similar): {}", s.card.id, s.similarity * 100.0, s.card.front);
+                eprintln!("  merge with `oakley cards merge {} {}`", s.card.id, card.id);
+            }
+            print_json(&card)
+        }
+        Response::Queued { job_id, retry_at, error } => {
+            let reason = error.map(|e| e.to_string()).unwrap_or_else(|| "busy".into());
+            match retry_at {
+                Some(at) => eprintln!("Queued as job #{job_id} ({reason}); retrying at {}.", at.to_rfc3339()),
+                None => eprintln!("Queued as job #{job_id} ({reason})."),
+            }
+            Ok(())
+        }
+        other => unexpected(other),
+    }
+}
+
+/// Human-readable `cards dedupe` report.
+fn print_duplicates(pairs: &[data::DuplicatePair]) {
+    if pairs.is_empty() {
+        println!("No duplicate cards found.");
+        return;
+    }
+    for p in pairs {
+        println!("{:.0}%  #{} {}", p.similarity * 100.0, p.a.id, p.a.front);
+        println!("      #{} {}", p.b.id, p.b.front);
+        println!("      merge with `oakley cards merge {} {}`\n", p.a.id, p.b.id);
+    }
+    println!("{} likely duplicate pair(s).", pairs.len());
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn unexpected(resp: Response) -> Result<()> {
+    bail!("unexpected daemon response: {resp:?}")
+}
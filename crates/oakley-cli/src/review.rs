@@ -0,0 +1,230 @@
+//! Minimal terminal review session: show front, reveal back, grade pass/fail.
+//! With `--type` (or `--voice`) the answer is typed (or spoken) first, checked
+//! by the daemon, and the suggested grade is the default. `--audio` is
+//! hands-free: cards are read aloud, answered aloud and graded as suggested.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, Utc};
+use data::CardJson;
+use ipc::{Client, Request, Response};
+use scheduler::{Grade, Verdict};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::Instant;
+use utils::error::ErrorPayload;
+use utils::source::Source;
+
+/// How answers are given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    /// Recall silently, reveal, say whether it was remembered.
+    Recall,
+    Typed,
+    /// Spoken into the microphone and transcribed by the daemon.
+    Spoken,
+    /// Front and back read aloud, answers spoken, no keyboard.
+    Audio,
+}
+
+/// Saying one of these instead of an answer ends a hands-free session.
+const STOP_WORDS: &[&str] = &["stop", "quit", "exit", "end session"];
+
+/// Cards in learning coming back within this long are waited for.
+const LEARN_AHEAD: std::time::Duration = std::time::Duration::from_secs(20 * 60);
+
+/// Walk through every due card on stdin/stdout, grading through the daemon.
+/// Cards answered wrong or still in learning come back once their step runs out.
+pub async fn run_session(socket: &Path, mode: Answer) -> Result<()> {
+    let mut client = Client::connect(socket).await?;
+    let mut cards = due_cards(&mut client).await?;
+    if cards.is_empty() {
+        println!("Nothing due – nice work.");
+        return Ok(());
+    }
+
+    'session: loop {
+        // Soonest any card graded this round is due again.
+        let mut back: Option<DateTime<Utc>> = None;
+        let total = cards.len();
+        for (i, card) in cards.into_iter().enumerate() {
+            let shown = Instant::now();
+            if mode != Answer::Recall {
+                let answer = match mode {
+                    Answer::Spoken | Answer::Audio => {
+                        println!("\n[{}/{}] {}", i + 1, total, card.front);
+                        if mode == Answer::Audio {
+                            speak(&mut client, &card.front).await?;
+                        }
+                        println!("🎙️ Answer aloud…");
+                        let text = listen(&mut client).await?;
+                        println!("You said: {}", if text.is_empty() { "(nothing)" } else { &text });
+                        if mode == Answer::Audio && STOP_WORDS.contains(&scheduler::grading::normalize(&text).as_str())
+                        {
+                            break 'session;
+                        }
+                        text
+                    }
+                    _ => {
+                        let prompt = format!("\n[{}/{}] {}\nYour answer: ", i + 1, total, card.front);
+                        let Some(answer) = read_line(prompt).await? else {
+                            break 'session;
+                        };
+                        answer
+                    }
+                };
+                let verdict = match client.call(&Request::CheckAnswer { card_id: card.id, answer }).await? {
+                    Response::Checked { verdict } => verdict,
+                    other => bail!("unexpected daemon response: {other:?}"),
+                };
+                if mode == Answer::Audio {
+                    println!("{}→ {}", verdict_lines(&verdict), card.back);
+                    let said = match verdict.grade {
+                        Grade::Again => "Not quite.",
+                        Grade::Hard => "Partly right.",
+                        Grade::Good | Grade::Easy => "Correct.",
+                    };
+                    speak(&mut client, &format!("{said} The answer is: {}", card.back)).await?;
+                    let (grade, duration_ms) = (Some(verdict.grade), elapsed_ms(shown));
+                    let next_due = grade_card(&mut client, card.id, verdict.grade.passed(), grade, duration_ms).await?;
+                    back = Some(back.map_or(next_due, |b| b.min(next_due)));
+                    println!("Graded {}.", verdict.grade.as_str());
+                    continue;
+                }
+                let prompt = format!(
+                    "{}→ {}\n{}Grade? [a]gain [h]ard [g]ood [e]asy, q to stop (Enter = {}) ",
+                    verdict_lines(&verdict),
+                    card.back,
+                    source_lines(card.source.as_ref()),
+                    verdict.grade.as_str(),
+                );
+                let grade = match read_line(prompt).await?.as_deref().map(str::trim) {
+                    Some("") => verdict.grade,
+                    Some("a") => Grade::Again,
+                    Some("h") => Grade::Hard,
+                    Some("g") => Grade::Good,
+                    Some("e") => Grade::Easy,
+                    _ => break 'session,
+                };
+                let duration_ms = elapsed_ms(shown);
+                let next_due = grade_card(&mut client, card.id, grade.passed(), Some(grade), duration_ms).await?;
+                back = Some(back.map_or(next_due, |b| b.min(next_due)));
+                continue;
+            }
+
+            let prompt = format!("\n[{}/{}] {}\n(press Enter to reveal) ", i + 1, total, card.front);
+            if read_line(prompt).await?.is_none() {
+                break 'session;
+            }
+            let answer = format!("→ {}\n{}Remembered? [y/n/q] ", card.back, source_lines(card.source.as_ref()));
+            let passed = match read_line(answer).await?.as_deref().map(str::trim) {
+                Some("y") | Some("Y") | Some("") => true,
+                Some("n") | Some("N") => false,
+                _ => break 'session,
+            };
+            let next_due = grade_card(&mut client, card.id, passed, None, elapsed_ms(shown)).await?;
+            back = Some(back.map_or(next_due, |b| b.min(next_due)));
+        }
+
+        cards = due_cards(&mut client).await?;
+        if cards.is_empty() {
+            // Wait for cards a few minutes from the end of their step rather than stop short.
+            let Some(wait) = back.and_then(|at| (at - Utc::now()).to_std().ok()).filter(|w| *w <= LEARN_AHEAD) else {
+                break;
+            };
+            let secs = wait.as_secs() + 1;
+            let when = if secs < 60 { format!("{secs}s") } else { format!("{}m", secs.div_ceil(60)) };
+            println!("\nNext card back in {when} – waiting (Ctrl-C to stop)…");
+            tokio::time::sleep(wait + std::time::Duration::from_secs(1)).await;
+            cards = due_cards(&mut client).await?;
+            if cards.is_empty() {
+                break;
+            }
+        }
+    }
+    println!("Session finished.");
+    Ok(())
+}
+
+async fn due_cards(client: &mut Client) -> Result<Vec<CardJson>> {
+    match client.call(&Request::DueCards).await? {
+        Response::Cards { cards } => Ok(cards),
+        other => bail!("unexpected daemon response: {other:?}"),
+    }
+}
+
+/// Grade a card; returns when it is due again.
+async fn grade_card(
+    client: &mut Client,
+    card_id: i64,
+    passed: bool,
+    grade: Option<Grade>,
+    duration_ms: Option<u32>,
+) -> Result<DateTime<Utc>> {
+    match client.call(&Request::Grade { card_id, passed, grade, duration_ms }).await? {
+        Response::Graded { next_due } => Ok(next_due),
+        other => bail!("unexpected daemon response: {other:?}"),
+    }
+}
+
+async fn speak(client: &mut Client, text: &str) -> Result<()> {
+    match client.call(&Request::Speak { text: text.to_string() }).await? {
+        Response::Spoken { .. } => Ok(()),
+        other => bail!("unexpected daemon response: {other:?}"),
+    }
+}
+
+/// Record and transcribe one answer; silence counts as no answer.
+async fn listen(client: &mut Client) -> Result<String> {
+    match client.call(&Request::Transcribe { wav: None }).await {
+        Ok(Response::Transcript { text }) => Ok(text),
+        Ok(other) => bail!("unexpected daemon response: {other:?}"),
+        Err(e) if e.downcast_ref::<ErrorPayload>().is_some_and(|p| p.code == "speech.no_speech") => Ok(String::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Time spent on a card, for the statistics.
+fn elapsed_ms(shown: Instant) -> Option<u32> {
+    u32::try_from(shown.elapsed().as_millis()).ok()
+}
+
+/// The daemon's take on a typed answer.
+fn verdict_lines(verdict: &Verdict) -> String {
+    let mark = match verdict.grade {
+        Grade::Again => "✗ Not quite",
+        Grade::Hard => "~ Partly right",
+        Grade::Good | Grade::Easy => "✓ Correct",
+    };
+    match &verdict.explanation {
+        Some(why) => format!("{mark}: {why}\n"),
+        None => format!("{mark}\n"),
+    }
+}
+
+/// Where the card was captured, so the original can be reopened.
+fn source_lines(source: Option<&Source>) -> String {
+    let Some(src) = source else {
+        return String::new();
+    };
+    let mut out = String::new();
+    if let Some(label) = src.label() {
+        out.push_str(&format!("  from {label}, {}\n", src.captured_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")));
+    }
+    if let Some(url) = &src.url {
+        out.push_str(&format!("  {url}\n"));
+    }
+    out
+}
+
+/// Print `prompt` and read one line without blocking the runtime. `None` on EOF.
+pub(crate) async fn read_line(prompt: String) -> Result<Option<String>> {
+    let line = tokio::task::spawn_blocking(move || -> io::Result<Option<String>> {
+        print!("{prompt}");
+        io::stdout().flush()?;
+        let mut buf = String::new();
+        let n = io::stdin().lock().read_line(&mut buf)?;
+        Ok((n > 0).then_some(buf))
+    })
+    .await??;
+    Ok(line)
+}
@@ -0,0 +1,82 @@
+//! Plain-text rendering of `oakley stats`.
+
+use data::stats::{Accuracy, Stats};
+
+/// Decks and tags listed before the rest are summarised.
+const TOP: usize = 10;
+
+pub fn print(stats: &Stats) {
+    let reviews: u32 = stats.reviews.iter().map(|d| d.reviews).sum();
+    let passed: u32 = stats.reviews.iter().map(|d| d.passed).sum();
+    let today = stats.reviews.last().map_or(0, |d| d.reviews);
+
+    println!("Last {} day(s)", stats.days);
+    let mut line = format!("  Reviews   {reviews} ({}), {}", percent(passed, reviews), duration(stats.time.total_secs));
+    if let Some(secs) = stats.time.secs_per_review {
+        line.push_str(&format!(", {secs:.0}s per card"));
+    }
+    println!("{line}");
+    println!("  Today     {today} review(s), {}", duration(stats.time.today_secs));
+    let streak = &stats.streak;
+    let pending = if streak.current > 0 && !streak.reviewed_today { " – review today to keep it" } else { "" };
+    println!("  Streak    {} day(s), longest {}{pending}", streak.current, streak.longest);
+    println!("  Per day   {}", spark(stats.reviews.iter().map(|d| d.reviews)));
+
+    println!("\nRetention by time since last review");
+    for b in &stats.retention {
+        println!("  {:<18} {:>5}  ({} review(s))", b.label, percent(b.passed, b.reviews), b.reviews);
+    }
+
+    let m = &stats.maturity;
+    println!("\nCards     {} new · {} young · {} mature", m.new, m.young, m.mature);
+    let due = |days: usize| stats.forecast.iter().take(days).map(|d| d.due).sum::<u32>();
+    println!(
+        "Due       {} today · {} tomorrow · {} this week · {} in {} days",
+        due(1),
+        due(2) - due(1),
+        due(7),
+        due(stats.forecast.len()),
+        stats.forecast.len()
+    );
+    println!("          {}", spark(stats.forecast.iter().map(|d| d.due)));
+
+    accuracy("Decks", &stats.decks);
+    accuracy("Tags", &stats.tags);
+}
+
+fn accuracy(title: &str, list: &[Accuracy]) {
+    if list.is_empty() {
+        return;
+    }
+    println!("\n{title}");
+    for a in list.iter().take(TOP) {
+        println!("  {:<24} {:>5}  ({} review(s))", a.name, percent(a.passed, a.reviews), a.reviews);
+    }
+    if list.len() > TOP {
+        println!("  … and {} more", list.len() - TOP);
+    }
+}
+
+fn percent(part: u32, whole: u32) -> String {
+    if whole == 0 {
+        return "–".into();
+    }
+    format!("{:.0}%", part as f32 * 100.0 / whole as f32)
+}
+
+fn duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// One bar per value, scaled to the largest.
+fn spark(values: impl Iterator<Item = u32> + Clone) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.clone().max().unwrap_or(0).max(1);
+    values
+        .map(|v| if v == 0 { '·' } else { BARS[(v * 7 / max) as usize] })
+        .collect()
+}
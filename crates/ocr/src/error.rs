@@ -0,0 +1,32 @@
+//! Errors returned by the `ocr` crate.
+
+use utils::error::Coded;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("OCR support not compiled in (enable the `tesseract` feature)")]
+    Unavailable,
+    /// Usually missing `tessdata` for the configured language.
+    #[error("OCR engine failed to start: {0}")]
+    Init(String),
+    #[error("OCR failed: {0}")]
+    Engine(String),
+    #[error("no legible text found in image")]
+    NoText,
+    #[error("image could not be prepared for OCR: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Unavailable => "ocr.unavailable",
+            Error::Init(_) => "ocr.init_failed",
+            Error::Engine(_) => "ocr.failed",
+            Error::NoText => "ocr.no_text",
+            Error::Image(_) => "ocr.bad_image",
+        }
+    }
+}
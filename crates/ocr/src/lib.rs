@@ -0,0 +1,187 @@
+//! Local OCR for screenshots. Engines implement [`OcrEngine`]; Tesseract is
+//! available behind the `tesseract` feature, and [`StubEngine`] returns fixed
+//! text for tests and builds without native libraries.
+
+mod error;
+pub mod preprocess;
+#[cfg(feature = "tesseract")]
+mod tesseract;
+
+pub use error::{Error, Result};
+
+use image::{GrayImage, RgbaImage};
+
+/// One recognised word with its confidence (0–100) and bounding box.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub text: String,
+    pub confidence: f32,
+    pub bbox: (u32, u32, u32, u32), // x,y,w,h
+    /// Line identifier within the page; words sharing it are on one line.
+    pub line: (u32, u32, u32),
+}
+
+/// Recognition result for one image.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OcrOutput {
+    pub words: Vec<Word>,
+}
+
+impl OcrOutput {
+    /// Text of words at or above `min_confidence`, one line per OCR line.
+    pub fn text(&self, min_confidence: f32) -> String {
+        let mut out = String::new();
+        let mut line = None;
+        for w in self.words.iter().filter(|w| w.confidence >= min_confidence) {
+            match line {
+                Some(l) if l == w.line => out.push(' '),
+                Some(_) => out.push('\n'),
+                None => {}
+            }
+            out.push_str(&w.text);
+            line = Some(w.line);
+        }
+        out
+    }
+
+    /// Mean word confidence, 0 when nothing was recognised.
+    pub fn mean_confidence(&self) -> f32 {
+        if self.words.is_empty() {
+            return 0.0;
+        }
+        self.words.iter().map(|w| w.confidence).sum::<f32>() / self.words.len() as f32
+    }
+}
+
+/// A text recogniser operating on preprocessed grayscale images.
+pub trait OcrEngine: Send + Sync {
+    fn recognize(&self, img: &GrayImage) -> Result<OcrOutput>;
+}
+
+/// Engine returning the same text for every image.
+#[derive(Debug, Clone)]
+pub struct StubEngine {
+    text: String,
+}
+
+impl StubEngine {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into() }
+    }
+}
+
+impl OcrEngine for StubEngine {
+    fn recognize(&self, _img: &GrayImage) -> Result<OcrOutput> {
+        let words = self
+            .text
+            .lines()
+            .enumerate()
+            .flat_map(|(l, line)| {
+                line.split_whitespace().map(move |w| Word {
+                    text: w.to_string(),
+                    confidence: 95.0,
+                    bbox: (0, 0, 0, 0),
+                    line: (1, 1, l as u32 + 1),
+                })
+            })
+            .collect();
+        Ok(OcrOutput { words })
+    }
+}
+
+/// The best engine compiled into this build for `language`.
+pub fn engine(language: &str) -> Result<Box<dyn OcrEngine>> {
+    #[cfg(feature = "tesseract")]
+    {
+        Ok(Box::new(tesseract::Tesseract::new(language)?))
+    }
+
+    #[cfg(not(feature = "tesseract"))]
+    {
+        let _ = language;
+        Err(Error::Unavailable)
+    }
+}
+
+/// Refuse `[ocr] enabled = true` in a build without an engine, so the daemon
+/// fails at startup instead of on every screenshot.
+pub fn check(cfg: &utils::config::OcrConfig) -> Result<()> {
+    if cfg.enabled && !cfg!(feature = "tesseract") {
+        return Err(Error::Unavailable);
+    }
+    Ok(())
+}
+
+/// Preprocess a capture and run it through `engine`.
+pub fn recognize(engine: &dyn OcrEngine, img: &RgbaImage) -> Result<OcrOutput> {
+    let prepared = preprocess::prepare(img);
+    let out = engine.recognize(&prepared)?;
+    tracing::info!(words = out.words.len(), confidence = out.mean_confidence(), "🔎 OCR finished");
+    Ok(out)
+}
+
+/// Parse Tesseract's TSV output, keeping word-level rows (level 5).
+pub fn parse_tsv(tsv: &str) -> OcrOutput {
+    let words = tsv
+        .lines()
+        .skip(1) // header
+        .filter_map(|row| {
+            let cols: Vec<&str> = row.splitn(12, '\t').collect();
+            if cols.len() < 12 || cols[0] != "5" {
+                return None;
+            }
+            let num = |i: usize| cols[i].trim().parse::<u32>().ok();
+            let text = cols[11].trim();
+            let confidence = cols[10].trim().parse::<f32>().ok().filter(|c| *c >= 0.0)?;
+            if text.is_empty() {
+                return None;
+            }
+            Some(Word {
+                text: text.to_string(),
+                confidence,
+                bbox: (num(6)?, num(7)?, num(8)?, num(9)?),
+                line: (num(2)?, num(3)?, num(4)?),
+            })
+        })
+        .collect();
+    OcrOutput { words }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t800\t200\t-1\t
+4\t1\t1\t1\t1\t0\t10\t10\t300\t20\t-1\t
+5\t1\t1\t1\t1\t1\t10\t10\t80\t20\t96.5\tBorrow
+5\t1\t1\t1\t1\t2\t95\t10\t70\t20\t91\tchecker
+5\t1\t1\t1\t2\t1\t10\t40\t60\t20\t12.0\t~~
+5\t1\t1\t1\t2\t2\t75\t40\t90\t20\t88\tlifetimes
+";
+
+    #[test]
+    fn parses_words_and_filters_by_confidence() {
+        let out = parse_tsv(TSV);
+        assert_eq!(out.words.len(), 4);
+        assert_eq!(out.words[0].bbox, (10, 10, 80, 20));
+        assert_eq!(out.text(0.0), "Borrow checker\n~~ lifetimes");
+        assert_eq!(out.text(60.0), "Borrow checker\nlifetimes");
+    }
+
+    #[test]
+    #[cfg(not(feature = "tesseract"))]
+    fn enabling_ocr_without_an_engine_is_refused() {
+        let mut cfg = utils::config::OcrConfig::default();
+        assert!(check(&cfg).is_ok());
+        cfg.enabled = true;
+        assert!(matches!(check(&cfg), Err(Error::Unavailable)));
+    }
+
+    #[test]
+    fn stub_engine_runs_through_pipeline() {
+        let img = RgbaImage::from_pixel(64, 32, image::Rgba([255, 255, 255, 255]));
+        let out = recognize(&StubEngine::new("what is\nownership"), &img).unwrap();
+        assert_eq!(out.text(60.0), "what is\nownership");
+    }
+}
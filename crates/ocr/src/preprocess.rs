@@ -0,0 +1,145 @@
+//! Image clean-up before recognition: grayscale, upscale small captures and
+//! straighten slightly rotated text. Tesseract is tuned for ~300 dpi scans;
+//! screenshots are usually far smaller and sometimes photographed at an angle.
+
+use image::imageops::{self, FilterType};
+use image::{GrayImage, Luma, RgbaImage};
+
+/// Captures whose longer side is below this are upscaled.
+const UPSCALE_BELOW: u32 = 2000;
+/// Never produce images larger than this on either side.
+const MAX_SIDE: u32 = 6000;
+/// Skew search range and step, in degrees.
+const MAX_SKEW: f32 = 10.0;
+const SKEW_STEP: f32 = 0.5;
+
+/// Full preprocessing chain used by [`crate::recognize`].
+pub fn prepare(img: &RgbaImage) -> GrayImage {
+    let gray = grayscale(img);
+    let gray = upscale(&gray);
+    deskew(&gray)
+}
+
+pub fn grayscale(img: &RgbaImage) -> GrayImage {
+    imageops::grayscale(img)
+}
+
+/// Integer factor that brings the longer side above [`UPSCALE_BELOW`].
+pub fn upscale_factor(width: u32, height: u32) -> u32 {
+    let side = width.max(height).max(1);
+    if side >= UPSCALE_BELOW {
+        return 1;
+    }
+    (UPSCALE_BELOW / side + 1).min(MAX_SIDE / side).clamp(1, 4)
+}
+
+pub fn upscale(img: &GrayImage) -> GrayImage {
+    let factor = upscale_factor(img.width(), img.height());
+    if factor == 1 {
+        return img.clone();
+    }
+    imageops::resize(img, img.width() * factor, img.height() * factor, FilterType::CatmullRom)
+}
+
+/// Rotate the text back to horizontal when a skew is detected.
+pub fn deskew(img: &GrayImage) -> GrayImage {
+    let angle = estimate_skew(img);
+    if angle.abs() < SKEW_STEP {
+        return img.clone();
+    }
+    rotate(img, -angle)
+}
+
+/// Estimated text rotation in degrees (projection-profile method): the angle
+/// at which dark pixels fall into the fewest, densest rows.
+pub fn estimate_skew(img: &GrayImage) -> f32 {
+    let (w, h) = img.dimensions();
+    let threshold = mean_luma(img).saturating_sub(30);
+    // Sample dark pixels; large captures do not need every one.
+    let step = ((w as usize * h as usize) / 250_000).max(1) as u32;
+    let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+    let dark: Vec<(f32, f32)> = (0..h)
+        .step_by(step as usize)
+        .flat_map(|y| (0..w).step_by(step as usize).map(move |x| (x, y)))
+        .filter(|&(x, y)| img.get_pixel(x, y)[0] < threshold)
+        .map(|(x, y)| (x as f32 - cx, y as f32 - cy))
+        .collect();
+    if dark.is_empty() {
+        return 0.0;
+    }
+
+    let rows = (w + h) as usize + 1;
+    let mut best = (0.0f32, f64::MIN);
+    let steps = (MAX_SKEW / SKEW_STEP) as i32;
+    for i in -steps..=steps {
+        let angle = i as f32 * SKEW_STEP;
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let mut hist = vec![0u32; rows];
+        for &(x, y) in &dark {
+            let r = (y * cos - x * sin + rows as f32 / 2.0) as usize;
+            if let Some(bin) = hist.get_mut(r) {
+                *bin += 1;
+            }
+        }
+        let score: f64 = hist.iter().map(|&n| (n as f64).powi(2)).sum();
+        // Prefer the smallest rotation on ties.
+        if score > best.1 || (score == best.1 && angle.abs() < best.0.abs()) {
+            best = (angle, score);
+        }
+    }
+    best.0
+}
+
+/// Rotate around the centre by `degrees`, filling uncovered area with white.
+pub fn rotate(img: &GrayImage, degrees: f32) -> GrayImage {
+    let (w, h) = img.dimensions();
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+    GrayImage::from_fn(w, h, |x, y| {
+        let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+        // Inverse mapping: sample the source at R(-θ)·p.
+        let sx = dx * cos + dy * sin + cx;
+        let sy = -dx * sin + dy * cos + cy;
+        if sx < 0.0 || sy < 0.0 || sx >= w as f32 || sy >= h as f32 {
+            Luma([255])
+        } else {
+            *img.get_pixel(sx as u32, sy as u32)
+        }
+    })
+}
+
+fn mean_luma(img: &GrayImage) -> u8 {
+    let n = (img.width() as u64 * img.height() as u64).max(1);
+    (img.pixels().map(|p| p[0] as u64).sum::<u64>() / n) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// White page with a few thick horizontal "text lines".
+    fn lined_page() -> GrayImage {
+        GrayImage::from_fn(400, 300, |x, y| {
+            let on_line = (y % 40) < 6 && (40..360).contains(&x) && (40..260).contains(&y);
+            Luma([if on_line { 0 } else { 255 }])
+        })
+    }
+
+    #[test]
+    fn detects_and_corrects_skew() {
+        let page = lined_page();
+        assert_eq!(estimate_skew(&page), 0.0);
+
+        let skewed = rotate(&page, 4.0);
+        let angle = estimate_skew(&skewed);
+        assert!((angle - 4.0).abs() <= SKEW_STEP, "estimated {angle}");
+        assert!(estimate_skew(&deskew(&skewed)).abs() <= SKEW_STEP);
+    }
+
+    #[test]
+    fn upscales_small_captures_only() {
+        assert_eq!(upscale_factor(800, 200), 3);
+        assert_eq!(upscale_factor(2560, 1440), 1);
+        assert_eq!(upscale_factor(10, 10), 4);
+    }
+}
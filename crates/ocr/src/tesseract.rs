@@ -0,0 +1,40 @@
+//! Tesseract engine via `leptess`.
+
+use crate::{parse_tsv, Error, OcrEngine, OcrOutput, Result};
+use image::{GrayImage, ImageEncoder};
+use leptess::LepTess;
+
+/// Preprocessed images are roughly at scan resolution.
+const SOURCE_DPI: i32 = 300;
+
+pub struct Tesseract {
+    language: String,
+}
+
+impl Tesseract {
+    /// Fails early when the language data is not installed.
+    pub fn new(language: &str) -> Result<Self> {
+        LepTess::new(None, language).map_err(|e| Error::Init(e.to_string()))?;
+        Ok(Self { language: language.to_string() })
+    }
+}
+
+impl OcrEngine for Tesseract {
+    fn recognize(&self, img: &GrayImage) -> Result<OcrOutput> {
+        // LepTess is not Send; build one per call (init is cheap next to recognition).
+        let mut lt = LepTess::new(None, &self.language).map_err(|e| Error::Init(e.to_string()))?;
+
+        let mut png = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut png).write_image(
+            img,
+            img.width(),
+            img.height(),
+            image::ColorType::L8,
+        )?;
+        lt.set_image_from_mem(&png).map_err(|e| Error::Engine(e.to_string()))?;
+        lt.set_source_resolution(SOURCE_DPI);
+
+        let tsv = lt.get_tsv_text(0).map_err(|e| Error::Engine(e.to_string()))?;
+        Ok(parse_tsv(&tsv))
+    }
+}
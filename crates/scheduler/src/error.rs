@@ -0,0 +1,30 @@
+//! Errors returned by the `scheduler` crate.
+
+use utils::error::Coded;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("card #{0} does not exist")]
+    UnknownCard(i64),
+    /// Reading or writing the review log or schedule failed; keeps its `db.*` code.
+    #[error(transparent)]
+    Data(#[from] data::Error),
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::UnknownCard(_) => "scheduler.unknown_card",
+            Error::Data(e) => e.code(),
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        match self {
+            Error::UnknownCard(_) => false,
+            Error::Data(e) => e.retryable(),
+        }
+    }
+}
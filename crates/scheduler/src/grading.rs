@@ -0,0 +1,241 @@
+//! Checking typed answers against the back of a card. The string checks here
+//! are pure and cheap; the daemon may escalate answers they reject to an
+//! embedding comparison or an LLM judge (see `[grading]`).
+
+use serde::{Deserialize, Serialize};
+use utils::config::GradingConfig;
+
+/// How well a card was recalled, as in Anki. Maps onto SM-2 quality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    pub fn passed(self) -> bool {
+        self != Grade::Again
+    }
+
+    /// SM-2 response quality (0–5).
+    pub fn quality(self) -> f32 {
+        match self {
+            Grade::Again => 1.0,
+            Grade::Hard => 3.0,
+            Grade::Good => 4.0,
+            Grade::Easy => 5.0,
+        }
+    }
+
+    /// Name as serialized, e.g. `"again"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        }
+    }
+}
+
+/// Which check settled a [`Verdict`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    Exact,
+    Fuzzy,
+    Semantic,
+    Judge,
+}
+
+/// Suggested grade for a typed answer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verdict {
+    pub grade: Grade,
+    /// Similarity to the expected answer, 0–1.
+    pub score: f32,
+    pub method: Method,
+    /// What was wrong or missing, or the typos an accepted answer had.
+    pub explanation: Option<String>,
+    /// The answer got a number wrong. Closeness in meaning cannot fix that, so
+    /// such answers are not escalated to the semantic check or the judge.
+    #[serde(default)]
+    pub wrong_number: bool,
+}
+
+/// Words that carry no meaning of their own in an answer.
+const FILLER: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "did", "do", "does", "for", "from", "had", "has", "have", "in",
+    "is", "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were", "when", "which", "with",
+];
+
+/// Word similarity from which two words count as the same word misspelt.
+const TYPO: f32 = 0.75;
+
+/// Compare `answer` with the `expected` back of the card.
+pub fn check(answer: &str, expected: &str, cfg: &GradingConfig) -> Verdict {
+    let (given, wanted) = (normalize(answer), normalize(expected));
+    if given.is_empty() {
+        return Verdict {
+            grade: Grade::Again,
+            score: 0.0,
+            method: Method::Exact,
+            explanation: Some("No answer given.".into()),
+            wrong_number: false,
+        };
+    }
+    if given == wanted {
+        return Verdict {
+            grade: Grade::Good,
+            score: 1.0,
+            method: Method::Exact,
+            explanation: None,
+            wrong_number: false,
+        };
+    }
+
+    let (given_words, wanted_words) = (words(&given), keywords(&wanted));
+    let mut missing = Vec::new();
+    let mut typos = Vec::new();
+    let mut found = 0;
+    for want in &wanted_words {
+        let best = given_words
+            .iter()
+            .map(|g| (g, similarity(g, want)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((_, 1.0)) => found += 1,
+            Some((g, s)) if s >= TYPO && !is_number(want) => {
+                found += 1;
+                typos.push(format!("“{g}” → “{want}”"));
+            }
+            _ => missing.push(want.as_str()),
+        }
+    }
+    let recall = if wanted_words.is_empty() { 0.0 } else { found as f32 / wanted_words.len() as f32 };
+    let score = similarity(&given, &wanted).max(recall);
+
+    // A wrong number is a wrong answer however close the rest is.
+    let numbers: Vec<&str> = missing.iter().copied().filter(|w| is_number(w)).collect();
+    let grade = if !numbers.is_empty() {
+        Grade::Again
+    } else if score >= cfg.fuzzy {
+        Grade::Good
+    } else if score >= cfg.partial {
+        Grade::Hard
+    } else {
+        Grade::Again
+    };
+
+    let mut notes = Vec::new();
+    if !numbers.is_empty() {
+        let given_numbers: Vec<&str> = given_words.iter().map(String::as_str).filter(|w| is_number(w)).collect();
+        if given_numbers.is_empty() {
+            notes.push(format!("Expected {}.", numbers.join(", ")));
+        } else {
+            notes.push(format!("Expected {}, not {}.", numbers.join(", "), given_numbers.join(", ")));
+        }
+    }
+    let terms: Vec<String> = missing.iter().filter(|w| !is_number(w)).map(|w| format!("“{w}”")).collect();
+    if !terms.is_empty() && grade != Grade::Good {
+        notes.push(format!("Missing {}.", terms.join(", ")));
+    }
+    if !typos.is_empty() {
+        notes.push(format!("Spelling: {}.", typos.join(", ")));
+    }
+    if notes.is_empty() && grade != Grade::Good {
+        notes.push(format!("Expected “{}”.", expected.trim()));
+    }
+    let explanation = (!notes.is_empty()).then(|| notes.join(" "));
+    Verdict { grade, score, method: Method::Fuzzy, explanation, wrong_number: !numbers.is_empty() }
+}
+
+/// Lowercase, drop punctuation and leading articles, collapse whitespace.
+pub fn normalize(text: &str) -> String {
+    let lower: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let mut out: Vec<&str> = lower.split_whitespace().collect();
+    while out.len() > 1 && matches!(out[0], "a" | "an" | "the") {
+        out.remove(0);
+    }
+    out.join(" ")
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(' ').filter(|w| !w.is_empty()).map(str::to_owned).collect()
+}
+
+/// Words of the expected answer a correct answer should contain.
+fn keywords(text: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for w in words(text) {
+        if !FILLER.contains(&w.as_str()) && !out.contains(&w) {
+            out.push(w);
+        }
+    }
+    out
+}
+
+fn is_number(word: &str) -> bool {
+    word.chars().any(|c| c.is_ascii_digit())
+}
+
+/// 1 minus the Levenshtein distance over the longer length, by characters.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let swap = prev[j] + usize::from(ca != cb);
+            row[j + 1] = swap.min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    1.0 - prev[b.len()] as f32 / longest as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grade(answer: &str, expected: &str) -> Verdict {
+        check(answer, expected, &GradingConfig::default())
+    }
+
+    #[test]
+    fn grades_typed_answers() {
+        assert_eq!(grade("  paris!", "Paris").grade, Grade::Good);
+        assert_eq!(grade("the mitochondria", "Mitochondria").method, Method::Exact);
+
+        let typo = grade("mitocondria", "mitochondria");
+        assert_eq!(typo.grade, Grade::Good);
+        assert!(typo.explanation.unwrap().contains("“mitocondria” → “mitochondria”"));
+
+        let wrong_year = grade("in 1798", "In 1789");
+        assert_eq!(wrong_year.grade, Grade::Again);
+        assert_eq!(wrong_year.explanation.as_deref(), Some("Expected 1789, not 1798."));
+        assert!(wrong_year.wrong_number);
+        assert!(!grade("London", "Paris").wrong_number);
+
+        let partial = grade(
+            "one owner, dropped when it goes out of scope",
+            "Each value has a single owner and is dropped when the owner goes out of scope.",
+        );
+        assert_eq!(partial.grade, Grade::Hard, "{partial:?}");
+        assert!(partial.explanation.unwrap().contains("“single”"));
+
+        assert_eq!(grade("London", "Paris").grade, Grade::Again);
+        assert_eq!(grade("", "Paris").explanation.as_deref(), Some("No answer given."));
+    }
+}
@@ -1,8 +1,23 @@
-//! Deterministic, pure SRS scheduler (SM-2).
+//! Deterministic, pure SRS scheduler (SM-2), with Anki-style learning steps
+//! in front of it: new cards are shown again after short delays (`1m`, `10m`)
+//! before they graduate to day-based review, and forgotten cards relearn.
 
 use chrono::{DateTime, Duration, Utc};
+use data::Phase;
 use serde::{Deserialize, Serialize};
-use tracing::debug;
+use tokio::sync::watch;
+use tracing::{debug, warn};
+use utils::config::{Config, SchedulerConfig};
+
+mod error;
+pub mod grading;
+pub mod queue;
+pub mod reminders;
+
+pub use error::{Error, Result};
+pub use grading::{Grade, Verdict};
+pub use queue::QueueBuilder;
+pub use reminders::{DueSummary, ReminderPolicy, Reminders};
 
 /// Result of a single card review.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,12 +25,32 @@ pub struct ReviewOutcome {
     pub card_id: i64,
     pub passed: bool,
     pub reviewed_at: DateTime<Utc>,
+    /// Finer-grained than `passed` when the reviewer chose one.
+    #[serde(default)]
+    pub grade: Option<Grade>,
+    /// Time from showing the card to grading it, when the client measured it.
+    #[serde(default)]
+    pub duration_ms: Option<u32>,
+}
+
+impl ReviewOutcome {
+    /// The grade to schedule by; a bare pass counts as `Easy`, as it always has.
+    pub fn grade(&self) -> Grade {
+        match self.grade {
+            Some(grade) => grade,
+            None if self.passed => Grade::Easy,
+            None => Grade::Again,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-struct CardState {
-    efactor: f32,
-    interval: u32, // days
+pub struct CardState {
+    pub efactor: f32,
+    pub interval: u32, // days
+    pub phase: Phase,
+    /// Index into the learning or relearning steps.
+    pub step: u32,
 }
 
 impl Default for CardState {
@@ -23,47 +58,216 @@ impl Default for CardState {
         Self {
             efactor: 2.5,
             interval: 1,
+            phase: Phase::New,
+            step: 0,
+        }
+    }
+}
+
+/// Delays for cards in (re)learning, from `[scheduler]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LearningSteps {
+    pub learning: Vec<Duration>,
+    pub relearning: Vec<Duration>,
+    /// Interval once a new card passes its last step.
+    pub graduating_days: u32,
+    /// Interval for a new card answered `easy`.
+    pub easy_days: u32,
+}
+
+impl LearningSteps {
+    pub fn from_config(cfg: &SchedulerConfig) -> Self {
+        // Config is validated on load, so a parse failure here means "no steps".
+        Self {
+            learning: cfg.learning_steps().unwrap_or_default(),
+            relearning: cfg.relearning_steps().unwrap_or_default(),
+            graduating_days: cfg.graduating_days.max(1),
+            easy_days: cfg.easy_days.max(1),
         }
     }
 }
 
+impl Default for LearningSteps {
+    fn default() -> Self {
+        Self::from_config(&SchedulerConfig::default())
+    }
+}
+
 pub struct Scheduler {
     db: data::DbPool,
-    outcome_tx: tokio::sync::mpsc::Sender<ReviewOutcome>,
+    config: watch::Receiver<Config>,
+    reminders: Reminders,
+    due_tx: tokio::sync::mpsc::Sender<DueSummary>,
 }
 
 impl Scheduler {
     pub fn new(
         db: data::DbPool,
-        outcome_tx: tokio::sync::mpsc::Sender<ReviewOutcome>,
+        config: watch::Receiver<Config>,
+        reminders: Reminders,
+        due_tx: tokio::sync::mpsc::Sender<DueSummary>,
     ) -> Self {
-        Self { db, outcome_tx }
+        Self { db, config, reminders, due_tx }
     }
 
-    /// Periodically scans for due cards and notifies the UI layer.
-    pub async fn run(self) {
+    /// Periodically scans for due cards and notifies the UI layer. Cards in
+    /// learning are surfaced as their step runs out, even between reminders.
+    pub async fn run(mut self) {
+        let mut last_learning = 0;
         loop {
-            let due_cards = data::fetch_due_cards(&self.db, Utc::now())
-                .unwrap_or_default();
+            // Pick up hot-reloaded reminder settings.
+            if self.config.has_changed().unwrap_or(false) {
+                let cfg = self.config.borrow_and_update();
+                self.reminders.set_policy(ReminderPolicy::from(&cfg.reminders));
+            }
+
+            let now = Utc::now();
+            // Only what today's limits let through is worth a reminder.
+            let queue = QueueBuilder::from_config(&self.config.borrow());
+            let cards = queue.due(&self.db, now).unwrap_or_default();
+            let due = cards.len();
+            let learning = cards.iter().filter(|c| c.phase.is_learning()).count();
+
+            if due > 0 {
+                debug!("{} cards due ({} learning)", due, learning);
+            }
+            let surface = learning > last_learning && self.reminders.allows(now);
+            last_learning = learning;
+            if self.reminders.should_notify(due, now) || surface {
+                let summary = DueSummary { due, at: now };
+                if self.due_tx.send(summary).await.is_err() {
+                    warn!("due-card receiver dropped; stopping scheduler loop");
+                    break;
+                }
+            }
+
+            let tick = std::time::Duration::from_secs(self.config.borrow().scheduler.tick_secs);
+            // Wake just after the next learning step runs out rather than up to a tick late.
+            let wake = match data::next_learning_due(&self.db, now) {
+                Ok(Some(at)) => (at + Duration::seconds(1) - Utc::now()).to_std().unwrap_or_default().min(tick),
+                _ => tick,
+            };
+            tokio::time::sleep(wake).await;
+        }
+    }
+
+    /// Persist a review and reschedule the card. Returns the new due date.
+    pub fn record(db: &data::DbPool, steps: &LearningSteps, outcome: &ReviewOutcome) -> Result<DateTime<Utc>> {
+        let sched = data::fetch_schedule(db, outcome.card_id).map_err(|e| match e {
+            data::Error::NotFound(_) => Error::UnknownCard(outcome.card_id),
+            other => other.into(),
+        })?;
+        let mut state = CardState {
+            efactor: sched.efactor,
+            interval: sched.interval.max(1),
+            phase: sched.phase,
+            step: sched.step,
+        };
+        let grade = match outcome.grade {
+            // A bare pass walks through the learning steps instead of skipping them.
+            None if outcome.passed && state.phase != Phase::Review => Grade::Good,
+            _ => outcome.grade(),
+        };
+        let next_due = outcome.reviewed_at + Self::answer(&mut state, grade, steps);
+
+        data::log_review(
+            db,
+            &data::ReviewLog {
+                card_id: outcome.card_id,
+                reviewed_at: outcome.reviewed_at,
+                passed: grade.passed(),
+                grade: outcome.grade.map(|g| g.as_str().to_owned()),
+                duration_ms: outcome.duration_ms,
+            },
+        )?;
+        data::update_schedule(
+            db,
+            outcome.card_id,
+            &data::CardSchedule {
+                efactor: state.efactor,
+                interval: state.interval,
+                next_due,
+                phase: state.phase,
+                step: state.step,
+            },
+        )?;
+        Ok(next_due)
+    }
 
-            if !due_cards.is_empty() {
-                debug!("{} cards due", due_cards.len());
-                // TODO: IPC → UI notification; placeholder emits debug.
+    /// Move a card through New → Learning → Review ⇄ Relearning and return
+    /// the delay until it is next shown.
+    pub fn answer(state: &mut CardState, grade: Grade, steps: &LearningSteps) -> Duration {
+        match state.phase {
+            Phase::Review if grade.passed() => Self::next_graded(state, grade),
+            Phase::Review => {
+                // Lapse: once relearnt the card starts over at a one-day interval.
+                state.interval = 1;
+                state.step = 0;
+                match steps.relearning.first() {
+                    Some(&delay) => {
+                        state.phase = Phase::Relearning;
+                        delay
+                    }
+                    None => Duration::days(1),
+                }
             }
+            Phase::New | Phase::Learning => {
+                Self::step(state, grade, &steps.learning, Phase::Learning, steps.graduating_days, steps.easy_days)
+            }
+            Phase::Relearning => {
+                let days = state.interval;
+                Self::step(state, grade, &steps.relearning, Phase::Relearning, days, days)
+            }
+        }
+    }
 
-            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+    /// One answer on `delays`: `again` restarts them, `hard` repeats the
+    /// current step, `good` moves on and `easy` skips the rest. Past the last
+    /// step the card graduates to review; without steps a failed answer comes
+    /// back the next day.
+    fn step(
+        state: &mut CardState,
+        grade: Grade,
+        delays: &[Duration],
+        phase: Phase,
+        good_days: u32,
+        easy_days: u32,
+    ) -> Duration {
+        let next = match grade {
+            Grade::Again => Some(0),
+            Grade::Hard => Some(state.step),
+            Grade::Good => Some(state.step + 1),
+            Grade::Easy => None,
+        };
+        if let Some((step, &delay)) = next.and_then(|i| Some((i, delays.get(i as usize)?))) {
+            state.phase = phase;
+            state.step = step;
+            return delay;
         }
+        state.phase = Phase::Review;
+        state.step = 0;
+        state.interval = match grade {
+            Grade::Easy => easy_days,
+            Grade::Good => good_days,
+            // No step to fall back to: never further out than a pass.
+            Grade::Again | Grade::Hard => 1,
+        };
+        Duration::days(state.interval as i64)
     }
 
     /// Evaluate the next interval for a card given its state and pass/fail.
     pub fn next(state: &mut CardState, passed: bool) -> Duration {
-        if !passed {
+        Self::next_graded(state, if passed { Grade::Easy } else { Grade::Again })
+    }
+
+    /// Evaluate the next interval for a card given its state and grade.
+    pub fn next_graded(state: &mut CardState, grade: Grade) -> Duration {
+        if !grade.passed() {
             state.interval = 1;
             return Duration::days(1);
         }
 
-        // We only record pass/fail; treat pass as quality 5 in SM-2.
-        let quality = 5.0;
+        let quality = grade.quality();
         let ef = state.efactor + 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02);
         state.efactor = ef.max(1.3);
 
@@ -86,5 +290,38 @@ mod tests {
         assert_eq!(Scheduler::next(&mut st, true).num_days(), 6);
         let next = Scheduler::next(&mut st, true).num_days();
         assert!(next > 6);
+
+        // A hard recall grows the interval less and lowers the ease.
+        let (mut easy, mut hard) = (st.clone(), st.clone());
+        let easy_days = Scheduler::next_graded(&mut easy, Grade::Easy).num_days();
+        assert!(Scheduler::next_graded(&mut hard, Grade::Hard).num_days() < easy_days);
+        assert!(hard.efactor < st.efactor);
+    }
+
+    #[test]
+    fn learning_steps_then_review_and_relearning() {
+        let steps = LearningSteps::default();
+        let mut st = CardState::default();
+        let mut answer = |grade| Scheduler::answer(&mut st, grade, &steps);
+
+        assert_eq!(answer(Grade::Again), Duration::minutes(1));
+        assert_eq!(answer(Grade::Good), Duration::minutes(10));
+        assert_eq!(answer(Grade::Hard), Duration::minutes(10), "hard repeats the step");
+        assert_eq!(answer(Grade::Good), Duration::days(1), "graduated");
+        assert_eq!(answer(Grade::Good), Duration::days(6));
+        assert_eq!(answer(Grade::Again), Duration::minutes(10), "lapse relearns");
+        assert_eq!(answer(Grade::Good), Duration::days(1));
+        assert_eq!((st.phase, st.step, st.interval), (Phase::Review, 0, 1));
+
+        let mut new = CardState::default();
+        assert_eq!(Scheduler::answer(&mut new, Grade::Easy, &steps), Duration::days(4));
+        let none = LearningSteps { learning: Vec::new(), relearning: Vec::new(), graduating_days: 3, ..steps };
+        for grade in [Grade::Again, Grade::Hard] {
+            let mut new = CardState::default();
+            assert_eq!(Scheduler::answer(&mut new, grade, &none), Duration::days(1), "no steps: back tomorrow");
+            assert_eq!((new.phase, new.interval), (Phase::Review, 1));
+        }
+        let mut new = CardState::default();
+        assert_eq!(Scheduler::answer(&mut new, Grade::Good, &none), Duration::days(3));
     }
-} 
\ No newline at end of file
+}
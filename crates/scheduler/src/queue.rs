@@ -0,0 +1,192 @@
+//! Today's review queue. Due cards are cut to the daily limits of `[queue]`
+//! – totals and per deck, counted from the start of the study day – reviews
+//! are ordered by overdueness or shuffled, and new cards are spread evenly
+//! between them. Cards in (re)learning whose step ran out come first and are
+//! not limited: they were started already.
+
+use chrono::{DateTime, Datelike, Utc};
+use crate::Result;
+use data::{CardJson, DbPool, DueCard, Phase, Studied};
+use std::collections::HashMap;
+use utils::clock::StudyClock;
+use utils::config::{Config, DeckLimits, QueueConfig, QueueOrder};
+
+#[derive(Debug, Clone)]
+pub struct QueueBuilder {
+    cfg: QueueConfig,
+    clock: StudyClock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    New,
+    Review,
+}
+
+impl QueueBuilder {
+    pub fn new(cfg: QueueConfig, clock: StudyClock) -> Self {
+        Self { cfg, clock }
+    }
+
+    pub fn from_config(cfg: &Config) -> Self {
+        Self::new(cfg.queue.clone(), StudyClock::from_config(&cfg.scheduler))
+    }
+
+    /// The cards to review at `now`, in order.
+    pub fn build(&self, db: &DbPool, now: DateTime<Utc>) -> Result<Vec<CardJson>> {
+        Ok(self.due(db, now)?.into_iter().map(|c| c.card).collect())
+    }
+
+    /// Like [`build`](Self::build), keeping what each card was queued by.
+    pub fn due(&self, db: &DbPool, now: DateTime<Utc>) -> Result<Vec<DueCard>> {
+        let due = data::fetch_due(db, now)?;
+        let studied = data::studied_since(db, self.clock.start(self.clock.day(now)))?;
+        Ok(self.arrange(due, &studied, now))
+    }
+
+    /// Order `due` and cut it to what the limits leave after `studied`.
+    pub fn arrange(&self, due: Vec<DueCard>, studied: &[Studied], now: DateTime<Utc>) -> Vec<DueCard> {
+        // Oldest step first; `due` comes sorted by when each card fell due.
+        let (mut queue, rest): (Vec<_>, Vec<_>) = due.into_iter().partition(|c| c.phase.is_learning());
+        let (mut new, mut reviews): (Vec<_>, Vec<_>) = rest.into_iter().partition(|c| c.phase == Phase::New);
+        match self.cfg.order {
+            QueueOrder::Overdue => reviews.sort_by(|a, b| {
+                overdueness(b, now).total_cmp(&overdueness(a, now)).then(a.card.id.cmp(&b.card.id))
+            }),
+            QueueOrder::Random => {
+                let day = self.clock.day(now).num_days_from_ce();
+                reviews.sort_by_key(|c| shuffle_key(c.card.id, day));
+            }
+        }
+        new.sort_by_key(|c| c.card.id);
+        let reviews = self.take(reviews, studied, Kind::Review);
+        let new = self.take(new, studied, Kind::New);
+        queue.extend(interleave(reviews, new));
+        queue
+    }
+
+    /// The first of `cards` that fit the total and every deck limit they fall under.
+    fn take(&self, cards: Vec<DueCard>, studied: &[Studied], kind: Kind) -> Vec<DueCard> {
+        let limit = |l: &DeckLimits| if kind == Kind::New { l.new_per_day } else { l.reviews_per_day };
+        let done = |s: &Studied| if kind == Kind::New { s.new } else { s.reviews };
+        let total = if kind == Kind::New { self.cfg.new_per_day } else { self.cfg.reviews_per_day };
+
+        let mut left = total.saturating_sub(studied.iter().map(done).sum());
+        let mut deck_left: HashMap<&str, u32> = self
+            .cfg
+            .decks
+            .iter()
+            .filter_map(|(deck, limits)| {
+                let used: u32 = studied.iter().filter(|s| in_deck(s.deck.as_deref(), deck)).map(done).sum();
+                Some((deck.as_str(), limit(limits)?.saturating_sub(used)))
+            })
+            .collect();
+
+        let mut out = Vec::new();
+        for c in cards {
+            if left == 0 {
+                break;
+            }
+            let decks: Vec<&str> =
+                deck_left.keys().copied().filter(|d| in_deck(c.card.deck.as_deref(), d)).collect();
+            if decks.iter().any(|d| deck_left[d] == 0) {
+                continue;
+            }
+            for d in decks {
+                deck_left.entry(d).and_modify(|n| *n -= 1);
+            }
+            left -= 1;
+            out.push(c);
+        }
+        out
+    }
+}
+
+/// Whether a card filed in `deck` falls under the limits of `parent`.
+fn in_deck(deck: Option<&str>, parent: &str) -> bool {
+    deck.is_some_and(|d| d.strip_prefix(parent).is_some_and(|rest| rest.is_empty() || rest.starts_with("::")))
+}
+
+/// How late a review is relative to its interval; 1.0 is a whole interval late.
+fn overdueness(c: &DueCard, now: DateTime<Utc>) -> f64 {
+    (now - c.next_due).num_seconds().max(0) as f64 / (f64::from(c.interval.max(1)) * 86_400.0)
+}
+
+/// Sort key for a shuffle that stays put for the whole study day (splitmix64).
+fn shuffle_key(id: i64, day: i32) -> u64 {
+    let mut x = (id as u64) ^ ((day as u64) << 32);
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// New cards spread evenly through the reviews: review `i` sits at
+/// `(i+1)/(r+1)` of the way through, new card `j` at `(j+1)/(n+1)`.
+fn interleave(reviews: Vec<DueCard>, new: Vec<DueCard>) -> Vec<DueCard> {
+    let (r, n) = (reviews.len(), new.len());
+    let mut slots: Vec<(usize, Kind, DueCard)> = reviews
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| ((i + 1) * (n + 1), Kind::Review, c))
+        .chain(new.into_iter().enumerate().map(|(j, c)| ((j + 1) * (r + 1), Kind::New, c)))
+        .collect();
+    slots.sort_by_key(|(at, kind, _)| (*at, *kind == Kind::New));
+    slots.into_iter().map(|(_, _, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn limits_orders_and_interleaves() {
+        let now = DateTime::parse_from_rfc3339("2026-03-10T09:00:00Z").unwrap().with_timezone(&Utc);
+        let card = |id: i64, deck: &str, phase: Phase, days_late: i64, interval: u32| DueCard {
+            card: CardJson {
+                id,
+                front: format!("#{id}"),
+                back: String::new(),
+                tags: Vec::new(),
+                source: None,
+                media: None,
+                deck: Some(deck.into()),
+            },
+            next_due: now - Duration::days(days_late),
+            interval,
+            phase,
+        };
+        let due = vec![
+            card(1, "Rust", Phase::Review, 2, 30),
+            card(2, "Rust", Phase::Review, 2, 2),
+            card(3, "Lectures::Biology", Phase::Review, 10, 20),
+            card(4, "Lectures::Biology", Phase::Review, 1, 1),
+            card(10, "Rust", Phase::New, 0, 1),
+            card(11, "Lectures::Chemistry", Phase::New, 0, 1),
+            card(12, "Lectures::Biology", Phase::New, 0, 1),
+            card(13, "Rust", Phase::New, 0, 1),
+            card(20, "Lectures::Biology", Phase::Relearning, 0, 1),
+        ];
+        let mut cfg = QueueConfig { new_per_day: 4, ..QueueConfig::default() };
+        cfg.decks = BTreeMap::from([("Lectures".to_string(), DeckLimits { new_per_day: Some(2), reviews_per_day: None })]);
+        let queue = QueueBuilder::new(cfg, StudyClock::new(utils::clock::Tz::UTC, 4));
+
+        // Already today: one new Chemistry card, so Lectures has one new card left.
+        let studied = [Studied { deck: Some("Lectures::Chemistry".into()), new: 1, reviews: 0 }];
+        let ids: Vec<i64> = queue.arrange(due.clone(), &studied, now).iter().map(|c| c.card.id).collect();
+        // The relearning card first, then reviews by relative lateness (2/2,
+        // 1/1, 10/20, 2/30) with the three new cards left spread between them;
+        // 12 is over the Lectures limit.
+        assert_eq!(ids, [20, 2, 10, 4, 11, 3, 13, 1]);
+
+        let cfg = QueueConfig { order: QueueOrder::Random, new_per_day: 0, ..QueueConfig::default() };
+        let random = QueueBuilder::new(cfg, StudyClock::new(utils::clock::Tz::UTC, 4));
+        let ids = |at| random.arrange(due.clone(), &[], at).iter().map(|c| c.card.id).collect::<Vec<_>>();
+        let mut sorted = ids(now);
+        sorted.sort();
+        assert_eq!(sorted, [1, 2, 3, 4, 20], "new cards paused");
+        assert_eq!(ids(now), ids(now + Duration::hours(3)), "the shuffle holds for the study day");
+    }
+}
@@ -0,0 +1,167 @@
+//! Due-card reminder policy: quiet hours, minimum gap between reminders and snooze.
+
+use chrono::{DateTime, Duration, Local, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use utils::config::ReminderConfig;
+
+/// Summary of due cards pushed to the UI layers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueSummary {
+    pub due: usize,
+    pub at: DateTime<Utc>,
+}
+
+/// User-facing knobs controlling when reminders may fire.
+#[derive(Debug, Clone)]
+pub struct ReminderPolicy {
+    /// Local-time window (start, end) during which no reminder is shown.
+    /// The window may wrap past midnight, e.g. 22:00 → 08:00.
+    pub quiet_hours: Option<(NaiveTime, NaiveTime)>,
+    /// Minimum time between two reminders.
+    pub min_gap: Duration,
+}
+
+impl Default for ReminderPolicy {
+    fn default() -> Self {
+        Self {
+            quiet_hours: Some((
+                NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+            )),
+            min_gap: Duration::minutes(60),
+        }
+    }
+}
+
+impl From<&ReminderConfig> for ReminderPolicy {
+    fn from(cfg: &ReminderConfig) -> Self {
+        Self {
+            // Config is validated on load, so a parse failure here means "off".
+            quiet_hours: cfg.quiet_hours().unwrap_or_default(),
+            min_gap: Duration::minutes(cfg.min_gap_minutes as i64),
+        }
+    }
+}
+
+impl ReminderPolicy {
+    /// Whether `t` (local wall-clock time) falls inside quiet hours.
+    pub fn is_quiet(&self, t: NaiveTime) -> bool {
+        match self.quiet_hours {
+            None => false,
+            Some((start, end)) if start <= end => t >= start && t < end,
+            Some((start, end)) => t >= start || t < end,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReminderState {
+    last_sent: Option<DateTime<Utc>>,
+    last_due: usize,
+    snoozed_until: Option<DateTime<Utc>>,
+}
+
+/// Shared reminder gate. Cheap to clone; the scheduler loop asks it whether to
+/// notify while UI layers use it to snooze.
+#[derive(Debug, Clone)]
+pub struct Reminders {
+    policy: Arc<Mutex<ReminderPolicy>>,
+    state: Arc<Mutex<ReminderState>>,
+}
+
+impl Reminders {
+    pub fn new(policy: ReminderPolicy) -> Self {
+        Self {
+            policy: Arc::new(Mutex::new(policy)),
+            state: Arc::new(Mutex::new(ReminderState::default())),
+        }
+    }
+
+    /// Replace the policy, keeping snooze and last-sent state.
+    pub fn set_policy(&self, policy: ReminderPolicy) {
+        *self.policy.lock().unwrap() = policy;
+    }
+
+    /// Suppress reminders for the given duration.
+    pub fn snooze(&self, dur: Duration) {
+        self.state.lock().unwrap().snoozed_until = Some(Utc::now() + dur);
+    }
+
+    /// Lift an active snooze.
+    pub fn resume(&self) {
+        self.state.lock().unwrap().snoozed_until = None;
+    }
+
+    pub fn snoozed_until(&self) -> Option<DateTime<Utc>> {
+        self.state.lock().unwrap().snoozed_until
+    }
+
+    /// Whether any reminder may show at `now`: not snoozed and outside quiet hours.
+    pub fn allows(&self, now: DateTime<Utc>) -> bool {
+        let quiet = self.policy.lock().unwrap().is_quiet(now.with_timezone(&Local).time());
+        !quiet && self.snoozed_until().is_none_or(|until| now >= until)
+    }
+
+    /// Decide whether a reminder for `due` cards should fire now, recording it if so.
+    pub fn should_notify(&self, due: usize, now: DateTime<Utc>) -> bool {
+        let local = now.with_timezone(&Local).time();
+        self.should_notify_at(due, now, local)
+    }
+
+    fn should_notify_at(&self, due: usize, now: DateTime<Utc>, local: NaiveTime) -> bool {
+        let policy = self.policy.lock().unwrap().clone();
+        let mut st = self.state.lock().unwrap();
+
+        if due == 0 {
+            st.last_due = 0;
+            return false;
+        }
+        if st.snoozed_until.is_some_and(|until| now < until) {
+            return false;
+        }
+        if policy.is_quiet(local) {
+            return false;
+        }
+        // Within the gap only nag again if the backlog actually grew.
+        if let Some(last) = st.last_sent {
+            if now - last < policy.min_gap || (due <= st.last_due && now - last < policy.min_gap * 2) {
+                return false;
+            }
+        }
+
+        st.last_sent = Some(now);
+        st.last_due = due;
+        st.snoozed_until = None;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn quiet_hours_wrap_midnight() {
+        let p = ReminderPolicy::default();
+        assert!(p.is_quiet(t(23, 30)));
+        assert!(p.is_quiet(t(7, 59)));
+        assert!(!p.is_quiet(t(12, 0)));
+    }
+
+    #[test]
+    fn respects_gap_and_snooze() {
+        let r = Reminders::new(ReminderPolicy { quiet_hours: None, min_gap: Duration::minutes(30) });
+        let now = Utc::now();
+        assert!(r.should_notify_at(3, now, t(12, 0)));
+        assert!(!r.should_notify_at(5, now + Duration::minutes(10), t(12, 10)));
+        assert!(r.should_notify_at(5, now + Duration::minutes(31), t(12, 31)));
+
+        r.state.lock().unwrap().snoozed_until = Some(now + Duration::hours(3));
+        assert!(!r.should_notify_at(9, now + Duration::hours(2), t(14, 0)));
+    }
+}
@@ -0,0 +1,77 @@
+//! Mono PCM buffers and WAV files.
+
+use crate::Result;
+use std::path::Path;
+
+/// Mono samples in −1..1 at `rate` Hz.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Audio {
+    pub samples: Vec<f32>,
+    pub rate: u32,
+}
+
+impl Audio {
+    /// Average interleaved `channels` down to mono.
+    pub fn from_interleaved(data: &[f32], channels: u16, rate: u32) -> Self {
+        let channels = usize::from(channels.max(1));
+        let samples = data.chunks(channels).map(|frame| frame.iter().sum::<f32>() / frame.len() as f32).collect();
+        Self { samples, rate }
+    }
+
+    /// Read a PCM (8–32 bit integer) or float WAV file of any channel count.
+    pub fn read_wav(path: &Path) -> Result<Self> {
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let data: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1u64 << (spec.bits_per_sample - 1)) as f32;
+                reader.samples::<i32>().map(|s| s.map(|s| s as f32 / scale)).collect::<Result<_, _>>()?
+            }
+        };
+        Ok(Self::from_interleaved(&data, spec.channels, spec.sample_rate))
+    }
+
+    /// Write as 16-bit mono PCM.
+    pub fn write_wav(&self, path: &Path) -> Result<()> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: self.rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for s in &self.samples {
+            writer.write_sample((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+        }
+        writer.finalize()?;
+        Ok(())
+    }
+
+    /// Linear-interpolation resample; good enough for speech going to 16 kHz.
+    pub fn resample(&self, rate: u32) -> Self {
+        if rate == self.rate || self.samples.is_empty() {
+            return Self { samples: self.samples.clone(), rate };
+        }
+        let step = self.rate as f64 / rate as f64;
+        let len = (self.samples.len() as f64 / step).floor() as usize;
+        let last = self.samples.len() - 1;
+        let samples = (0..len)
+            .map(|i| {
+                let pos = i as f64 * step;
+                let (at, frac) = (pos as usize, (pos.fract()) as f32);
+                let (a, b) = (self.samples[at.min(last)], self.samples[(at + 1).min(last)]);
+                a + (b - a) * frac
+            })
+            .collect();
+        Self { samples, rate }
+    }
+
+    pub fn seconds(&self) -> f32 {
+        if self.rate == 0 {
+            0.0
+        } else {
+            self.samples.len() as f32 / self.rate as f32
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Errors returned by the `speech` crate.
+
+use utils::error::Coded;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// `feature` names the cargo feature the build lacks.
+    #[error("{0} support not compiled in (enable the `{0}` feature)")]
+    Unavailable(&'static str),
+    #[error("microphone use is off; set speech.microphone = true to answer by voice")]
+    MicrophoneOff,
+    #[error("microphone failed: {0}")]
+    Device(String),
+    /// Usually a missing or truncated model file.
+    #[error("speech model could not be loaded: {0}")]
+    Model(String),
+    #[error("transcription failed: {0}")]
+    Engine(String),
+    #[error("no speech detected")]
+    NoSpeech,
+    #[error("speech synthesis failed: {0}")]
+    Tts(String),
+    #[error("audio playback failed: {0}")]
+    Playback(String),
+    #[error("audio could not be read: {0}")]
+    Wav(#[from] hound::Error),
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Unavailable(_) => "speech.unavailable",
+            Error::MicrophoneOff => "speech.microphone_off",
+            Error::Device(_) => "speech.device_failed",
+            Error::Model(_) => "speech.model_failed",
+            Error::Engine(_) => "speech.failed",
+            Error::NoSpeech => "speech.no_speech",
+            Error::Tts(_) => "speech.tts_failed",
+            Error::Playback(_) => "speech.playback_failed",
+            Error::Wav(_) => "speech.bad_audio",
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Offline speech-to-text for spoken answers. Audio comes from a WAV file or,
+//! when `speech.microphone` is on, the microphone (`microphone` feature);
+//! voice activity detection trims it to the answer, and whisper.cpp
+//! (`whisper` feature) transcribes it on the CPU with a model from disk.
+//! [`StubTranscriber`] returns fixed text for tests and builds without it.
+//! [`tts`] reads text aloud for hands-free review.
+
+mod audio;
+mod error;
+#[cfg(feature = "microphone")]
+mod mic;
+pub mod tts;
+pub mod vad;
+#[cfg(feature = "whisper")]
+mod whisper;
+
+pub use audio::Audio;
+pub use error::{Error, Result};
+
+use utils::config::SpeechConfig;
+use vad::VadOptions;
+
+/// Sample rate whisper models expect.
+pub const SAMPLE_RATE: u32 = 16_000;
+
+/// Audio kept either side of the detected speech, so word edges survive.
+const PAD_MS: u32 = 200;
+
+/// A speech recogniser over 16 kHz mono samples.
+pub trait Transcriber: Send + Sync {
+    fn transcribe(&self, samples: &[f32]) -> Result<String>;
+}
+
+/// Transcriber returning the same text for any audio.
+#[derive(Debug, Clone)]
+pub struct StubTranscriber {
+    text: String,
+}
+
+impl StubTranscriber {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into() }
+    }
+}
+
+impl Transcriber for StubTranscriber {
+    fn transcribe(&self, _samples: &[f32]) -> Result<String> {
+        Ok(self.text.clone())
+    }
+}
+
+/// The best transcriber compiled into this build, loading `cfg.model`.
+pub fn transcriber(cfg: &SpeechConfig) -> Result<Box<dyn Transcriber>> {
+    #[cfg(feature = "whisper")]
+    {
+        Ok(Box::new(whisper::Whisper::load(cfg)?))
+    }
+
+    #[cfg(not(feature = "whisper"))]
+    {
+        let _ = cfg;
+        Err(Error::Unavailable("whisper"))
+    }
+}
+
+/// Record one answer: from the first speech until `cfg.silence_ms` of quiet,
+/// or `cfg.max_secs`. Refuses unless the user enabled `speech.microphone`.
+pub fn record(cfg: &SpeechConfig) -> Result<Audio> {
+    if !cfg.microphone {
+        return Err(Error::MicrophoneOff);
+    }
+    #[cfg(feature = "microphone")]
+    {
+        mic::record(cfg)
+    }
+
+    #[cfg(not(feature = "microphone"))]
+    {
+        Err(Error::Unavailable("microphone"))
+    }
+}
+
+/// Trim `audio` to the speech in it and transcribe that.
+pub fn transcribe(engine: &dyn Transcriber, audio: &Audio) -> Result<String> {
+    let audio = audio.resample(SAMPLE_RATE);
+    let speech = vad::trim(&audio, &VadOptions::default(), PAD_MS).ok_or(Error::NoSpeech)?;
+    let text = tidy(&engine.transcribe(&speech.samples)?);
+    tracing::info!(seconds = speech.seconds(), chars = text.len(), "🎙️ Transcribed answer");
+    if text.is_empty() {
+        return Err(Error::NoSpeech);
+    }
+    Ok(text)
+}
+
+/// Drop whisper's non-speech annotations (`[BLANK_AUDIO]`, `(coughs)`) and
+/// collapse whitespace.
+fn tidy(text: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcribes_speech_from_a_wav_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("answer.wav");
+        // 44.1 kHz, like most recorders, with the answer in the middle.
+        vad::tests::utterance(44_100, 600, 900, 600).write_wav(&path).unwrap();
+        let audio = Audio::read_wav(&path).unwrap();
+        assert_eq!(audio.rate, 44_100);
+
+        let engine = StubTranscriber::new(" [BLANK_AUDIO] Paris.  ");
+        assert_eq!(transcribe(&engine, &audio).unwrap(), "Paris.");
+
+        vad::tests::utterance(44_100, 600, 0, 600).write_wav(&path).unwrap();
+        let silent = Audio::read_wav(&path).unwrap();
+        assert!(matches!(transcribe(&engine, &silent), Err(Error::NoSpeech)));
+    }
+}
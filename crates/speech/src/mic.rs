@@ -0,0 +1,60 @@
+//! Microphone recording via `cpal`, stopped by [`Endpointer`].
+
+use crate::vad::{Endpointer, Listening, VadOptions};
+use crate::{Audio, Error, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use utils::config::SpeechConfig;
+
+pub fn record(cfg: &SpeechConfig) -> Result<Audio> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| Error::Device("no input device".into()))?;
+    let supported = device.default_input_config().map_err(|e| Error::Device(e.to_string()))?;
+    let config: StreamConfig = supported.config();
+    let (tx, rx) = mpsc::channel::<Vec<f32>>();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => open::<f32>(&device, &config, tx),
+        SampleFormat::I16 => open::<i16>(&device, &config, tx),
+        SampleFormat::U16 => open::<u16>(&device, &config, tx),
+        SampleFormat::I32 => open::<i32>(&device, &config, tx),
+        other => return Err(Error::Device(format!("unsupported sample format {other}"))),
+    }?;
+    stream.play().map_err(|e| Error::Device(e.to_string()))?;
+    tracing::info!(rate = config.sample_rate.0, "🎙️ Listening for an answer");
+
+    let rate = config.sample_rate.0;
+    let mut endpointer = Endpointer::new(rate, cfg.silence_ms, VadOptions::default());
+    let mut samples = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(cfg.max_secs.into());
+    while Instant::now() < deadline {
+        let Ok(chunk) = rx.recv_timeout(Duration::from_millis(100)) else { continue };
+        let mono = Audio::from_interleaved(&chunk, config.channels, rate).samples;
+        let state = endpointer.push(&mono);
+        samples.extend(mono);
+        if state == Listening::Done {
+            break;
+        }
+    }
+    drop(stream);
+    Ok(Audio { samples, rate })
+}
+
+fn open<T>(device: &cpal::Device, config: &StreamConfig, tx: mpsc::Sender<Vec<f32>>) -> Result<Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let _ = tx.send(data.iter().map(|s| f32::from_sample_(*s)).collect());
+            },
+            |e| tracing::warn!(error = %e, "microphone stream error"),
+            None,
+        )
+        .map_err(|e| Error::Device(e.to_string()))
+}
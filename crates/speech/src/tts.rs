@@ -0,0 +1,177 @@
+//! Text-to-speech for reading cards aloud. Engines run an offline synthesiser
+//! (espeak-ng or piper) into a WAV file; [`say`] plays the result through the
+//! system audio player, or keeps it as a numbered file in `tts.output_dir`
+//! so sessions can be checked without speakers.
+
+use crate::{Audio, Error, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tempfile::NamedTempFile;
+use utils::config::{TtsConfig, TtsEngine};
+
+/// Turns text into speech audio.
+pub trait Synthesizer: Send + Sync {
+    fn synthesize(&self, text: &str) -> Result<Audio>;
+}
+
+/// The engine `[tts]` selects.
+pub fn synthesizer(cfg: &TtsConfig) -> Box<dyn Synthesizer> {
+    match cfg.engine {
+        TtsEngine::Espeak => Box::new(Espeak { voice: cfg.voice.clone(), rate: cfg.rate }),
+        TtsEngine::Piper => Box::new(Piper { model: cfg.model.clone(), rate: cfg.rate }),
+    }
+}
+
+/// `espeak-ng` (or classic `espeak`): robotic but tiny and everywhere.
+#[derive(Debug, Clone)]
+pub struct Espeak {
+    pub voice: String,
+    pub rate: u32,
+}
+
+impl Synthesizer for Espeak {
+    fn synthesize(&self, text: &str) -> Result<Audio> {
+        let out = temp_wav().map_err(|e| Error::Tts(e.to_string()))?;
+        let rate = self.rate.to_string();
+        let run = |program| {
+            Command::new(program)
+                .args(["-v", &self.voice, "-s", &rate, "-w"])
+                .arg(out.path())
+                .args(["--", text])
+                .status()
+        };
+        let status = run("espeak-ng")
+            .or_else(|_| run("espeak"))
+            .map_err(|e| Error::Tts(format!("espeak-ng not found ({e}); install it or use tts.engine = \"piper\"")))?;
+        if !status.success() {
+            return Err(Error::Tts(format!("espeak-ng exited with {status}")));
+        }
+        Audio::read_wav(out.path())
+    }
+}
+
+/// Piper neural voices: natural-sounding, needs a downloaded `.onnx` voice.
+#[derive(Debug, Clone)]
+pub struct Piper {
+    pub model: PathBuf,
+    pub rate: u32,
+}
+
+/// Words per minute piper voices speak at with `--length_scale 1`.
+const PIPER_WPM: f32 = 170.0;
+
+impl Synthesizer for Piper {
+    fn synthesize(&self, text: &str) -> Result<Audio> {
+        if !self.model.is_file() {
+            return Err(Error::Tts(format!("piper voice {} not found", self.model.display())));
+        }
+        let out = temp_wav().map_err(|e| Error::Tts(e.to_string()))?;
+        let mut child = Command::new("piper")
+            .arg("--model")
+            .arg(&self.model)
+            .arg("--output_file")
+            .arg(out.path())
+            .args(["--length_scale", &format!("{:.2}", PIPER_WPM / self.rate as f32)])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Error::Tts(format!("piper not found ({e})")))?;
+        // Piper reads one utterance per line.
+        let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{line}").map_err(|e| Error::Tts(e.to_string()))?;
+        }
+        let status = child.wait().map_err(|e| Error::Tts(e.to_string()))?;
+        if !status.success() {
+            return Err(Error::Tts(format!("piper exited with {status}")));
+        }
+        Audio::read_wav(out.path())
+    }
+}
+
+/// Synthesiser for tests: a short tone per word, no external program.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StubSynthesizer;
+
+impl Synthesizer for StubSynthesizer {
+    fn synthesize(&self, text: &str) -> Result<Audio> {
+        let rate = crate::SAMPLE_RATE;
+        let mut samples = Vec::new();
+        for _ in text.split_whitespace() {
+            samples.extend((0..rate / 5).map(|i| 0.2 * (i as f32 / rate as f32 * 440.0 * std::f32::consts::TAU).sin()));
+            samples.extend(std::iter::repeat_n(0.0, (rate / 20) as usize));
+        }
+        Ok(Audio { samples, rate })
+    }
+}
+
+/// Speak `text`: play it, or with `output_dir` write it to the next
+/// `NNNN.wav` there and return the path.
+pub fn say(engine: &dyn Synthesizer, text: &str, output_dir: Option<&Path>) -> Result<Option<PathBuf>> {
+    let audio = engine.synthesize(text)?;
+    tracing::debug!(seconds = audio.seconds(), "🔊 Speaking");
+    match output_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir).map_err(|e| Error::Playback(e.to_string()))?;
+            let path = dir.join(format!("{:04}.wav", next_index(dir)));
+            audio.write_wav(&path)?;
+            Ok(Some(path))
+        }
+        None => {
+            let tmp = temp_wav().map_err(|e| Error::Playback(e.to_string()))?;
+            audio.write_wav(tmp.path())?;
+            play(tmp.path())?;
+            Ok(None)
+        }
+    }
+}
+
+/// One more than the highest `NNNN.wav` already in `dir`.
+fn next_index(dir: &Path) -> usize {
+    let taken = std::fs::read_dir(dir).into_iter().flatten().flatten().filter_map(|e| {
+        let name = e.file_name().into_string().ok()?;
+        name.strip_suffix(".wav")?.parse::<usize>().ok()
+    });
+    taken.max().map_or(1, |n| n + 1)
+}
+
+/// Play a WAV file through the first audio player found, waiting until done.
+fn play(path: &Path) -> Result<()> {
+    let players: &[(&str, &[&str])] =
+        if cfg!(target_os = "macos") { &[("afplay", &[])] } else { &[("paplay", &[]), ("aplay", &["-q"])] };
+    for (player, args) in players {
+        match Command::new(player).args(*args).arg(path).status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => return Err(Error::Playback(format!("{player} exited with {status}"))),
+            Err(_) => continue,
+        }
+    }
+    Err(Error::Playback("no audio player found (afplay, paplay or aplay)".into()))
+}
+
+/// A `.wav` file in the temp dir, removed on drop.
+fn temp_wav() -> std::io::Result<NamedTempFile> {
+    tempfile::Builder::new().prefix("oakley-tts-").suffix(".wav").tempfile()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_numbered_files_in_file_mode() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("spoken");
+        let first = say(&StubSynthesizer, "What is ownership?", Some(&dir)).unwrap().unwrap();
+        let second = say(&StubSynthesizer, "Paris", Some(&dir)).unwrap().unwrap();
+        assert_eq!(first, dir.join("0001.wav"));
+        assert_eq!(second, dir.join("0002.wav"));
+
+        // Three words come out longer than one, and the files read back.
+        let (long, short) = (Audio::read_wav(&first).unwrap(), Audio::read_wav(&second).unwrap());
+        assert!(long.seconds() > 2.0 * short.seconds());
+        assert_eq!(short.rate, crate::SAMPLE_RATE);
+    }
+}
@@ -0,0 +1,197 @@
+//! Energy-based voice activity detection. Frames louder than the estimated
+//! noise floor by [`VadOptions::margin_db`] count as speech; short gaps are
+//! bridged and blips dropped. [`Endpointer`] does the same on a live stream
+//! to tell when the speaker has finished.
+
+use crate::Audio;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VadOptions {
+    pub frame_ms: u32,
+    /// Loudness above the noise floor that counts as speech.
+    pub margin_db: f32,
+    /// Frames quieter than this are silence however quiet the room is.
+    pub min_db: f32,
+    /// Gaps shorter than this inside speech are bridged.
+    pub hangover_ms: u32,
+    /// Speech shorter than this (clicks, a cough) is dropped.
+    pub min_speech_ms: u32,
+}
+
+impl Default for VadOptions {
+    fn default() -> Self {
+        Self { frame_ms: 30, margin_db: 12.0, min_db: -50.0, hangover_ms: 300, min_speech_ms: 120 }
+    }
+}
+
+impl VadOptions {
+    fn frames(&self, ms: u32) -> usize {
+        (ms / self.frame_ms.max(1)).max(1) as usize
+    }
+}
+
+/// Loudness of a frame in dB relative to full scale.
+fn energy_db(frame: &[f32]) -> f32 {
+    let power = frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+    10.0 * (power + 1e-10).log10()
+}
+
+/// Sample ranges holding speech, in order.
+pub fn segments(audio: &Audio, opts: &VadOptions) -> Vec<Range<usize>> {
+    let frame = (audio.rate * opts.frame_ms / 1000).max(1) as usize;
+    let energies: Vec<f32> = audio.samples.chunks(frame).map(energy_db).collect();
+    if energies.is_empty() {
+        return Vec::new();
+    }
+    // The quietest tenth of the recording is taken as the room's noise.
+    let mut sorted = energies.clone();
+    sorted.sort_by(f32::total_cmp);
+    let noise = sorted[sorted.len() / 10];
+    let threshold = (noise + opts.margin_db).max(opts.min_db);
+
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for (i, e) in energies.iter().enumerate() {
+        if *e <= threshold {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if i - run.end < opts.frames(opts.hangover_ms) => run.end = i + 1,
+            _ => runs.push(i..i + 1),
+        }
+    }
+    runs.into_iter()
+        .filter(|r| r.len() >= opts.frames(opts.min_speech_ms))
+        .map(|r| r.start * frame..(r.end * frame).min(audio.samples.len()))
+        .collect()
+}
+
+/// The span from the first to the last speech, padded by `pad_ms` either
+/// side; `None` when nothing was said.
+pub fn trim(audio: &Audio, opts: &VadOptions, pad_ms: u32) -> Option<Audio> {
+    let found = segments(audio, opts);
+    let (first, last) = (found.first()?, found.last()?);
+    let pad = (audio.rate * pad_ms / 1000) as usize;
+    let span = first.start.saturating_sub(pad)..(last.end + pad).min(audio.samples.len());
+    Some(Audio { samples: audio.samples[span].to_vec(), rate: audio.rate })
+}
+
+/// Where a live recording stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Listening {
+    /// Nothing said yet.
+    Waiting,
+    Speaking,
+    /// Speech followed by enough silence: the answer is over.
+    Done,
+}
+
+/// Streaming end-of-answer detection for microphone input.
+#[derive(Debug, Clone)]
+pub struct Endpointer {
+    opts: VadOptions,
+    frame: usize,
+    pending: Vec<f32>,
+    noise_db: Option<f32>,
+    speech_frames: usize,
+    silent_frames: usize,
+    end_frames: usize,
+}
+
+impl Endpointer {
+    /// `silence_ms` of quiet after speech ends the answer.
+    pub fn new(rate: u32, silence_ms: u32, opts: VadOptions) -> Self {
+        Self {
+            frame: (rate * opts.frame_ms / 1000).max(1) as usize,
+            end_frames: opts.frames(silence_ms),
+            opts,
+            pending: Vec::new(),
+            noise_db: None,
+            speech_frames: 0,
+            silent_frames: 0,
+        }
+    }
+
+    /// Feed mono samples as they arrive.
+    pub fn push(&mut self, samples: &[f32]) -> Listening {
+        self.pending.extend_from_slice(samples);
+        let whole = self.pending.len() / self.frame * self.frame;
+        let frames: Vec<f32> = self.pending[..whole].chunks(self.frame).map(energy_db).collect();
+        self.pending.drain(..whole);
+        for e in frames {
+            // Follow the floor down at once and up slowly, so speech does not raise it.
+            let noise = match self.noise_db {
+                Some(n) if e < n => e,
+                Some(n) => n + (e - n) * 0.01,
+                None => e,
+            };
+            self.noise_db = Some(noise);
+            if e > (noise + self.opts.margin_db).max(self.opts.min_db) {
+                self.speech_frames += 1;
+                self.silent_frames = 0;
+            } else if self.speech_frames > 0 {
+                self.silent_frames += 1;
+            }
+        }
+        self.state()
+    }
+
+    pub fn state(&self) -> Listening {
+        if self.speech_frames < self.opts.frames(self.opts.min_speech_ms) {
+            Listening::Waiting
+        } else if self.silent_frames >= self.end_frames {
+            Listening::Done
+        } else {
+            Listening::Speaking
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Quiet hiss, `speech_ms` of a loud vowel-like tone, then hiss again.
+    pub(crate) fn utterance(rate: u32, lead_ms: u32, speech_ms: u32, tail_ms: u32) -> Audio {
+        let n = |ms: u32| (rate * ms / 1000) as usize;
+        let mut noise = 12345u32;
+        let mut hiss = move || {
+            noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (noise >> 16) as f32 / 65536.0 * 0.002 - 0.001
+        };
+        let mut samples: Vec<f32> = (0..n(lead_ms)).map(|_| hiss()).collect();
+        samples.extend((0..n(speech_ms)).map(|i| {
+            let t = i as f32 / rate as f32;
+            0.3 * (t * 220.0 * std::f32::consts::TAU).sin() + 0.1 * (t * 660.0 * std::f32::consts::TAU).sin()
+        }));
+        samples.extend((0..n(tail_ms)).map(|_| hiss()));
+        Audio { samples, rate }
+    }
+
+    #[test]
+    fn finds_speech_between_silence() {
+        let audio = utterance(16_000, 500, 800, 700);
+        let found = segments(&audio, &VadOptions::default());
+        assert_eq!(found.len(), 1, "{found:?}");
+        let (start, end) = (found[0].start as f32 / 16.0, found[0].end as f32 / 16.0);
+        assert!((450.0..=540.0).contains(&start) && (1260.0..=1350.0).contains(&end), "{start}..{end} ms");
+
+        let trimmed = trim(&audio, &VadOptions::default(), 100).unwrap();
+        assert!((0.9..1.1).contains(&trimmed.seconds()), "{}", trimmed.seconds());
+        assert!(trim(&utterance(16_000, 500, 0, 500), &VadOptions::default(), 100).is_none());
+    }
+
+    #[test]
+    fn endpointer_waits_for_silence_after_speech() {
+        let audio = utterance(48_000, 400, 600, 1200);
+        let mut ep = Endpointer::new(48_000, 900, VadOptions::default());
+        let mut states = Vec::new();
+        for chunk in audio.samples.chunks(480) {
+            let state = ep.push(chunk);
+            if states.last() != Some(&state) {
+                states.push(state);
+            }
+        }
+        assert_eq!(states, [Listening::Waiting, Listening::Speaking, Listening::Done]);
+    }
+}
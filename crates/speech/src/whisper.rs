@@ -0,0 +1,50 @@
+//! whisper.cpp transcription via `whisper-rs`, on the CPU.
+
+use crate::{Error, Result, Transcriber};
+use utils::config::SpeechConfig;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+pub struct Whisper {
+    ctx: WhisperContext,
+    language: String,
+    threads: i32,
+}
+
+impl Whisper {
+    /// Load the model once; a state per call keeps [`Transcriber`] `&self`.
+    pub fn load(cfg: &SpeechConfig) -> Result<Self> {
+        let path = cfg.model.to_str().ok_or_else(|| Error::Model(format!("bad path {}", cfg.model.display())))?;
+        if !cfg.model.is_file() {
+            return Err(Error::Model(format!("{path} not found; download a ggml model (e.g. ggml-base.en.bin)")));
+        }
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(false);
+        let ctx = WhisperContext::new_with_params(path, params).map_err(|e| Error::Model(e.to_string()))?;
+        tracing::info!(model = path, "Loaded whisper model");
+        Ok(Self { ctx, language: cfg.language.clone(), threads: cfg.threads as i32 })
+    }
+}
+
+impl Transcriber for Whisper {
+    fn transcribe(&self, samples: &[f32]) -> Result<String> {
+        let mut state = self.ctx.create_state().map_err(|e| Error::Engine(e.to_string()))?;
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_n_threads(self.threads);
+        params.set_language(Some(&self.language));
+        // Answers are short and independent: no carried-over context or console output.
+        params.set_no_context(true);
+        params.set_single_segment(true);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        state.full(params, samples).map_err(|e| Error::Engine(e.to_string()))?;
+
+        let segments = state.full_n_segments().map_err(|e| Error::Engine(e.to_string()))?;
+        let mut text = String::new();
+        for i in 0..segments {
+            text.push_str(&state.full_get_segment_text_lossy(i).map_err(|e| Error::Engine(e.to_string()))?);
+        }
+        Ok(text)
+    }
+}
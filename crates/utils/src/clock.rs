@@ -0,0 +1,73 @@
+//! Study days. A day runs from `scheduler.rollover_hour` to the same hour the
+//! next day in the user's time zone, so a session past midnight still counts
+//! towards the evening it started in.
+
+use crate::config::SchedulerConfig;
+use chrono::{DateTime, Days, Duration, LocalResult, NaiveDate, NaiveTime, TimeZone, Utc};
+pub use chrono_tz::Tz;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StudyClock {
+    pub tz: Tz,
+    /// Local hour (0–23) at which a new study day begins.
+    pub rollover_hour: u32,
+}
+
+impl StudyClock {
+    pub fn new(tz: Tz, rollover_hour: u32) -> Self {
+        Self { tz, rollover_hour: rollover_hour.min(23) }
+    }
+
+    /// The clock `[scheduler]` describes; an empty `timezone` means the system's.
+    pub fn from_config(cfg: &SchedulerConfig) -> Self {
+        // Config is validated on load, so a parse failure here means "unset".
+        let tz = cfg.timezone().ok().flatten().unwrap_or_else(system_timezone);
+        Self::new(tz, cfg.rollover_hour)
+    }
+
+    /// The study day `t` falls in.
+    pub fn day(&self, t: DateTime<Utc>) -> NaiveDate {
+        (t.with_timezone(&self.tz).naive_local() - Duration::hours(self.rollover_hour.into())).date()
+    }
+
+    /// When `day` begins.
+    pub fn start(&self, day: NaiveDate) -> DateTime<Utc> {
+        let mut local = day.and_time(NaiveTime::from_hms_opt(self.rollover_hour, 0, 0).unwrap_or_default());
+        loop {
+            match self.tz.from_local_datetime(&local) {
+                LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => return t.with_timezone(&Utc),
+                // The hour was skipped by a daylight-saving jump; begin once clocks moved on.
+                LocalResult::None => local += Duration::minutes(30),
+            }
+        }
+    }
+
+    /// When the study day after the one `t` falls in begins.
+    pub fn next_day(&self, t: DateTime<Utc>) -> DateTime<Utc> {
+        self.start(self.day(t) + Days::new(1))
+    }
+}
+
+/// The system time zone, or UTC when it cannot be determined.
+pub fn system_timezone() -> Tz {
+    iana_time_zone::get_timezone().ok().and_then(|name| name.parse().ok()).unwrap_or(Tz::UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_roll_over_at_the_configured_hour() {
+        let clock = StudyClock::new(chrono_tz::Europe::Berlin, 4);
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let date = |d| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+
+        // 02:30 local on the 11th still belongs to the 10th.
+        assert_eq!(clock.day(at("2026-03-11T02:30:00+01:00")), date("2026-03-10"));
+        assert_eq!(clock.day(at("2026-03-11T04:00:00+01:00")), date("2026-03-11"));
+        assert_eq!(clock.start(date("2026-03-11")), at("2026-03-11T04:00:00+01:00"));
+        // Summer time: the day still starts at 04:00 on the wall clock.
+        assert_eq!(clock.next_day(at("2026-03-29T12:00:00+02:00")), at("2026-03-30T04:00:00+02:00"));
+    }
+}
@@ -0,0 +1,839 @@
+//! Layered configuration shared by every Oakley crate.
+//!
+//! Values are resolved as built-in defaults ← `~/.oakley/config.toml` ←
+//! `OAKLEY_*` environment variables ← command-line overrides. Every key can be
+//! overridden through the environment by upper-casing its dotted path, e.g.
+//! `llm.temperature` → `OAKLEY_LLM_TEMPERATURE`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// How often [`ConfigLoader::watch`] checks the config file for changes.
+const RELOAD_POLL: Duration = Duration::from_secs(2);
+
+/// `$HOME/.oakley` – config, database and socket live here.
+pub fn oakley_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(".oakley")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub db_path: PathBuf,
+    pub socket_path: PathBuf,
+    /// Content-addressed store for source screenshots.
+    pub media_dir: PathBuf,
+    pub llm: LlmConfig,
+    pub http: HttpConfig,
+    pub scheduler: SchedulerConfig,
+    pub queue: QueueConfig,
+    pub reminders: ReminderConfig,
+    pub hotkeys: HotkeyConfig,
+    pub ocr: OcrConfig,
+    pub capture: CaptureConfig,
+    pub ingest: IngestConfig,
+    pub dedupe: DedupeConfig,
+    pub grading: GradingConfig,
+    pub speech: SpeechConfig,
+    pub tts: TtsConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmConfig {
+    pub api_base: String,
+    pub text_model: String,
+    pub vision_model: String,
+    pub temperature: f64,
+    pub max_output_tokens: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub enabled: bool,
+    pub port: u16,
+    /// Secret browser extensions send as `Authorization: Bearer <token>` to
+    /// `POST /ingest`, also needed for `GET /media` and `GET /stats`; empty
+    /// locks those routes.
+    pub token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerConfig {
+    /// Seconds between due-card scans.
+    pub tick_secs: u64,
+    /// Local hour (0–23) at which a new study day begins for daily limits
+    /// and statistics.
+    pub rollover_hour: u32,
+    /// IANA time zone such as `"Europe/Berlin"`; empty uses the system's.
+    pub timezone: String,
+    /// Delays between the first showings of a new card, e.g. `["1m", "10m"]`
+    /// (units `s`, `m`, `h`, `d`). Empty sends new cards straight to review.
+    pub learning_steps: Vec<String>,
+    /// Delays before a forgotten card is shown again; empty reschedules it
+    /// for the next day.
+    pub relearning_steps: Vec<String>,
+    /// Days until the first review once a card passes its last learning step.
+    pub graduating_days: u32,
+    /// Days until the first review of a new card answered `easy`.
+    pub easy_days: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    /// Cards seen for the first time per study day; 0 pauses new cards.
+    pub new_per_day: u32,
+    /// Reviews of already-learnt cards per study day.
+    pub reviews_per_day: u32,
+    pub order: QueueOrder,
+    /// Limits for a deck together with its subdecks, on top of the totals,
+    /// e.g. `[queue.decks."Lectures"] new_per_day = 5`.
+    pub decks: BTreeMap<String, DeckLimits>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeckLimits {
+    pub new_per_day: Option<u32>,
+    pub reviews_per_day: Option<u32>,
+}
+
+/// Order of due reviews; new cards always come in the order they were added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOrder {
+    /// Most overdue relative to its interval first, so short intervals that
+    /// slipped are rescued before long ones.
+    #[default]
+    Overdue,
+    /// Shuffled, in the same order for the whole study day.
+    Random,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReminderConfig {
+    /// Local-time window without reminders, `"HH:MM-HH:MM"`; empty disables.
+    pub quiet_hours: String,
+    pub min_gap_minutes: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HotkeyConfig {
+    /// Install global capture hot-keys in the daemon.
+    pub enabled: bool,
+    /// Accelerators such as `"CmdOrCtrl+Shift+Comma"`; `Cmd` means Ctrl off
+    /// macOS. An empty string leaves the action unbound.
+    pub capture_screen: String,
+    pub capture_selection: String,
+    pub capture_window: String,
+    pub capture_clipboard: String,
+    pub start_review: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OcrConfig {
+    /// Read screenshots locally and send only the text to the LLM.
+    pub enabled: bool,
+    /// Tesseract language code(s), e.g. `"eng"` or `"eng+deu"`.
+    pub language: String,
+    /// Words below this confidence (0–100) are dropped.
+    pub min_confidence: f32,
+    /// Send the image to the vision model when OCR finds no usable text or
+    /// the engine fails.
+    pub fallback_to_vision: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptureConfig {
+    /// Display for full-screen captures: `"cursor"`, `"primary"` or a display id.
+    pub monitor: MonitorChoice,
+    /// Generate a card whenever new text or an image is copied.
+    pub clipboard_watch: bool,
+    /// Minutes a screenshot is remembered for duplicate detection; 0 turns it off.
+    pub duplicate_window_mins: u32,
+    /// Most perceptual-hash bits (of 64) a screenshot may differ by from one
+    /// taken inside the window and still be held as a duplicate.
+    pub duplicate_distance: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IngestConfig {
+    /// Folders watched for new images, PDFs, web pages, Markdown and text
+    /// files. Cards are filed into a deck named after the sub-folder a file
+    /// sits in.
+    pub watch_dirs: Vec<PathBuf>,
+    /// Seconds between scans of the watched folders.
+    pub poll_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DedupeConfig {
+    /// Where card embeddings come from.
+    pub provider: EmbeddingProvider,
+    /// Embedding model for the `"api"` provider.
+    pub model: String,
+    /// Cosine similarity (0–1) from which two cards count as duplicates. The
+    /// local provider scores rewordings lower than embedding models do;
+    /// around 0.85 suits `"api"`.
+    pub threshold: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GradingConfig {
+    /// Similarity (0–1) to the expected answer from which a typed answer is
+    /// right despite typos.
+    pub fuzzy: f32,
+    /// Similarity from which an answer counts as partly right (`hard`).
+    pub partial: f32,
+    /// Accept answers whose embedding (see `[dedupe]`) is this close to the
+    /// expected one; 0 turns the check off.
+    pub semantic: f32,
+    /// Ask the text model about answers the other checks reject.
+    pub judge: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeechConfig {
+    /// Allow recording answers from the microphone. Off until the user opts in.
+    pub microphone: bool,
+    /// whisper.cpp (ggml) model file, e.g. `ggml-base.en.bin`.
+    pub model: PathBuf,
+    /// Spoken language code, or `"auto"` to detect it.
+    pub language: String,
+    /// CPU threads used for transcription.
+    pub threads: u32,
+    /// Longest answer recorded, in seconds.
+    pub max_secs: u32,
+    /// Silence that ends an answer, in milliseconds.
+    pub silence_ms: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TtsConfig {
+    pub engine: TtsEngine,
+    /// espeak-ng voice, e.g. `"en-us"`.
+    pub voice: String,
+    /// Piper voice model (`.onnx`, with its `.onnx.json` beside it).
+    pub model: PathBuf,
+    /// Speaking rate in words per minute.
+    pub rate: u32,
+    /// Write each utterance here as a numbered WAV file instead of playing
+    /// it; empty plays through the speakers.
+    pub output_dir: PathBuf,
+}
+
+/// Offline text-to-speech program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsEngine {
+    Espeak,
+    Piper,
+}
+
+/// Source of the vectors cards are compared by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingProvider {
+    /// Offline word and character-trigram hashing; no model download.
+    #[default]
+    Local,
+    /// `<llm.api_base>/embeddings`, e.g. OpenAI or a local Ollama server.
+    Api,
+}
+
+/// Which display a full-screen capture targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum MonitorChoice {
+    /// The display under the mouse pointer.
+    #[default]
+    Cursor,
+    Primary,
+    /// A display id as reported in `CaptureEvent.display`.
+    Id(u32),
+}
+
+impl std::str::FromStr for MonitorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "cursor" => Ok(MonitorChoice::Cursor),
+            "primary" => Ok(MonitorChoice::Primary),
+            id => id
+                .parse()
+                .map(MonitorChoice::Id)
+                .map_err(|_| anyhow!("expected \"cursor\", \"primary\" or a display id, got {s:?}")),
+        }
+    }
+}
+
+impl TryFrom<String> for MonitorChoice {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<MonitorChoice> for String {
+    fn from(m: MonitorChoice) -> String {
+        match m {
+            MonitorChoice::Cursor => "cursor".into(),
+            MonitorChoice::Primary => "primary".into(),
+            MonitorChoice::Id(id) => id.to_string(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let dir = oakley_dir();
+        Self {
+            db_path: dir.join("oakley.db"),
+            socket_path: dir.join("oakley.sock"),
+            media_dir: dir.join("media"),
+            llm: LlmConfig::default(),
+            http: HttpConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            queue: QueueConfig::default(),
+            reminders: ReminderConfig::default(),
+            hotkeys: HotkeyConfig::default(),
+            ocr: OcrConfig::default(),
+            capture: CaptureConfig::default(),
+            ingest: IngestConfig::default(),
+            dedupe: DedupeConfig::default(),
+            grading: GradingConfig::default(),
+            speech: SpeechConfig::default(),
+            tts: TtsConfig::default(),
+        }
+    }
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            api_base: "https://api.openai.com/v1".into(),
+            text_model: "gpt-4.1".into(),
+            vision_model: "gpt-4o-mini".into(),
+            temperature: 0.4,
+            max_output_tokens: 256,
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self { enabled: true, port: 3030, token: String::new() }
+    }
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            tick_secs: 60,
+            rollover_hour: 4,
+            timezone: String::new(),
+            learning_steps: vec!["1m".into(), "10m".into()],
+            relearning_steps: vec!["10m".into()],
+            graduating_days: 1,
+            easy_days: 4,
+        }
+    }
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { new_per_day: 20, reviews_per_day: 200, order: QueueOrder::Overdue, decks: BTreeMap::new() }
+    }
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self { quiet_hours: "22:00-08:00".into(), min_gap_minutes: 60 }
+    }
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            capture_screen: "CmdOrCtrl+Shift+Comma".into(),
+            capture_selection: "CmdOrCtrl+Shift+Period".into(),
+            capture_window: String::new(),
+            capture_clipboard: String::new(),
+            start_review: String::new(),
+        }
+    }
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            monitor: MonitorChoice::Cursor,
+            clipboard_watch: false,
+            duplicate_window_mins: 10,
+            duplicate_distance: 8,
+        }
+    }
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        Self { watch_dirs: Vec::new(), poll_secs: 30 }
+    }
+}
+
+impl Default for DedupeConfig {
+    fn default() -> Self {
+        Self { provider: EmbeddingProvider::Local, model: "text-embedding-3-small".into(), threshold: 0.6 }
+    }
+}
+
+impl Default for GradingConfig {
+    fn default() -> Self {
+        Self { fuzzy: 0.85, partial: 0.6, semantic: 0.0, judge: false }
+    }
+}
+
+impl Default for SpeechConfig {
+    fn default() -> Self {
+        Self {
+            microphone: false,
+            model: oakley_dir().join("models").join("ggml-base.en.bin"),
+            language: "en".into(),
+            threads: 4,
+            max_secs: 15,
+            silence_ms: 900,
+        }
+    }
+}
+
+impl Default for TtsConfig {
+    fn default() -> Self {
+        Self {
+            engine: TtsEngine::Espeak,
+            voice: "en".into(),
+            model: oakley_dir().join("models").join("en_US-lessac-medium.onnx"),
+            rate: 170,
+            output_dir: PathBuf::new(),
+        }
+    }
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self { enabled: false, language: "eng".into(), min_confidence: 60.0, fallback_to_vision: false }
+    }
+}
+
+impl SchedulerConfig {
+    /// Parsed `timezone`, `None` for the system's.
+    pub fn timezone(&self) -> Result<Option<chrono_tz::Tz>> {
+        match self.timezone.trim() {
+            "" => Ok(None),
+            name => name.parse().map(Some).map_err(|_| anyhow!("unknown time zone {name:?}")),
+        }
+    }
+
+    /// Parsed `learning_steps`.
+    pub fn learning_steps(&self) -> Result<Vec<chrono::Duration>> {
+        self.learning_steps.iter().map(|s| parse_step(s)).collect()
+    }
+
+    /// Parsed `relearning_steps`.
+    pub fn relearning_steps(&self) -> Result<Vec<chrono::Duration>> {
+        self.relearning_steps.iter().map(|s| parse_step(s)).collect()
+    }
+}
+
+/// A learning step such as `"90s"`, `"10m"`, `"1h"` or `"2d"`.
+fn parse_step(raw: &str) -> Result<chrono::Duration> {
+    let s = raw.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (n, unit) = s.split_at(split);
+    let n: i64 = n.parse().map_err(|_| anyhow!("expected a step such as \"10m\", got {raw:?}"))?;
+    let step = match unit.trim() {
+        "s" => chrono::Duration::seconds(n),
+        "m" => chrono::Duration::minutes(n),
+        "h" => chrono::Duration::hours(n),
+        "d" => chrono::Duration::days(n),
+        _ => bail!("unknown unit in step {raw:?}; use s, m, h or d"),
+    };
+    if n == 0 || step > chrono::Duration::days(365) {
+        bail!("step {raw:?} must be between 1s and 365d");
+    }
+    Ok(step)
+}
+
+impl ReminderConfig {
+    /// Parsed quiet-hours window, `None` when disabled.
+    pub fn quiet_hours(&self) -> Result<Option<(NaiveTime, NaiveTime)>> {
+        let s = self.quiet_hours.trim();
+        if s.is_empty() {
+            return Ok(None);
+        }
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| anyhow!("expected \"HH:MM-HH:MM\", got {s:?}"))?;
+        let parse = |t: &str| {
+            NaiveTime::parse_from_str(t.trim(), "%H:%M")
+                .with_context(|| format!("{:?} is not a valid HH:MM time", t.trim()))
+        };
+        Ok(Some((parse(start)?, parse(end)?)))
+    }
+}
+
+impl Config {
+    /// Every settable dotted key, e.g. `llm.temperature`.
+    pub fn keys() -> Vec<String> {
+        fn walk(prefix: &str, v: &toml::Value, out: &mut Vec<String>) {
+            match v {
+                toml::Value::Table(t) => {
+                    for (k, v) in t {
+                        let key = if prefix.is_empty() { k.clone() } else { format!("{prefix}.{k}") };
+                        walk(&key, v, out);
+                    }
+                }
+                _ => out.push(prefix.to_string()),
+            }
+        }
+        let mut out = Vec::new();
+        walk("", &toml::Value::try_from(Config::default()).expect("config serializes"), &mut out);
+        out
+    }
+
+    /// Set one dotted key from its string form, as used by env vars and `--set`.
+    pub fn set(&mut self, key: &str, raw: &str) -> Result<()> {
+        let mut root = toml::Value::try_from(&*self)?;
+        let slot = key
+            .split('.')
+            .try_fold(&mut root, |v, part| v.get_mut(part))
+            .filter(|v| !v.is_table())
+            .ok_or_else(|| anyhow!("unknown config key `{key}` (known keys: {})", Self::keys().join(", ")))?;
+        *slot = parse_like(slot, raw).with_context(|| format!("invalid value {raw:?} for `{key}`"))?;
+        *self = root
+            .try_into()
+            .with_context(|| format!("invalid value {raw:?} for `{key}`"))?;
+        Ok(())
+    }
+
+    /// Check value ranges; reports every problem at once.
+    pub fn validate(&self) -> Result<()> {
+        let mut errs = Vec::new();
+        if !(0.0..=2.0).contains(&self.llm.temperature) {
+            errs.push(format!("llm.temperature must be between 0.0 and 2.0 (got {})", self.llm.temperature));
+        }
+        if self.llm.max_output_tokens == 0 {
+            errs.push("llm.max_output_tokens must be at least 1".to_string());
+        }
+        for (key, model) in [("llm.text_model", &self.llm.text_model), ("llm.vision_model", &self.llm.vision_model)] {
+            if model.trim().is_empty() {
+                errs.push(format!("{key} must not be empty"));
+            }
+        }
+        if self.http.port == 0 {
+            errs.push("http.port must be between 1 and 65535".to_string());
+        }
+        if self.scheduler.tick_secs == 0 {
+            errs.push("scheduler.tick_secs must be at least 1".to_string());
+        }
+        if self.scheduler.rollover_hour > 23 {
+            errs.push(format!("scheduler.rollover_hour must be between 0 and 23 (got {})", self.scheduler.rollover_hour));
+        }
+        if let Err(e) = self.scheduler.timezone() {
+            errs.push(format!("scheduler.timezone: {e:#}"));
+        }
+        if let Err(e) = self.scheduler.learning_steps() {
+            errs.push(format!("scheduler.learning_steps: {e:#}"));
+        }
+        if let Err(e) = self.scheduler.relearning_steps() {
+            errs.push(format!("scheduler.relearning_steps: {e:#}"));
+        }
+        if self.scheduler.graduating_days == 0 || self.scheduler.easy_days == 0 {
+            errs.push("scheduler.graduating_days and scheduler.easy_days must be at least 1".to_string());
+        }
+        if let Err(e) = self.reminders.quiet_hours() {
+            errs.push(format!("reminders.quiet_hours: {e:#}"));
+        }
+        if !(0.0..=100.0).contains(&self.ocr.min_confidence) {
+            errs.push(format!("ocr.min_confidence must be between 0 and 100 (got {})", self.ocr.min_confidence));
+        }
+        if self.ocr.language.trim().is_empty() {
+            errs.push("ocr.language must not be empty".to_string());
+        }
+        if self.capture.duplicate_distance > 64 {
+            errs.push(format!("capture.duplicate_distance must be at most 64 (got {})", self.capture.duplicate_distance));
+        }
+        if !(0.0..=1.0).contains(&self.dedupe.threshold) {
+            errs.push(format!("dedupe.threshold must be between 0 and 1 (got {})", self.dedupe.threshold));
+        }
+        let g = &self.grading;
+        if !(0.0..=1.0).contains(&g.partial) || !(g.partial..=1.0).contains(&g.fuzzy) {
+            errs.push(format!("grading: need 0 <= partial <= fuzzy <= 1 (got {} and {})", g.partial, g.fuzzy));
+        }
+        if !(0.0..=1.0).contains(&g.semantic) {
+            errs.push(format!("grading.semantic must be between 0 and 1 (got {})", g.semantic));
+        }
+        if self.speech.threads == 0 || self.speech.max_secs == 0 {
+            errs.push("speech.threads and speech.max_secs must be at least 1".to_string());
+        }
+        if !(80..=450).contains(&self.tts.rate) {
+            errs.push(format!("tts.rate must be between 80 and 450 words per minute (got {})", self.tts.rate));
+        }
+        if self.ingest.poll_secs == 0 {
+            errs.push("ingest.poll_secs must be at least 1".to_string());
+        }
+        if !errs.is_empty() {
+            bail!("invalid configuration:\n  - {}", errs.join("\n  - "));
+        }
+        Ok(())
+    }
+
+    /// Take the hot-reloadable settings from `new`, keeping those that need a
+    /// restart (paths, HTTP listener, hot-key installation) from `self`. The
+    /// HTTP token is checked per request, so it reloads.
+    pub fn reload_safe(&self, new: Config) -> Config {
+        let restart_only = [
+            ("db_path", self.db_path != new.db_path),
+            ("socket_path", self.socket_path != new.socket_path),
+            ("media_dir", self.media_dir != new.media_dir),
+            ("http.enabled", self.http.enabled != new.http.enabled),
+            ("http.port", self.http.port != new.http.port),
+            ("hotkeys.enabled", self.hotkeys.enabled != new.hotkeys.enabled),
+        ];
+        for (key, changed) in restart_only {
+            if changed {
+                warn!("config: change to `{key}` takes effect after a restart");
+            }
+        }
+        Config {
+            db_path: self.db_path.clone(),
+            socket_path: self.socket_path.clone(),
+            media_dir: self.media_dir.clone(),
+            http: HttpConfig { token: new.http.token.clone(), ..self.http.clone() },
+            hotkeys: HotkeyConfig { enabled: self.hotkeys.enabled, ..new.hotkeys },
+            ..new
+        }
+    }
+
+    fn expand_paths(&mut self) {
+        self.db_path = expand_home(&self.db_path);
+        self.socket_path = expand_home(&self.socket_path);
+        self.media_dir = expand_home(&self.media_dir);
+        for dir in &mut self.ingest.watch_dirs {
+            *dir = expand_home(dir);
+        }
+        self.speech.model = expand_home(&self.speech.model);
+        self.tts.model = expand_home(&self.tts.model);
+        self.tts.output_dir = expand_home(&self.tts.output_dir);
+    }
+}
+
+/// Resolves the layered configuration and optionally watches it for edits.
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    path: PathBuf,
+    overrides: Vec<(String, String)>,
+}
+
+impl ConfigLoader {
+    /// `path` defaults to `~/.oakley/config.toml` (or `$OAKLEY_CONFIG`).
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let path = path
+            .or_else(|| std::env::var_os("OAKLEY_CONFIG").map(PathBuf::from))
+            .unwrap_or_else(|| oakley_dir().join("config.toml"));
+        Self { path: expand_home(&path), overrides: Vec::new() }
+    }
+
+    /// Add a command-line override; these win over file and environment.
+    pub fn with_override(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Parse a `key=value` override as given to `--set`.
+    pub fn with_override_arg(self, arg: &str) -> Result<Self> {
+        let (k, v) = arg
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected key=value, got {arg:?}"))?;
+        Ok(self.with_override(k.trim(), v.trim()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Resolve all layers and validate the result.
+    pub fn load(&self) -> Result<Config> {
+        let mut cfg = if self.path.exists() {
+            let text = std::fs::read_to_string(&self.path)
+                .with_context(|| format!("failed to read {}", self.path.display()))?;
+            toml::from_str::<Config>(&text)
+                .with_context(|| format!("failed to parse {}", self.path.display()))?
+        } else {
+            Config::default()
+        };
+
+        for key in Config::keys() {
+            let var = format!("OAKLEY_{}", key.replace('.', "_").to_uppercase());
+            if let Ok(raw) = std::env::var(&var) {
+                cfg.set(&key, &raw).with_context(|| format!("from environment variable {var}"))?;
+            }
+        }
+        for (key, raw) in &self.overrides {
+            cfg.set(key, raw).context("from command-line override")?;
+        }
+
+        cfg.expand_paths();
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// Poll the config file and publish hot-reloadable changes. Invalid edits
+    /// are logged and ignored so a typo never takes the running daemon down.
+    pub fn watch(&self, initial: Config) -> watch::Receiver<Config> {
+        let (tx, rx) = watch::channel(initial);
+        let loader = self.clone();
+        tokio::spawn(async move {
+            let mut last_modified = modified(&loader.path);
+            while !tx.is_closed() {
+                tokio::time::sleep(RELOAD_POLL).await;
+                let m = modified(&loader.path);
+                if m == last_modified {
+                    continue;
+                }
+                last_modified = m;
+                match loader.load() {
+                    Ok(new) => {
+                        let merged = tx.borrow().reload_safe(new);
+                        tx.send_if_modified(|cur| {
+                            let changed = *cur != merged;
+                            if changed {
+                                info!(path = %loader.path.display(), "🔧 Configuration reloaded");
+                                *cur = merged;
+                            }
+                            changed
+                        });
+                    }
+                    Err(e) => error!("config reload failed, keeping previous settings: {e:#}"),
+                }
+            }
+        });
+        rx
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default().join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Parse `raw` into the same TOML type as `current`.
+fn parse_like(current: &toml::Value, raw: &str) -> Result<toml::Value> {
+    use toml::Value;
+    Ok(match current {
+        Value::String(_) => Value::String(raw.to_string()),
+        Value::Integer(_) => Value::Integer(raw.trim().parse().context("expected an integer")?),
+        Value::Float(_) => Value::Float(raw.trim().parse().context("expected a number")?),
+        Value::Boolean(_) => Value::Boolean(match raw.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => true,
+            "false" | "0" | "no" | "off" => false,
+            _ => bail!("expected true or false"),
+        }),
+        Value::Array(_) => Value::Array(
+            raw.split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| Value::String(s.to_string()))
+                .collect(),
+        ),
+        other => bail!("cannot set a {} from a string", other.type_str()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_file_then_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let file = "[llm]\ntemperature = 0.9\n\n[http]\nport = 4000\n\n[queue.decks.\"Lectures::Biology\"]\nnew_per_day = 5\n";
+        std::fs::write(&path, file).unwrap();
+
+        let cfg = ConfigLoader::new(Some(path))
+            .with_override("http.port", "5000")
+            .with_override("ingest.watch_dirs", "/srv/slides, ~/Screenshots")
+            .load()
+            .unwrap();
+        assert_eq!(cfg.llm.temperature, 0.9);
+        assert_eq!(cfg.http.port, 5000);
+        assert_eq!(cfg.ingest.watch_dirs[0], PathBuf::from("/srv/slides"));
+        assert!(!cfg.ingest.watch_dirs[1].starts_with("~"));
+        assert_eq!(cfg.llm.text_model, LlmConfig::default().text_model);
+        assert_eq!(cfg.queue.decks["Lectures::Biology"], DeckLimits { new_per_day: Some(5), reviews_per_day: None });
+    }
+
+    #[test]
+    fn rejects_bad_values_with_key_names() {
+        let mut cfg = Config::default();
+        let err = cfg.set("http.port", "70000").unwrap_err();
+        assert!(format!("{err:#}").contains("http.port"));
+        assert!(cfg.set("llm.temprature", "1").is_err());
+        assert!(cfg.set("capture.monitor", "left").is_err());
+        cfg.set("capture.monitor", "2").unwrap();
+        assert_eq!(cfg.capture.monitor, MonitorChoice::Id(2));
+
+        cfg.set("llm.temperature", "3.5").unwrap();
+        cfg.set("reminders.quiet_hours", "25:00-08:00").unwrap();
+        cfg.set("scheduler.timezone", "Mars/Olympus_Mons").unwrap();
+        cfg.set("scheduler.relearning_steps", "5m, 1h").unwrap();
+        assert_eq!(cfg.scheduler.relearning_steps().unwrap(), [chrono::Duration::minutes(5), chrono::Duration::hours(1)]);
+        cfg.set("scheduler.learning_steps", "1m,10 minutes").unwrap();
+        let msg = format!("{:#}", cfg.validate().unwrap_err());
+        assert!(msg.contains("llm.temperature") && msg.contains("reminders.quiet_hours"));
+        assert!(msg.contains("scheduler.timezone") && msg.contains("scheduler.learning_steps"));
+    }
+
+    #[test]
+    fn reload_keeps_restart_only_settings() {
+        let old = Config::default();
+        let mut new = Config::default();
+        new.http.port = 9999;
+        new.http.token = "s3cret".into();
+        new.llm.temperature = 0.1;
+        let merged = old.reload_safe(new);
+        assert_eq!(merged.http.port, 3030);
+        assert_eq!(merged.http.token, "s3cret");
+        assert_eq!(merged.llm.temperature, 0.1);
+    }
+}
@@ -0,0 +1,65 @@
+//! Shared error vocabulary. Each crate defines its own error enum and maps
+//! every variant to a stable, dotted code (`llm.rate_limited`, `db.locked`, …)
+//! via [`Coded`]. [`ErrorPayload`] is the wire form sent over IPC, HTTP and
+//! Tauri so front-ends can match on `code` instead of parsing messages.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Code used when an error carries no more specific classification.
+pub const INTERNAL: &str = "internal";
+
+/// Implemented by every crate-level error enum.
+pub trait Coded: std::error::Error {
+    /// Stable machine-readable code. Never change an existing code.
+    fn code(&self) -> &'static str;
+
+    /// Whether repeating the same operation later may succeed.
+    fn retryable(&self) -> bool {
+        false
+    }
+
+    /// Server-provided hint for when to retry, if any.
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
+
+    fn payload(&self) -> ErrorPayload {
+        ErrorPayload {
+            code: self.code().to_string(),
+            message: self.to_string(),
+            retryable: self.retryable(),
+            retry_after_secs: self.retry_after().map(|d| d.as_secs()),
+        }
+    }
+}
+
+/// Serializable error shared by IPC responses, HTTP bodies and Tauri commands.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorPayload {
+    pub code: String,
+    pub message: String,
+    #[serde(default)]
+    pub retryable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl ErrorPayload {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { code: code.into(), message: message.into(), retryable: false, retry_after_secs: None }
+    }
+
+    /// Fallback for errors without a typed classification.
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(INTERNAL, message)
+    }
+}
+
+impl std::fmt::Display for ErrorPayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for ErrorPayload {}
@@ -0,0 +1,213 @@
+//! HTML → plain text for clipboard and web captures. Not a full parser: it
+//! keeps the readable text and the block structure a card prompt cares about.
+
+/// Elements whose content is never shown.
+const HIDDEN: &[&str] = &["script", "style", "head", "title", "noscript", "template", "svg"];
+
+/// Elements that start a new line.
+const BLOCKS: &[&str] = &[
+    "p", "div", "section", "article", "header", "footer", "aside", "nav", "main", "blockquote", "pre", "table",
+    "tr", "ul", "ol", "dl", "dt", "dd", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "figure", "figcaption",
+];
+
+/// Convert an HTML fragment or document to clean text: tags dropped,
+/// entities decoded, whitespace collapsed, paragraphs separated by one blank
+/// line and list items prefixed with `- `.
+pub fn to_text(html: &str) -> String {
+    let mut out = String::new();
+    let mut hidden: Option<String> = None;
+    let mut pre = 0usize;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            if hidden.is_none() {
+                push_text(&mut out, &decode_entities(rest), pre > 0);
+            }
+            break;
+        };
+        if hidden.is_none() {
+            push_text(&mut out, &decode_entities(&rest[..lt]), pre > 0);
+        }
+        rest = &rest[lt..];
+
+        if let Some(body) = rest.strip_prefix("<!--") {
+            rest = body.find("-->").map_or("", |end| &body[end + 3..]);
+            continue;
+        }
+        let starts_tag = rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        let Some(gt) = rest.find('>').filter(|_| starts_tag) else {
+            // A stray `<` in text.
+            if hidden.is_none() {
+                push_text(&mut out, "<", pre > 0);
+            }
+            rest = &rest[1..];
+            continue;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if let Some(open) = &hidden {
+            if closing && *open == name {
+                hidden = None;
+            }
+            continue;
+        }
+        if HIDDEN.contains(&name.as_str()) {
+            if !closing && !tag.ends_with('/') {
+                hidden = Some(name);
+            }
+            continue;
+        }
+        match name.as_str() {
+            "br" => newline(&mut out),
+            "li" if !closing => {
+                newline(&mut out);
+                out.push_str("- ");
+            }
+            "pre" => {
+                pre = if closing { pre.saturating_sub(1) } else { pre + 1 };
+                paragraph(&mut out);
+            }
+            "td" | "th" if !closing && !out.ends_with('\n') && !out.is_empty() => out.push('\t'),
+            n if BLOCKS.contains(&n) => paragraph(&mut out),
+            _ => {}
+        }
+    }
+    tidy(&out)
+}
+
+/// Append text, collapsing whitespace runs to one space outside `<pre>`.
+fn push_text(out: &mut String, text: &str, pre: bool) {
+    if pre {
+        out.push_str(text);
+        return;
+    }
+    for (i, word) in text.split_whitespace().enumerate() {
+        let glued = i == 0 && !text.starts_with(char::is_whitespace);
+        if !glued && !out.is_empty() && !out.ends_with([' ', '\n', '\t']) && !out.ends_with("- ") {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    if text.ends_with(char::is_whitespace) && !text.trim().is_empty() {
+        out.push(' ');
+    }
+}
+
+fn newline(out: &mut String) {
+    trim_end_spaces(out);
+    out.push('\n');
+}
+
+fn paragraph(out: &mut String) {
+    trim_end_spaces(out);
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+}
+
+fn trim_end_spaces(out: &mut String) {
+    out.truncate(out.trim_end_matches([' ', '\t']).len());
+}
+
+/// Trim every line and allow at most one blank line in a row.
+fn tidy(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank = true;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            if !blank {
+                out.push('\n');
+            }
+            blank = true;
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+        blank = false;
+    }
+    out.trim_end().to_string()
+}
+
+/// Decode named entities common in copied HTML and all numeric ones.
+pub fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..].find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..=end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" | "#39" => Some('\''),
+                "nbsp" => Some(' '),
+                "ndash" => Some('–'),
+                "mdash" => Some('—'),
+                "hellip" => Some('…'),
+                "lsquo" => Some('‘'),
+                "rsquo" => Some('’'),
+                "ldquo" => Some('“'),
+                "rdquo" => Some('”'),
+                "copy" => Some('©'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            }?;
+            Some((c, end + 2))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_copied_html_to_clean_text() {
+        let html = r#"<html><head><style>p { color: red }</style></head><body>
+            <h1>Borrowing</h1>
+            <p>A <b>reference</b> lets you use a value
+               without taking <a href="x">ownership</a>.</p>
+            <!-- comment -->
+            <ul><li>one &amp; only</li><li>&lt;mut&gt;&nbsp;ref</li></ul>
+            <script>alert("no")</script>
+            <pre>let r = &amp;s;
+let n = 1;</pre>
+            <p>x&#8594;y &copy; &unknown; 5 < 6</p>
+        </body></html>"#;
+        assert_eq!(
+            to_text(html),
+            "Borrowing\n\nA reference lets you use a value without taking ownership.\n\n- one & only\n- <mut> ref\n\n\
+             let r = &s;\nlet n = 1;\n\nx→y © &unknown; 5 < 6"
+        );
+        assert_eq!(to_text("plain <i>text</i>, no blocks"), "plain text, no blocks");
+    }
+}
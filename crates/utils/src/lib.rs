@@ -1,3 +1,9 @@
+pub mod clock;
+pub mod config;
+pub mod error;
+pub mod html;
+pub mod source;
+
 pub mod log {
     use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
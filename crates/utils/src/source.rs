@@ -0,0 +1,35 @@
+//! Provenance of a capture – the application, window and page it came from –
+//! stored with the card so review can lead back to the original context.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    /// Frontmost application, e.g. `"Firefox"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_title: Option<String>,
+    /// Page address when the application is a browser that reveals it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub captured_at: DateTime<Utc>,
+}
+
+impl Source {
+    /// A source known only by its time.
+    pub fn now() -> Self {
+        Self { app: None, window_title: None, url: None, captured_at: Utc::now() }
+    }
+
+    /// One line for review screens, e.g. `"Firefox — The Rust Book"`.
+    pub fn label(&self) -> Option<String> {
+        match (self.app.as_deref(), self.window_title.as_deref()) {
+            (Some(app), Some(title)) if !title.is_empty() && title != app => Some(format!("{app} — {title}")),
+            (Some(app), _) => Some(app.to_string()),
+            (None, Some(title)) if !title.is_empty() => Some(title.to_string()),
+            _ => None,
+        }
+    }
+}
@@ -1,77 +1,98 @@
-//! Tauri desktop shell for Oakley SRS.
+//! Tauri desktop shell for Oakley SRS. A thin client of the Oakley daemon: it
+//! owns the window and global shortcuts and forwards everything else over IPC.
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use tauri::{Manager, GlobalShortcutManager};
-use anyhow::Result;
+use tauri::{GlobalShortcutManager, Manager};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 // internal crates
-use llm::{gen_card, gen_card_from_image};
-use scheduler::{Scheduler, ReviewOutcome};
-use data::{DbPool, insert_card, fetch_all_cards};
-use capture::CaptureEvent;
+use capture::hotkey::{Action, Bindings, Platform};
+use data::{CardJson, DuplicatePair, Job, JobStatus, Stats};
+use ipc::{Event, Request, Response};
+use scheduler::{Grade, Verdict};
 use tracing::{info, error, warn};
 use get_selected_text::get_selected_text;
+use utils::config::{Config, ConfigLoader};
+use utils::error::{Coded, ErrorPayload};
 
-#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
-pub struct CardJson {
-    pub id: i64,
-    pub front: String,
-    pub back: String,
-    pub tags: Vec<String>,
+/// Where the daemon we talk to is listening.
+struct DaemonLink {
+    socket: PathBuf,
+}
+
+/// Commands return the daemon's typed error so the UI can offer a recovery action.
+type CmdResult<T> = Result<T, ErrorPayload>;
+
+async fn call(socket: &Path, req: Request) -> CmdResult<Response> {
+    ipc::request(socket, &req).await.map_err(|e| match e.downcast::<ErrorPayload>() {
+        Ok(payload) => payload,
+        Err(e) => ErrorPayload::internal(e.to_string()),
+    })
+}
+
+fn unexpected(resp: Response) -> ErrorPayload {
+    ErrorPayload::internal(format!("unexpected daemon response: {resp:?}"))
+}
+
+/// `None` when generation was queued for retry (a `job_failed` event follows)
+/// or a screenshot was held as a duplicate (`duplicate_capture` follows).
+fn expect_card(resp: Response) -> CmdResult<Option<CardJson>> {
+    match resp {
+        Response::Card { card, .. } => Ok(Some(card)),
+        Response::Queued { job_id, .. } => {
+            info!(job_id, "Card generation queued for retry");
+            Ok(None)
+        }
+        Response::Duplicate { job_id, duplicate_of, .. } => {
+            info!(job_id, duplicate_of, "Screenshot held as a duplicate");
+            Ok(None)
+        }
+        other => Err(unexpected(other)),
+    }
+}
+
+fn expect_cards(resp: Response) -> CmdResult<Vec<CardJson>> {
+    match resp {
+        Response::Cards { cards } => Ok(cards),
+        other => Err(unexpected(other)),
+    }
 }
 
 #[tauri::command]
-fn accept_card(card: CardJson) -> Result<(), String> {
+fn accept_card(card: CardJson) -> CmdResult<()> {
     // TODO: send acknowledgement back to core process via IPC
     println!("Card accepted: {:?}", card);
     Ok(())
 }
 
 #[tauri::command]
-fn discard_card(card_id: i64) -> Result<(), String> {
+fn discard_card(card_id: i64) -> CmdResult<()> {
     println!("Card discarded: {}", card_id);
     Ok(())
 }
 
 #[tauri::command]
-async fn generate_card(app: tauri::AppHandle, db: tauri::State<'_, DbPool>, text: String) -> Result<CardJson, String> {
-    // Call OpenAI via llm crate
-    let fields = gen_card(&text).await.map_err(|e| e.to_string())?;
-
-    let db_card = data::CardJson {
-        id: 0,
-        front: fields.front.clone(),
-        back: fields.back.clone(),
-        tags: fields.tags.clone(),
-    };
-
-    let id = insert_card(&db, &db_card, None).map_err(|e| e.to_string())?;
-
-    let card_json = CardJson {
-        id,
-        front: fields.front,
-        back: fields.back,
-        tags: fields.tags,
-    };
-
-    // Notify UI
-    let _ = app.emit_all("card_created", &card_json);
-
-    Ok(card_json)
+async fn generate_card(daemon: tauri::State<'_, DaemonLink>, text: String) -> CmdResult<Option<CardJson>> {
+    // The daemon emits card_created to every subscriber, including this UI.
+    expect_card(call(&daemon.socket, Request::GenerateFromText { text, source: None }).await?)
 }
 
 #[tauri::command]
-async fn create_card_from_selection(app: tauri::AppHandle, db: tauri::State<'_, DbPool>) -> Result<(), String> {
-    create_card_from_selection_impl(app, &db).await
+async fn create_card_from_selection(daemon: tauri::State<'_, DaemonLink>) -> CmdResult<()> {
+    create_card_from_selection_impl(&daemon.socket).await
 }
 
 /// Core implementation used by both the Tauri command and the global-shortcut handler.
-async fn create_card_from_selection_impl(app: tauri::AppHandle, db: &DbPool) -> Result<(), String> {
+async fn create_card_from_selection_impl(socket: &Path) -> CmdResult<()> {
     info!("Attempting to get selected text...");
 
+    // Probe the frontmost window before anything else can take focus.
+    let source = tauri::async_runtime::spawn_blocking(capture::source::active).await.ok();
+
     // Retrieve selected text first to avoid holding a non-`Send` error across an await.
     let selected_text = get_selected_text()
-        .map_err(|_| "Failed to get selected text".to_string())?;
+        .map_err(|_| ErrorPayload::new("capture.selection_failed", "Failed to get selected text"))?;
 
     if selected_text.trim().is_empty() {
         warn!("No text selected or retrieved.");
@@ -79,126 +100,290 @@ async fn create_card_from_selection_impl(app: tauri::AppHandle, db: &DbPool) ->
     }
 
     info!(length = selected_text.len(), "Got selected text, generating card.");
-    let _ = app.emit_all("hotkey", ()); // Show spinner on UI
+    if let Some(card) = expect_card(call(socket, Request::GenerateFromText { text: selected_text, source }).await?)? {
+        info!(id = card.id, "🧠 Card saved from selection");
+    }
+    Ok(())
+}
 
-    // Generate card content
-    let fields = gen_card(&selected_text)
-        .await
-        .map_err(|e| format!("LLM Error: {}", e))?;
+fn show_review(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = app.emit_all("review_requested", ());
+}
 
-    // Persist to DB
-    let db_card = data::CardJson {
-        id: 0,
-        front: fields.front.clone(),
-        back: fields.back.clone(),
-        tags: fields.tags.clone(),
-    };
+/// Replace the registered global shortcuts with `bindings`. Accelerators
+/// another application already holds are reported; the rest stay bound.
+fn bind_hotkeys(app: &tauri::AppHandle, bindings: &Bindings, socket: &Path) -> CmdResult<()> {
+    let mut manager = app.global_shortcut_manager();
+    manager.unregister_all().map_err(|e| ErrorPayload::internal(e.to_string()))?;
+
+    let mut taken = Vec::new();
+    for (accel, action) in bindings.iter() {
+        let (handle, socket) = (app.clone(), socket.to_path_buf());
+        match manager.register(&accel.to_string(), move || on_hotkey(&handle, &socket, action)) {
+            Ok(()) => info!("🎯 Hot-key {accel} → {action}"),
+            Err(e) => taken.push(format!("hotkeys.{action}: {accel} is taken by another application ({e})")),
+        }
+    }
+    if taken.is_empty() {
+        Ok(())
+    } else {
+        Err(capture::Error::Hotkey(taken.join("\n")).payload())
+    }
+}
 
-    let id = insert_card(db, &db_card, None).map_err(|e| format!("DB Error: {}", e))?;
+fn on_hotkey(app: &tauri::AppHandle, socket: &Path, action: Action) {
+    info!("Hot-key {action} triggered");
+    if action == Action::StartReview {
+        return show_review(app);
+    }
+    let (app, socket) = (app.clone(), socket.to_path_buf());
+    tauri::async_runtime::spawn(async move {
+        let result = match action {
+            Action::CaptureScreen => call(&socket, Request::CaptureScreen).await.map(drop),
+            Action::CaptureWindow => call(&socket, Request::CaptureWindow).await.map(drop),
+            Action::CaptureSelection => create_card_from_selection_impl(&socket).await,
+            // The daemon reads HTML and images too, not just plain text.
+            Action::CaptureClipboard => call(&socket, Request::CaptureClipboard).await.map(drop),
+            Action::StartReview => Ok(()),
+        };
+        if let Err(e) = result {
+            error!(error = %e, "Hot-key {action} failed");
+            let _ = app.emit_all("error", &e);
+        }
+    });
+}
 
-    info!(id, "🧠 Card saved from selection");
+/// Re-register shortcuts whenever `[hotkeys]` changes in the config file.
+/// An invalid edit is reported and the previous shortcuts stay active.
+async fn rebind_on_change(app: tauri::AppHandle, loader: ConfigLoader, cfg: Config, socket: PathBuf) {
+    let mut config = loader.watch(cfg);
+    let mut current = config.borrow().hotkeys.clone();
+    while config.changed().await.is_ok() {
+        let next = config.borrow().hotkeys.clone();
+        if next == current {
+            continue;
+        }
+        let rebound = Bindings::from_config(&next, Platform::current())
+            .map_err(|e| e.payload())
+            .and_then(|b| bind_hotkeys(&app, &b, &socket));
+        if let Err(e) = rebound {
+            error!(error = %e, "Could not rebind hot-keys");
+            let _ = app.emit_all("error", &e);
+        }
+        current = next;
+    }
+}
 
-    let card_json = CardJson {
-        id,
-        front: fields.front,
-        back: fields.back,
-        tags: fields.tags,
-    };
+#[tauri::command]
+async fn list_cards(daemon: tauri::State<'_, DaemonLink>) -> CmdResult<Vec<CardJson>> {
+    expect_cards(call(&daemon.socket, Request::ListCards).await?)
+}
 
-    let _ = app.emit_all("card_created", &card_json);
+#[tauri::command]
+async fn list_due_cards(daemon: tauri::State<'_, DaemonLink>) -> CmdResult<Vec<CardJson>> {
+    expect_cards(call(&daemon.socket, Request::DueCards).await?)
+}
 
+#[tauri::command]
+async fn grade_card(
+    daemon: tauri::State<'_, DaemonLink>,
+    card_id: i64,
+    passed: bool,
+    grade: Option<Grade>,
+    duration_ms: Option<u32>,
+) -> CmdResult<()> {
+    call(&daemon.socket, Request::Grade { card_id, passed, grade, duration_ms }).await?;
     Ok(())
 }
 
+/// Review statistics for the dashboard over the last `days` days.
 #[tauri::command]
-fn list_cards(db: tauri::State<'_, DbPool>) -> Result<Vec<data::CardJson>, String> {
-    fetch_all_cards(&db).map_err(|e| e.to_string())
+async fn get_stats(daemon: tauri::State<'_, DaemonLink>, days: Option<u32>) -> CmdResult<Stats> {
+    match call(&daemon.socket, Request::Stats { days }).await? {
+        Response::Stats { stats } => Ok(stats),
+        other => Err(unexpected(other)),
+    }
 }
 
-// legacy background capture (no longer used)
-#[allow(dead_code)]
-fn spawn_background(_app: &tauri::AppHandle) {
-    // ... existing code ...
-    // Register global shortcut Cmd+Shift+P (less likely to conflict)
-    // app.global_shortcut_manager().register("Cmd+Shift+,", move || {
+/// A card's source screenshot (or its thumbnail) as a `data:` URL. The HTTP
+/// media routes need the API token, which the web view does not hold.
+#[tauri::command]
+async fn load_media(daemon: tauri::State<'_, DaemonLink>, hash: String, thumb: bool) -> CmdResult<String> {
+    match call(&daemon.socket, Request::Media { hash, thumb }).await? {
+        Response::Media { content_type, data } => Ok(format!("data:{content_type};base64,{data}")),
+        other => Err(unexpected(other)),
+    }
 }
 
-fn main() {
-    tracing_subscriber::fmt::init();
+/// Check a typed answer and suggest a grade.
+#[tauri::command]
+async fn check_answer(daemon: tauri::State<'_, DaemonLink>, card_id: i64, answer: String) -> CmdResult<Verdict> {
+    match call(&daemon.socket, Request::CheckAnswer { card_id, answer }).await? {
+        Response::Checked { verdict } => Ok(verdict),
+        other => Err(unexpected(other)),
+    }
+}
 
-    tauri::Builder::default()
-        .setup(|app| {
-            // Initialise database and share via state
-            let db = data::new_pool("oakley.db")?;
-            app.manage(db.clone());
-
-            // Kick off scheduler loop
-            let (rev_tx, _rev_rx) = tokio::sync::mpsc::channel::<ReviewOutcome>(32);
-            tauri::async_runtime::spawn(Scheduler::new(db.clone(), rev_tx.clone()).run());
-
-            // ── Global shortcut Cmd+Shift+<  ──
-            let shortcut_handle = app.handle();
-            let db_clone_capture = db.clone();
-            let db_clone_selection = db.clone();
-
-            // --- Global shortcut for Image Capture (Cmd+Shift+Comma) ---
-            app.global_shortcut_manager().register("Cmd+Shift+Comma", move || {
-                let db = db_clone_capture.clone();
-                let async_handle = shortcut_handle.clone();
-
-                tauri::async_runtime::spawn(async move {
-                    // Capture the screen (blocking call executed inside async task)
-                    match capture::capture_screen() {
-                        Ok(evt) => {
-                            if let Err(e) = process_capture(evt, &db, &async_handle).await {
-                                error!(?e, "Failed to process captured image");
+/// Record a spoken answer and return its transcript (needs `speech.microphone`).
+#[tauri::command]
+async fn transcribe_answer(daemon: tauri::State<'_, DaemonLink>) -> CmdResult<String> {
+    match call(&daemon.socket, Request::Transcribe { wav: None }).await? {
+        Response::Transcript { text } => Ok(text),
+        other => Err(unexpected(other)),
+    }
+}
+
+/// Read text aloud through the daemon's `[tts]` engine; resolves once spoken.
+#[tauri::command]
+async fn speak(daemon: tauri::State<'_, DaemonLink>, text: String) -> CmdResult<()> {
+    call(&daemon.socket, Request::Speak { text }).await?;
+    Ok(())
+}
+
+/// Pairs of cards that look like duplicates, most similar first.
+#[tauri::command]
+async fn dedupe_cards(daemon: tauri::State<'_, DaemonLink>, threshold: Option<f32>) -> CmdResult<Vec<DuplicatePair>> {
+    match call(&daemon.socket, Request::DedupeCards { threshold }).await? {
+        Response::Duplicates { pairs } => Ok(pairs),
+        other => Err(unexpected(other)),
+    }
+}
+
+#[tauri::command]
+async fn merge_cards(daemon: tauri::State<'_, DaemonLink>, keep: i64, duplicate: i64) -> CmdResult<CardJson> {
+    match call(&daemon.socket, Request::MergeCards { keep, duplicate }).await? {
+        Response::Card { card, .. } => Ok(card),
+        other => Err(unexpected(other)),
+    }
+}
+
+#[tauri::command]
+async fn snooze_reminders(daemon: tauri::State<'_, DaemonLink>, minutes: i64) -> CmdResult<()> {
+    call(&daemon.socket, Request::SnoozeReminders { minutes }).await?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_jobs(daemon: tauri::State<'_, DaemonLink>, status: Option<JobStatus>) -> CmdResult<Vec<Job>> {
+    match call(&daemon.socket, Request::ListJobs { status }).await? {
+        Response::Jobs { jobs } => Ok(jobs),
+        other => Err(unexpected(other)),
+    }
+}
+
+#[tauri::command]
+async fn retry_jobs(daemon: tauri::State<'_, DaemonLink>, id: Option<i64>) -> CmdResult<usize> {
+    match call(&daemon.socket, Request::RetryJobs { id }).await? {
+        Response::Retried { count } => Ok(count),
+        other => Err(unexpected(other)),
+    }
+}
+
+/// Settle a screenshot held as a duplicate: keep the earlier card or generate a new one.
+#[tauri::command]
+async fn resolve_duplicate(
+    daemon: tauri::State<'_, DaemonLink>,
+    job_id: i64,
+    reuse: bool,
+) -> CmdResult<Option<CardJson>> {
+    expect_card(call(&daemon.socket, Request::ResolveDuplicate { job_id, reuse }).await?)
+}
+
+/// Attach to a running daemon, or start one inside this process when none is up.
+/// Returns whether the shell should bind the global hot-keys: not when the
+/// daemon already listens for them, or every accelerator would fire twice.
+async fn ensure_daemon(loader: ConfigLoader, socket: &Path) -> anyhow::Result<bool> {
+    if let Ok(pong) = ipc::request(socket, &Request::Ping).await {
+        info!(socket = %socket.display(), "Attached to running Oakley daemon");
+        return Ok(!matches!(pong, Response::Pong { hotkeys: true, .. }));
+    }
+
+    info!("No Oakley daemon running – starting an embedded one");
+    // Global shortcuts are registered by the shell itself.
+    let loader = loader.with_override("hotkeys.enabled", "false");
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = daemon::run(loader).await {
+            error!(?e, "embedded daemon stopped");
+        }
+    });
+
+    for _ in 0..50 {
+        if ipc::request(socket, &Request::Ping).await.is_ok() {
+            return Ok(true);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    anyhow::bail!("embedded daemon did not come up on {}", socket.display())
+}
+
+/// Re-emit daemon events to the front-end, reconnecting if the daemon restarts.
+async fn forward_events(app: tauri::AppHandle, socket: PathBuf) {
+    loop {
+        let sub = match ipc::Client::connect(&socket).await {
+            Ok(client) => client.subscribe().await,
+            Err(e) => Err(e),
+        };
+        match sub {
+            Ok(mut sub) => {
+                while let Ok(Some(evt)) = sub.next().await {
+                    match evt {
+                        Event::Generating => { let _ = app.emit_all("hotkey", ()); }
+                        Event::CardCreated { card, similar, .. } => {
+                            let _ = app.emit_all("card_created", &card);
+                            if !similar.is_empty() {
+                                let payload = serde_json::json!({ "card_id": card.id, "similar": similar });
+                                let _ = app.emit_all("similar_cards", payload);
                             }
                         }
-                        Err(e) => {
-                            error!(?e, "Screen capture error");
+                        Event::CardsDue { summary } => { let _ = app.emit_all("cards_due", &summary); }
+                        Event::ReviewRequested => show_review(&app),
+                        Event::DuplicateCapture { job_id, duplicate_of, card } => {
+                            let payload = serde_json::json!({ "job_id": job_id, "duplicate_of": duplicate_of, "card": card });
+                            let _ = app.emit_all("duplicate_capture", payload);
                         }
+                        Event::JobFailed { job_id, retry_at, error } => {
+                            let payload = serde_json::json!({ "job_id": job_id, "retry_at": retry_at, "error": error });
+                            let _ = app.emit_all("job_failed", payload);
+                        }
+                        Event::Error { error } => { let _ = app.emit_all("error", &error); }
                     }
-                });
-            })?;
-
-            // --- Global shortcut for Text Selection (Cmd+Shift+Period) ---
-            // Using Cmd+Shift+. as Cmd+Shift+< might be awkward / require alias
-            let shortcut_handle_sel = app.handle();
-            app.global_shortcut_manager().register("Cmd+Shift+.", move || {
-                info!("Text selection shortcut triggered");
-                let db_sel = db_clone_selection.clone();
-                let async_handle_sel = shortcut_handle_sel.clone();
-                tauri::async_runtime::spawn(async move {
-                    if let Err(e) = create_card_from_selection_impl(async_handle_sel.clone(), &db_sel).await {
-                        error!(error = %e, "Error processing text selection shortcut");
-                        // Optionally notify the frontend about the overall failure
-                        // async_handle_sel.emit_all("error_message", format!("Failed: {}", e)).ok();
-                    }
-                });
-            })?;
-
-            // --- HTTP JSON endpoint for external browsers ---
-            let db_http = db.clone();
-            tauri::async_runtime::spawn(async move {
-                use warp::Filter;
-                let db_ref = db_http.clone();
-                let get_cards = warp::path("cards").and(warp::get()).and_then(move || {
-                    let db = db_ref.clone();
-                    async move {
-                        let reply = match fetch_all_cards(&db) {
-                            Ok(cards) => warp::reply::json(&cards),
-                            Err(e) => {
-                                error!(?e, "fetch_all_cards failed");
-                                warp::reply::json(&Vec::<data::CardJson>::new())
-                            }
-                        };
-                        Ok::<_, std::convert::Infallible>(reply)
-                    }
-                });
-                // CORS: allow any origin (dev)
-                let routes = get_cards.with(warp::cors().allow_any_origin());
-                warp::serve(routes).run(([127,0,0,1], 3030)).await;
-            });
+                }
+                warn!("daemon event stream ended; reconnecting");
+            }
+            Err(e) => warn!(?e, "cannot subscribe to daemon events"),
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+fn main() {
+    tracing_subscriber::fmt::init();
+
+    tauri::Builder::default()
+        .setup(|app| {
+            let loader = ConfigLoader::new(None);
+            let watcher = loader.clone();
+            let cfg = loader.load()?;
+            let socket = cfg.socket_path.clone();
+            let bind = tauri::async_runtime::block_on(ensure_daemon(loader, &socket))?;
+            app.manage(DaemonLink { socket: socket.clone() });
+
+            tauri::async_runtime::spawn(forward_events(app.handle(), socket.clone()));
+
+            if !bind {
+                info!("The daemon owns the global hot-keys; not registering them here");
+                return Ok(());
+            }
+            // Reject unparsable or clashing [hotkeys] before touching the OS.
+            let bindings = Bindings::from_config(&cfg.hotkeys, Platform::current())?;
+            if let Err(e) = bind_hotkeys(&app.handle(), &bindings, &socket) {
+                warn!(error = %e, "Some global shortcuts could not be registered");
+            }
+            tauri::async_runtime::spawn(rebind_on_change(app.handle(), watcher, cfg, socket));
 
             Ok(())
         })
@@ -207,45 +392,21 @@ fn main() {
             accept_card,
             discard_card,
             create_card_from_selection,
-            list_cards
+            list_cards,
+            list_due_cards,
+            grade_card,
+            get_stats,
+            load_media,
+            check_answer,
+            transcribe_answer,
+            speak,
+            dedupe_cards,
+            merge_cards,
+            snooze_reminders,
+            list_jobs,
+            retry_jobs,
+            resolve_duplicate
         ])
         .run(tauri::generate_context!())
         .expect("error while running Oakley");
 }
-
-async fn process_capture(evt: CaptureEvent, db: &DbPool, app_handle: &tauri::AppHandle) -> Result<()> {
-    // Always build PNG from in-memory image to avoid temp-file lifetime issues.
-    let mut png_bytes = Vec::new();
-    image::codecs::png::PngEncoder::new(&mut png_bytes)
-        .encode(
-            &evt.image,
-            evt.image.width(),
-            evt.image.height(),
-            image::ColorType::Rgba8,
-        )?;
-
-    info!(size = png_bytes.len(), "📸 Screenshot bytes prepared");
-
-    let fields = gen_card_from_image(&png_bytes).await?;
-
-    let db_card = data::CardJson {
-        id: 0,
-        front: fields.front.clone(),
-        back: fields.back.clone(),
-        tags: fields.tags.clone(),
-    };
-
-    let id = insert_card(db, &db_card, evt.path.as_deref())?;
-    info!(id, "🧠 Card saved");
-
-    let card_json = CardJson {
-        id,
-        front: fields.front,
-        back: fields.back,
-        tags: fields.tags,
-    };
-
-    let _ = app_handle.emit_all("card_created", &card_json);
-
-    Ok(())
-} 
\ No newline at end of file
@@ -1,8 +1,11 @@
 import React, { useEffect, useState } from 'react';
 import { listen } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/tauri';
-import CardPreview from './components/CardPreview';
+import CardPreview, { Similar } from './components/CardPreview';
 import CardList from './components/CardList';
+import ReviewSession from './components/ReviewSession';
+import StatsPanel from './components/StatsPanel';
+import ErrorToast, { ErrorPayload, toPayload } from './components/ErrorToast';
 
 interface CardJson {
   id: number;
@@ -11,11 +14,34 @@ interface CardJson {
   tags: string[];
 }
 
+interface JobFailed {
+  job_id: number;
+  retry_at: string | null;
+  error: ErrorPayload;
+}
+
+interface DuplicateCapture {
+  job_id: number;
+  duplicate_of: number;
+  card: CardJson | null;
+}
+
+interface DueSummary {
+  due: number;
+  at: string;
+}
+
 export default function App() {
   const [loading, setLoading] = useState(false);
   const [card, setCard] = useState<CardJson | null>(null);
+  const [similar, setSimilar] = useState<{ card_id: number; similar: Similar[] } | null>(null);
   const [visible, setVisible] = useState(false);
   const [cards, setCards] = useState<CardJson[]>([]);
+  const [due, setDue] = useState<DueSummary | null>(null);
+  const [duplicate, setDuplicate] = useState<DuplicateCapture | null>(null);
+  const [reviewing, setReviewing] = useState(false);
+  const [showStats, setShowStats] = useState(false);
+  const [failure, setFailure] = useState<{ error: ErrorPayload; retry?: () => void } | null>(null);
 
   useEffect(() => {
     const tauri = (window as any).__TAURI_IPC__;
@@ -58,6 +84,62 @@ export default function App() {
           refresh();
         })
       );
+
+      // Follows card_created when the new card looks like existing ones
+      unlisten.push(
+        await listen<{ card_id: number; similar: Similar[] }>('similar_cards', (event) => {
+          setSimilar(event.payload);
+        })
+      );
+
+      // Scheduler reminder – offer to jump straight into a review session
+      unlisten.push(
+        await listen<DueSummary>('cards_due', (event) => {
+          setDue(event.payload);
+        })
+      );
+
+      // Screenshot looks like a recent one – held until the user picks
+      unlisten.push(
+        await listen<DuplicateCapture>('duplicate_capture', (event) => {
+          setLoading(false);
+          setVisible(false);
+          setDuplicate(event.payload);
+        })
+      );
+
+      // Failures from hot-key captures have no caller – surface them here
+      unlisten.push(
+        await listen<ErrorPayload>('error', (event) => {
+          setLoading(false);
+          setVisible(false);
+          if (event.payload.code !== 'capture.cancelled') setFailure({ error: event.payload });
+        })
+      );
+
+      // Queued generation failed – either retried automatically or dead-lettered
+      unlisten.push(
+        await listen<JobFailed>('job_failed', (event) => {
+          const { job_id, retry_at, error } = event.payload;
+          setLoading(false);
+          setVisible(false);
+          if (retry_at) {
+            const when = new Date(retry_at).toLocaleTimeString();
+            setFailure({ error: { ...error, message: `${error.message} – capture saved, retrying at ${when}` } });
+          } else {
+            const retry = () => invoke('retry_jobs', { id: job_id }).catch((e) => setFailure({ error: toPayload(e) }));
+            setFailure({ error: { ...error, retryable: true }, retry });
+          }
+        })
+      );
+
+      // Daemon notification action "Review now"
+      unlisten.push(
+        await listen('review_requested', () => {
+          setDue(null);
+          setReviewing(true);
+        })
+      );
     };
 
     setup();
@@ -67,8 +149,99 @@ export default function App() {
     };
   }, []);
 
+  const startReview = () => {
+    setDue(null);
+    setReviewing(true);
+  };
+
+  const snooze = async () => {
+    setDue(null);
+    try {
+      await invoke('snooze_reminders', { minutes: 60 });
+    } catch (e) {
+      console.error('snooze_reminders failed', e);
+      setFailure({ error: toPayload(e), retry: snooze });
+    }
+  };
+
+  const resolveDuplicate = async (reuse: boolean) => {
+    if (!duplicate) return;
+    const { job_id } = duplicate;
+    setDuplicate(null);
+    if (!reuse) {
+      setLoading(true);
+      setVisible(true);
+    }
+    try {
+      const res = (await invoke('resolve_duplicate', { jobId: job_id, reuse })) as CardJson | null;
+      if (res) {
+        setCard(res);
+        setLoading(false);
+        setVisible(true);
+      }
+    } catch (e) {
+      setLoading(false);
+      setVisible(false);
+      setFailure({ error: toPayload(e) });
+    }
+  };
+
   return (
     <>
+      {/* Near-duplicate screenshot – reuse the earlier card or make a new one */}
+      {duplicate && (
+        <div className="fixed top-4 left-1/2 -translate-x-1/2 z-40 flex items-center gap-4 px-5 py-3 rounded-2xl bg-white/90 backdrop-blur-xl shadow border border-white/20 text-sm text-neutral-700">
+          <span>
+            {duplicate.card
+              ? `Looks like card #${duplicate.card.id}: ${duplicate.card.front}`
+              : 'You just captured this – its card is still being made'}
+          </span>
+          <button className="text-neutral-400 hover:text-neutral-900" onClick={() => resolveDuplicate(false)}>
+            Create new card
+          </button>
+          {duplicate.card ? (
+            <button className="px-4 py-1.5 rounded-full bg-neutral-900 text-white/90" onClick={() => resolveDuplicate(true)}>
+              Use existing
+            </button>
+          ) : (
+            // Stays held; `oakley jobs reuse` settles it once the first card exists.
+            <button className="px-4 py-1.5 rounded-full bg-neutral-900 text-white/90" onClick={() => setDuplicate(null)}>
+              Later
+            </button>
+          )}
+        </div>
+      )}
+
+      {/* Due-card reminder banner */}
+      {due && !reviewing && (
+        <div className="fixed top-4 right-4 z-40 flex items-center gap-4 px-5 py-3 rounded-2xl bg-white/90 backdrop-blur-xl shadow border border-white/20 text-sm text-neutral-700">
+          <span>{due.due === 1 ? '1 card is due' : `${due.due} cards are due`}</span>
+          <button className="text-neutral-400 hover:text-neutral-900" onClick={snooze}>
+            Snooze
+          </button>
+          <button className="px-4 py-1.5 rounded-full bg-neutral-900 text-white/90" onClick={startReview}>
+            Review now
+          </button>
+        </div>
+      )}
+
+      {failure && (
+        <ErrorToast
+          error={failure.error}
+          onClose={() => setFailure(null)}
+          onRetry={failure.retry && (() => { setFailure(null); failure.retry?.(); })}
+        />
+      )}
+
+      {/* Review session modal */}
+      {reviewing && (
+        <div className="fixed inset-0 flex items-center justify-center z-50">
+          <div className="w-[460px] p-10 bg-white/90 backdrop-blur-xl rounded-3xl shadow-[0_8px_32px_rgba(0,0,0,0.08)] border border-white/20 animate-fade-in">
+            <ReviewSession onClose={() => setReviewing(false)} />
+          </div>
+        </div>
+      )}
+
       {/* Overlay card modal */}
       {visible && (
         <div className="fixed inset-0 flex items-center justify-center z-50">
@@ -79,7 +252,12 @@ export default function App() {
                 <p className="mt-4 text-sm tracking-wide">Generating card…</p>
               </div>
             ) : card ? (
-              <CardPreview card={card} onClose={() => setVisible(false)} />
+              <CardPreview
+                key={card.id}
+                card={card}
+                similar={similar?.card_id === card.id ? similar.similar : []}
+                onClose={() => setVisible(false)}
+              />
             ) : null}
           </div>
         </div>
@@ -87,9 +265,15 @@ export default function App() {
 
       {/* Card list – always render so localhost shows it. When in Tauri and overlay
           hidden we keep window transparent via CSS (opacity-0) to avoid flashing. */}
-      <div className={`min-h-screen bg-neutral-50/50 ${visible ? 'opacity-30 blur-sm pointer-events-none' : ''}`}>
-        <header className="px-10 py-6 text-xl font-semibold">Oakley</header>
-        <CardList cards={cards} />
+      <div className={`min-h-screen bg-neutral-50/50 ${visible || reviewing ? 'opacity-30 blur-sm pointer-events-none' : ''}`}>
+        <header className="flex items-center justify-between px-10 py-6">
+          <span className="text-xl font-semibold">Oakley</span>
+          <button className="text-sm text-neutral-400 hover:text-neutral-900" onClick={() => setShowStats((s) => !s)}>
+            {showStats ? 'Cards' : 'Statistics'}
+          </button>
+        </header>
+        {/* Remount on reopen so the numbers include reviews just done. */}
+        {showStats ? <StatsPanel /> : <CardList cards={cards} />}
       </div>
     </>
   );
@@ -1,10 +1,23 @@
 import React from 'react';
 
+/** Where a card was captured (`utils::source::Source`). */
+export interface CardSource {
+  app?: string;
+  window_title?: string;
+  url?: string;
+  captured_at: string;
+}
+
 export interface CardJson {
   id: number;
   front: string;
   back: string;
   tags: string[];
+  source?: CardSource;
+  /** Hash of the source screenshot, served at `/media/<hash>`. */
+  media?: string;
+  /** Deck the card is filed into, e.g. `Lectures::Biology`. */
+  deck?: string;
 }
 
 interface Props {
@@ -16,7 +29,10 @@ const CardList: React.FC<Props> = ({ cards }) => {
     <div className="px-10 py-8 grid gap-6 grid-cols-1 md:grid-cols-2 lg:grid-cols-3">
       {cards.map((c) => (
         <div key={c.id} className="p-6 rounded-2xl bg-white/90 backdrop-blur-sm shadow border border-white/30">
-          <h3 className="text-sm tracking-wider text-neutral-400 mb-3">#{c.id}</h3>
+          <h3 className="text-sm tracking-wider text-neutral-400 mb-3">
+            #{c.id}
+            {c.deck && <span className="ml-2 text-neutral-500">{c.deck.split('::').join(' › ')}</span>}
+          </h3>
           <p className="font-semibold text-neutral-800 mb-2 whitespace-pre-wrap break-words">{c.front}</p>
           <p className="text-neutral-600 whitespace-pre-wrap break-words">{c.back}</p>
           {c.tags.length > 0 && (
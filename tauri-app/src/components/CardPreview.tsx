@@ -8,12 +8,19 @@ interface CardJson {
   tags: string[];
 }
 
+export interface Similar {
+  card: CardJson;
+  similarity: number;
+}
+
 interface Props {
   card: CardJson;
+  /** Existing cards this one may duplicate, most similar first. */
+  similar?: Similar[];
   onClose: () => void;
 }
 
-const CardPreview: React.FC<Props> = ({ card, onClose }) => {
+const CardPreview: React.FC<Props> = ({ card, similar = [], onClose }) => {
   const [front, setFront] = useState(card.front);
   const [back, setBack] = useState(card.back);
 
@@ -25,9 +32,30 @@ const CardPreview: React.FC<Props> = ({ card, onClose }) => {
     await invoke('discard_card', { cardId: card.id });
     onClose();
   };
+  // Keep the existing card; the new one's tags and screenshot fold into it.
+  const merge = async (keep: number) => {
+    await invoke('merge_cards', { keep, duplicate: card.id });
+    onClose();
+  };
 
   return (
     <div className="flex flex-col gap-10 text-neutral-800">
+      {/* Possible duplicates */}
+      {similar.length > 0 && (
+        <div className="flex flex-col gap-2 text-sm">
+          {similar.slice(0, 3).map((s) => (
+            <div key={s.card.id} className="flex items-center gap-3 px-4 py-2 rounded-2xl bg-amber-50 text-amber-900">
+              <span className="flex-1 truncate">
+                Looks like #{s.card.id} ({Math.round(s.similarity * 100)}%): {s.card.front}
+              </span>
+              <button className="font-medium hover:underline" onClick={() => merge(s.card.id)}>
+                Merge
+              </button>
+            </div>
+          ))}
+        </div>
+      )}
+
       {/* Front */}
       <div className="flex flex-col gap-4">
         <label htmlFor="front" className="text-[11px] tracking-[0.2em] uppercase text-neutral-400">
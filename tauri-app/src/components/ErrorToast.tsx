@@ -0,0 +1,67 @@
+import React from 'react';
+
+/** Mirrors `utils::error::ErrorPayload` on the Rust side. */
+export interface ErrorPayload {
+  code: string;
+  message: string;
+  retryable?: boolean;
+  retry_after_secs?: number;
+}
+
+/** Normalise whatever `invoke` rejected with into an ErrorPayload. */
+export const toPayload = (e: unknown): ErrorPayload =>
+  typeof e === 'object' && e !== null && 'code' in e
+    ? (e as ErrorPayload)
+    : { code: 'internal', message: String(e) };
+
+const hint = (err: ErrorPayload): string | null => {
+  switch (err.code) {
+    case 'llm.missing_api_key':
+      return 'Set OPENAI_API_KEY in the daemon environment and restart it.';
+    case 'llm.unauthorized':
+      return 'Your OpenAI API key was rejected – check that it is valid.';
+    case 'llm.rate_limited':
+      return err.retry_after_secs
+        ? `Rate limited – try again in ${err.retry_after_secs}s.`
+        : 'Rate limited – try again shortly.';
+    case 'llm.offline':
+      return 'Cannot reach the model provider – check your connection.';
+    case 'llm.timeout':
+      return 'The model provider took too long to answer – try again.';
+    case 'db.locked':
+      return 'The database is busy – retry in a moment.';
+    case 'daemon.unreachable':
+      return 'The Oakley daemon is not running – start it with `oakley daemon`.';
+    case 'capture.cancelled':
+      return null;
+    case 'capture.failed':
+      return 'Screen capture failed – grant screen-recording permission to Oakley.';
+    default:
+      return err.retryable ? 'This is usually temporary – try again.' : null;
+  }
+};
+
+interface Props {
+  error: ErrorPayload;
+  onRetry?: () => void;
+  onClose: () => void;
+}
+
+const ErrorToast: React.FC<Props> = ({ error, onRetry, onClose }) => (
+  <div className="fixed bottom-4 right-4 z-50 max-w-sm px-5 py-4 rounded-2xl bg-white/90 backdrop-blur-xl shadow border border-red-200 text-sm text-neutral-700">
+    <p className="font-medium text-red-600">{error.message}</p>
+    {hint(error) && <p className="mt-1 text-neutral-500">{hint(error)}</p>}
+    <div className="mt-3 flex justify-end gap-3">
+      <button className="text-neutral-400 hover:text-neutral-900" onClick={onClose}>
+        Dismiss
+      </button>
+      {error.retryable && onRetry && (
+        <button className="px-4 py-1.5 rounded-full bg-neutral-900 text-white/90" onClick={onRetry}>
+          Retry
+        </button>
+      )}
+    </div>
+  </div>
+);
+
+export default ErrorToast;
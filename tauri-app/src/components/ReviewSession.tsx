@@ -0,0 +1,306 @@
+import React, { useEffect, useRef, useState } from 'react';
+import { invoke } from '@tauri-apps/api/tauri';
+import { open } from '@tauri-apps/api/shell';
+import type { CardJson, CardSource } from './CardList';
+import { toPayload } from './ErrorToast';
+
+/** "Firefox — Page title · 12 Mar", linking back to the page when known. */
+const SourceLine: React.FC<{ source: CardSource }> = ({ source }) => {
+  const where = [source.app, source.window_title !== source.app ? source.window_title : undefined]
+    .filter(Boolean)
+    .join(' — ');
+  const when = new Date(source.captured_at).toLocaleDateString(undefined, { day: 'numeric', month: 'short' });
+  return (
+    <p className="text-xs text-neutral-400 truncate">
+      {source.url ? (
+        <button className="underline hover:text-neutral-700" title={source.url} onClick={() => open(source.url!)}>
+          {where || source.url}
+        </button>
+      ) : (
+        where
+      )}
+      {where || source.url ? ' · ' : ''}
+      {when}
+    </p>
+  );
+};
+
+type Grade = 'again' | 'hard' | 'good' | 'easy';
+
+/** The daemon's suggested grade for a typed answer. */
+interface Verdict {
+  grade: Grade;
+  score: number;
+  method: 'exact' | 'fuzzy' | 'semantic' | 'judge';
+  explanation: string | null;
+  wrong_number: boolean;
+}
+
+const GRADES: { grade: Grade; label: string }[] = [
+  { grade: 'again', label: 'Again' },
+  { grade: 'hard', label: 'Hard' },
+  { grade: 'good', label: 'Good' },
+  { grade: 'easy', label: 'Easy' },
+];
+
+const VERDICT_TEXT: Record<Grade, string> = {
+  again: 'Not quite',
+  hard: 'Partly right',
+  good: 'Correct',
+  easy: 'Correct',
+};
+
+/** Saying one of these in hands-free mode ends it. */
+const STOP_WORDS = ['stop', 'quit', 'exit'];
+
+interface Props {
+  onClose: () => void;
+}
+
+const ReviewSession: React.FC<Props> = ({ onClose }) => {
+  const [queue, setQueue] = useState<CardJson[] | null>(null);
+  const [index, setIndex] = useState(0);
+  const [revealed, setRevealed] = useState(false);
+  const [answer, setAnswer] = useState('');
+  const [verdict, setVerdict] = useState<Verdict | null>(null);
+  const [listening, setListening] = useState(false);
+  const [speechError, setSpeechError] = useState<string | null>(null);
+  const [handsFree, setHandsFree] = useState(false);
+  // The current card's screenshot as a data URL; full size once clicked.
+  const [shot, setShot] = useState<{ url: string; full: boolean } | null>(null);
+  // When the current card appeared, for the time-spent statistics.
+  const shownAt = useRef(Date.now());
+
+  useEffect(() => {
+    invoke<CardJson[]>('list_due_cards')
+      .then(setQueue)
+      .catch((e) => {
+        console.error('list_due_cards failed', e);
+        setQueue([]);
+      });
+  }, []);
+
+  // Once through the queue, keep checking: cards in learning come back when their step runs out.
+  useEffect(() => {
+    if (!queue || queue.length === 0 || index < queue.length) return;
+    const refill = () =>
+      invoke<CardJson[]>('list_due_cards')
+        .then((cards) => {
+          if (cards.length > 0) {
+            setQueue(cards);
+            setIndex(0);
+          }
+        })
+        .catch((e) => console.error('list_due_cards failed', e));
+    refill();
+    const timer = window.setInterval(refill, 15_000);
+    return () => window.clearInterval(timer);
+  }, [index, queue]);
+
+  useEffect(() => {
+    shownAt.current = Date.now();
+  }, [index, queue]);
+
+  const media = queue?.[index]?.media;
+  useEffect(() => {
+    setShot(null);
+    if (!media) return;
+    let cancelled = false;
+    invoke<string>('load_media', { hash: media, thumb: true })
+      .then((url) => !cancelled && setShot({ url, full: false }))
+      .catch((e) => console.error('load_media failed', e));
+    return () => {
+      cancelled = true;
+    };
+  }, [media]);
+
+  const enlarge = async () => {
+    if (!media || shot?.full) return;
+    try {
+      setShot({ url: await invoke<string>('load_media', { hash: media, thumb: false }), full: true });
+    } catch (e) {
+      console.error('load_media failed', e);
+    }
+  };
+
+  /** Reveal the back, checking the typed answer first if there is one. */
+  const reveal = async () => {
+    const card = queue?.[index];
+    if (!card) return;
+    if (answer.trim()) {
+      try {
+        setVerdict(await invoke<Verdict>('check_answer', { cardId: card.id, answer }));
+      } catch (e) {
+        console.error('check_answer failed', e);
+      }
+    }
+    setRevealed(true);
+  };
+
+  /** Record a spoken answer into the answer box. */
+  const listen = async () => {
+    setListening(true);
+    setSpeechError(null);
+    try {
+      setAnswer(await invoke<string>('transcribe_answer'));
+    } catch (e) {
+      const err = toPayload(e);
+      setSpeechError(err.code === 'speech.no_speech' ? 'Didn’t catch that.' : err.message);
+    } finally {
+      setListening(false);
+    }
+  };
+
+  const grade = async (grade: Grade) => {
+    const card = queue?.[index];
+    if (!card) return;
+    try {
+      const durationMs = Date.now() - shownAt.current;
+      await invoke('grade_card', { cardId: card.id, passed: grade !== 'again', grade, durationMs });
+    } catch (e) {
+      console.error('grade_card failed', e);
+    }
+    setRevealed(false);
+    setAnswer('');
+    setVerdict(null);
+    setSpeechError(null);
+    setIndex((i) => i + 1);
+  };
+
+  // Hands-free: read the front, listen for the answer, read the verdict and
+  // the back, then grade as suggested and move on.
+  useEffect(() => {
+    const card = queue?.[index];
+    if (!handsFree || !card) return;
+    let cancelled = false;
+    (async () => {
+      try {
+        await invoke('speak', { text: card.front });
+        if (cancelled) return;
+        setListening(true);
+        let heard = '';
+        try {
+          heard = await invoke<string>('transcribe_answer');
+        } catch (e) {
+          if (toPayload(e).code !== 'speech.no_speech') throw e;
+        } finally {
+          setListening(false);
+        }
+        if (cancelled) return;
+        if (STOP_WORDS.includes(heard.toLowerCase().replace(/[^a-z ]/g, '').trim())) {
+          setHandsFree(false);
+          return;
+        }
+        setAnswer(heard);
+        const v = await invoke<Verdict>('check_answer', { cardId: card.id, answer: heard });
+        setVerdict(v);
+        setRevealed(true);
+        await invoke('speak', { text: `${VERDICT_TEXT[v.grade]}. The answer is: ${card.back}` });
+        if (!cancelled) await grade(v.grade);
+      } catch (e) {
+        setSpeechError(toPayload(e).message);
+        setHandsFree(false);
+      }
+    })();
+    return () => {
+      cancelled = true;
+    };
+  }, [handsFree, index, queue]);
+
+  if (queue === null) {
+    return <p className="text-sm text-neutral-500">Loading due cards…</p>;
+  }
+
+  const card = queue[index];
+  if (!card) {
+    return (
+      <div className="flex flex-col items-center gap-6 text-neutral-700">
+        <p className="text-lg">{queue.length === 0 ? 'Nothing due right now.' : 'Session finished.'}</p>
+        <button className="px-6 py-2.5 text-sm font-medium rounded-full bg-neutral-900 text-white/90" onClick={onClose}>
+          Done
+        </button>
+      </div>
+    );
+  }
+
+  return (
+    <div className="flex flex-col gap-8 text-neutral-800">
+      <span className="text-[11px] tracking-[0.2em] uppercase text-neutral-400">
+        {index + 1} / {queue.length}
+      </span>
+      <p className="text-lg leading-relaxed whitespace-pre-wrap break-words">{card.front}</p>
+      {revealed ? (
+        <>
+          {verdict && (
+            <p className={`text-sm ${verdict.grade === 'again' ? 'text-red-700' : 'text-neutral-500'}`}>
+              <span className="font-medium">{VERDICT_TEXT[verdict.grade]}</span>
+              {verdict.explanation ? ` — ${verdict.explanation}` : ''}
+            </p>
+          )}
+          <p className="text-neutral-600 whitespace-pre-wrap break-words border-t border-neutral-200 pt-6">{card.back}</p>
+          {shot && (
+            <button className={shot.full ? '' : 'cursor-zoom-in'} onClick={enlarge}>
+              <img
+                className={`${shot.full ? 'max-h-[60vh]' : 'max-h-40'} rounded-md border border-neutral-200`}
+                src={shot.url}
+                alt="Source screenshot"
+              />
+            </button>
+          )}
+          {card.source && <SourceLine source={card.source} />}
+          <div className="flex gap-2 justify-end">
+            {GRADES.map(({ grade: g, label }) => (
+              <button
+                key={g}
+                className={
+                  g === (verdict?.grade ?? 'good')
+                    ? 'px-6 py-2.5 text-sm font-medium rounded-full bg-neutral-900 text-white/90'
+                    : 'px-5 py-2.5 text-sm font-medium text-neutral-400 hover:text-neutral-900'
+                }
+                onClick={() => grade(g)}
+              >
+                {label}
+              </button>
+            ))}
+          </div>
+        </>
+      ) : (
+        <>
+          <input
+            className="w-full border-b border-neutral-200 bg-transparent py-2 text-neutral-700 outline-none focus:border-neutral-500"
+            placeholder="Type your answer (optional)"
+            value={answer}
+            autoFocus
+            onChange={(e) => setAnswer(e.target.value)}
+            onKeyDown={(e) => e.key === 'Enter' && reveal()}
+          />
+          {speechError && <p className="text-xs text-red-700">{speechError}</p>}
+          <div className="flex gap-4 justify-end">
+            <button
+              className={`px-5 py-2.5 text-sm font-medium hover:text-neutral-900 ${handsFree ? 'text-neutral-900' : 'text-neutral-400'}`}
+              title="Read cards aloud and answer by voice"
+              onClick={() => setHandsFree((on) => !on)}
+            >
+              {handsFree ? 'Hands-free on' : 'Hands-free'}
+            </button>
+            <button
+              className="px-5 py-2.5 text-sm font-medium text-neutral-400 hover:text-neutral-900 disabled:opacity-50"
+              disabled={listening || handsFree}
+              onClick={listen}
+            >
+              {listening ? 'Listening…' : 'Speak'}
+            </button>
+            <button className="px-5 py-2.5 text-sm font-medium text-neutral-400 hover:text-neutral-900" onClick={onClose}>
+              Stop
+            </button>
+            <button className="px-6 py-2.5 text-sm font-medium rounded-full bg-neutral-900 text-white/90" onClick={reveal}>
+              {answer.trim() ? 'Check' : 'Show answer'}
+            </button>
+          </div>
+        </>
+      )}
+    </div>
+  );
+};
+
+export default ReviewSession;
@@ -0,0 +1,179 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/tauri';
+import { ErrorPayload, toPayload } from './ErrorToast';
+
+/** `data::stats::Stats` as served by `get_stats` and `GET /stats`. */
+export interface Stats {
+  days: number;
+  reviews: { date: string; reviews: number; passed: number; secs: number }[];
+  retention: { label: string; min_days: number; max_days: number | null; reviews: number; passed: number }[];
+  streak: { current: number; longest: number; reviewed_today: boolean };
+  time: { total_secs: number; today_secs: number; secs_per_review: number | null };
+  maturity: { new: number; young: number; mature: number };
+  forecast: { date: string; due: number }[];
+  decks: Accuracy[];
+  tags: Accuracy[];
+}
+
+interface Accuracy {
+  name: string;
+  reviews: number;
+  passed: number;
+}
+
+const RANGES = [7, 30, 90, 365];
+
+const percent = (part: number, whole: number) => (whole ? `${Math.round((part * 100) / whole)}%` : '–');
+
+const duration = (secs: number) =>
+  secs < 60 ? `${secs}s` : secs < 3600 ? `${Math.floor(secs / 60)}m` : `${Math.floor(secs / 3600)}h ${Math.floor((secs % 3600) / 60)}m`;
+
+const day = (date: string) => new Date(`${date}T00:00`).toLocaleDateString(undefined, { day: 'numeric', month: 'short' });
+
+/** Vertical bars, one per value, scaled to the largest. */
+const Bars: React.FC<{ values: { label: string; value: number; title: string }[] }> = ({ values }) => {
+  const max = Math.max(1, ...values.map((v) => v.value));
+  return (
+    <div className="flex items-end gap-px h-24">
+      {values.map((v) => (
+        <div
+          key={v.label}
+          className="flex-1 bg-neutral-800/80 rounded-t-sm min-h-[1px]"
+          style={{ height: `${(v.value / max) * 100}%` }}
+          title={v.title}
+        />
+      ))}
+    </div>
+  );
+};
+
+const Tile: React.FC<{ label: string; value: React.ReactNode; hint?: string }> = ({ label, value, hint }) => (
+  <div className="p-5 rounded-2xl bg-white/90 shadow border border-white/30">
+    <p className="text-[11px] tracking-[0.2em] uppercase text-neutral-400">{label}</p>
+    <p className="mt-2 text-2xl font-semibold text-neutral-800">{value}</p>
+    {hint && <p className="mt-1 text-xs text-neutral-400">{hint}</p>}
+  </div>
+);
+
+const AccuracyList: React.FC<{ title: string; list: Accuracy[] }> = ({ title, list }) =>
+  list.length === 0 ? null : (
+    <section>
+      <h3 className="text-sm font-medium text-neutral-500 mb-3">{title}</h3>
+      <ul className="space-y-2">
+        {list.slice(0, 8).map((a) => (
+          <li key={a.name} className="flex items-center gap-3 text-sm text-neutral-700">
+            <span className="w-40 truncate" title={a.name}>
+              {a.name.split('::').join(' › ')}
+            </span>
+            <span className="flex-1 h-1.5 rounded-full bg-neutral-200">
+              <span className="block h-full rounded-full bg-neutral-800" style={{ width: percent(a.passed, a.reviews) }} />
+            </span>
+            <span className="w-12 text-right">{percent(a.passed, a.reviews)}</span>
+            <span className="w-10 text-right text-neutral-400">{a.reviews}</span>
+          </li>
+        ))}
+      </ul>
+    </section>
+  );
+
+/** Review dashboard: activity, retention curve, streak, maturity and load. */
+const StatsPanel: React.FC = () => {
+  const [days, setDays] = useState(30);
+  const [stats, setStats] = useState<Stats | null>(null);
+  const [error, setError] = useState<ErrorPayload | null>(null);
+
+  useEffect(() => {
+    const load = async () => {
+      try {
+        // The HTTP route needs the API token, so statistics come over IPC only.
+        setStats(await invoke<Stats>('get_stats', { days }));
+        setError(null);
+      } catch (e) {
+        setError(toPayload(e));
+      }
+    };
+    load();
+  }, [days]);
+
+  if (error) return <p className="px-10 py-8 text-sm text-red-700">{error.message}</p>;
+  if (!stats) return <p className="px-10 py-8 text-sm text-neutral-500">Loading statistics…</p>;
+
+  const total = stats.reviews.reduce((n, d) => n + d.reviews, 0);
+  const passed = stats.reviews.reduce((n, d) => n + d.passed, 0);
+  const today = stats.reviews[stats.reviews.length - 1];
+  const { current, longest, reviewed_today } = stats.streak;
+
+  return (
+    <div className="px-10 py-8 space-y-10">
+      <div className="flex gap-2">
+        {RANGES.map((r) => (
+          <button
+            key={r}
+            className={`px-4 py-1.5 rounded-full text-sm ${r === days ? 'bg-neutral-900 text-white/90' : 'text-neutral-400 hover:text-neutral-900'}`}
+            onClick={() => setDays(r)}
+          >
+            {r === 365 ? '1 year' : `${r} days`}
+          </button>
+        ))}
+      </div>
+
+      <div className="grid gap-4 grid-cols-2 lg:grid-cols-4">
+        <Tile
+          label="Streak"
+          value={`${current} ${current === 1 ? 'day' : 'days'}`}
+          hint={current > 0 && !reviewed_today ? 'Review today to keep it' : `Longest ${longest}`}
+        />
+        <Tile label="Today" value={today?.reviews ?? 0} hint={duration(stats.time.today_secs)} />
+        <Tile label="Reviews" value={total} hint={`${percent(passed, total)} passed · ${duration(stats.time.total_secs)}`} />
+        <Tile
+          label="Cards"
+          value={stats.maturity.new + stats.maturity.young + stats.maturity.mature}
+          hint={`${stats.maturity.new} new · ${stats.maturity.young} young · ${stats.maturity.mature} mature`}
+        />
+      </div>
+
+      <section>
+        <h3 className="text-sm font-medium text-neutral-500 mb-3">Reviews per day</h3>
+        <Bars
+          values={stats.reviews.map((d) => ({
+            label: d.date,
+            value: d.reviews,
+            title: `${day(d.date)}: ${d.reviews} reviews, ${percent(d.passed, d.reviews)} passed`,
+          }))}
+        />
+      </section>
+
+      <section>
+        <h3 className="text-sm font-medium text-neutral-500 mb-3">Retention by time since last review</h3>
+        <div className="grid grid-cols-5 gap-4">
+          {stats.retention.map((b) => (
+            <div key={b.label} className="flex flex-col items-center gap-2">
+              <div className="w-full h-24 flex items-end rounded-md bg-neutral-100">
+                <div
+                  className="w-full rounded-md bg-neutral-800/80"
+                  style={{ height: b.reviews ? `${(b.passed / b.reviews) * 100}%` : 0 }}
+                />
+              </div>
+              <span className="text-sm text-neutral-800">{percent(b.passed, b.reviews)}</span>
+              <span className="text-xs text-neutral-400 text-center">
+                {b.label} · {b.reviews}
+              </span>
+            </div>
+          ))}
+        </div>
+      </section>
+
+      <section>
+        <h3 className="text-sm font-medium text-neutral-500 mb-3">Due in the next {stats.forecast.length} days</h3>
+        <Bars values={stats.forecast.map((d) => ({ label: d.date, value: d.due, title: `${day(d.date)}: ${d.due} due` }))} />
+      </section>
+
+      <div className="grid gap-10 md:grid-cols-2">
+        <AccuracyList title="Decks" list={stats.decks} />
+        <AccuracyList title="Tags" list={stats.tags} />
+      </div>
+    </div>
+  );
+};
+
+export default StatsPanel;